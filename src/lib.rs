@@ -0,0 +1,4 @@
+pub mod font;
+pub mod plugins;
+pub mod state;
+pub mod storage;
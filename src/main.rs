@@ -1,26 +1,75 @@
-mod font;
-mod plugins;
-mod state;
-
 use bevy::prelude::*;
-use bevy::window::WindowResolution;
-use font::setup_font;
-use state::GameState;
-
-fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                resolution: WindowResolution::new(800, 800),
-                ..default()
-            }),
-            ..default()
-        }))
-        .init_state::<GameState>()
-        .add_systems(Startup, setup_font)
-        .add_plugins(plugins::title::TitlePlugin)
-        .add_plugins(plugins::game::GamePlugin)
-        .add_plugins(plugins::gameover::GameOverPlugin)
-        .add_plugins(plugins::gameclear::GameClearPlugin)
-        .run();
+use space_battle::font::setup_font;
+use space_battle::plugins;
+use space_battle::plugins::settings::Settings;
+use space_battle::state::GameState;
+
+fn main() -> AppExit {
+    // --headless ならウィンドウを開かずにボットでシミュレーションを行う
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--headless") {
+        return match plugins::simulation::SimulationOptions::from_args(&args) {
+            Ok(options) => plugins::simulation::run(options),
+            Err(err) => {
+                eprintln!("{err}");
+                AppExit::error()
+            }
+        };
+    }
+
+    // ウィンドウの大きさなどを決めるため、プラグインを組む前に設定を読み込む
+    let (settings, settings_warnings) = Settings::load();
+
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(settings.window()),
+        ..default()
+    }))
+    .insert_resource(settings)
+    .init_state::<GameState>()
+    .add_systems(Startup, setup_font)
+    .add_plugins(plugins::title::TitlePlugin)
+    .add_plugins(plugins::game::GamePlugin)
+    .add_plugins(plugins::game::SpriteAnimationPlugin)
+    .insert_resource(plugins::game::GameRng::new(seed_arg()))
+    .add_plugins(plugins::gameover::GameOverPlugin)
+    .add_plugins(plugins::gameclear::GameClearPlugin)
+    .add_plugins(plugins::highscore::HighScorePlugin)
+    .add_plugins(plugins::campaign::CampaignPlugin)
+    .insert_resource(plugins::input::InputBindings::load())
+    .add_plugins(plugins::input::InputActionPlugin)
+    .add_plugins(plugins::controls::ControlsPlugin)
+    .add_plugins(plugins::audio::SoundPlugin)
+    .add_plugins(plugins::settings::SettingsPlugin)
+    .add_plugins(plugins::locale::LocalePlugin)
+    .add_plugins(plugins::replay::ReplayPlugin);
+
+    // 設定の読み込みはログの出力が始まる前なので、LogPlugin を組み込んだ後で警告を出す
+    for warning in settings_warnings {
+        warn!("{warning}");
+    }
+
+    // リプレイファイルが指定されていれば、起動してすぐに再生する
+    if let Some(path) = arg_value("--replay") {
+        plugins::replay::watch_file(&mut app, path.into());
+    }
+
+    app.run()
+}
+
+/// コマンドライン引数の `<name> <値>` の値
+fn arg_value(name: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != name).nth(1)
+}
+
+/// コマンドライン引数の `--seed <数値>`（指定するとそのシードで遊ぶ。同じ操作なら同じ展開になる）
+fn seed_arg() -> Option<u64> {
+    let value = arg_value("--seed")?;
+    match value.parse() {
+        Ok(seed) => Some(seed),
+        Err(err) => {
+            eprintln!("invalid --seed value {value}: {err}");
+            None
+        }
+    }
 }
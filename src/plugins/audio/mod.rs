@@ -0,0 +1,395 @@
+/// 音声（効果音とBGM）
+///
+/// 効果音は `assets/audio/sfx/`、BGM は `assets/audio/music/` の ogg ファイルを使う。
+/// 効果音のファイルがない・読み込めないときは正弦波の短い音で代用し、BGM のファイルがなければ無音にする。
+/// ない音声ファイルは読み込もうとしないので、ファイルを用意していなくてもエラーは出ない。
+/// 音声デバイスがない環境では再生されないだけで、ゲームはそのまま動く
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::audio::{AudioSinkPlayback, Pitch, PlaybackMode, Volume};
+use bevy::prelude::*;
+
+use crate::plugins::game::PauseState;
+use crate::state::GameState;
+
+/// BGM の切り替えにかける秒数
+const CROSSFADE_SECS: f32 = 1.0;
+/// ポーズ中の BGM の音量の倍率
+const PAUSE_DUCK: f32 = 0.3;
+/// 同時に鳴らす効果音の上限
+const MAX_SFX_VOICES: usize = 12;
+/// 効果音のエンティティを残しておく最大の秒数（再生されなかった場合の後始末用）
+const SFX_MAX_SECS: f32 = 3.0;
+/// アセットのフォルダ（AssetPlugin の既定のフォルダ）
+const ASSET_DIR: &str = "assets";
+
+/// 音声のプラグイン
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<PlaySfx>();
+        app.init_resource::<AudioVolumes>();
+        app.add_systems(Startup, load_audio);
+        app.add_systems(
+            Update,
+            (play_sfx, expire_sfx_voices, switch_music, fade_music).chain(),
+        );
+    }
+}
+
+/// 効果音の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    /// 弾の発射
+    Fire,
+    /// 敵やボスに弾が当たった
+    Hit,
+    /// 敵やボスを倒した
+    Explosion,
+    /// 敵を取り逃がした
+    EnemyEscape,
+    /// プレイヤーがダメージを受けた
+    Damage,
+    /// アイテムを拾った
+    PowerUp,
+}
+
+impl Sfx {
+    /// すべての効果音
+    const ALL: [Sfx; 6] = [
+        Sfx::Fire,
+        Sfx::Hit,
+        Sfx::Explosion,
+        Sfx::EnemyEscape,
+        Sfx::Damage,
+        Sfx::PowerUp,
+    ];
+
+    /// 効果音ファイルのパス（assets/ からの相対パス）
+    const fn path(self) -> &'static str {
+        match self {
+            Self::Fire => "audio/sfx/fire.ogg",
+            Self::Hit => "audio/sfx/hit.ogg",
+            Self::Explosion => "audio/sfx/explosion.ogg",
+            Self::EnemyEscape => "audio/sfx/escape.ogg",
+            Self::Damage => "audio/sfx/damage.ogg",
+            Self::PowerUp => "audio/sfx/powerup.ogg",
+        }
+    }
+
+    /// ファイルがないときに代わりに鳴らす音（周波数 Hz、秒数）
+    const fn fallback_tone(self) -> (f32, f32) {
+        match self {
+            Self::Fire => (880.0, 0.05),
+            Self::Hit => (440.0, 0.04),
+            Self::Explosion => (110.0, 0.2),
+            Self::EnemyEscape => (220.0, 0.25),
+            Self::Damage => (150.0, 0.3),
+            Self::PowerUp => (1320.0, 0.15),
+        }
+    }
+
+    /// 効果音ごとの音量の倍率（よく鳴る音は小さめにする）
+    const fn gain(self) -> f32 {
+        match self {
+            Self::Fire | Self::Hit => 0.3,
+            Self::Explosion | Self::PowerUp => 0.6,
+            Self::EnemyEscape | Self::Damage => 0.8,
+        }
+    }
+}
+
+/// 効果音を鳴らすメッセージ
+///
+/// ゲームの処理はこのメッセージを送るだけで、音声の有無や音量は気にしない
+#[derive(Message, Clone, Copy, Debug)]
+pub struct PlaySfx(pub Sfx);
+
+/// BGM の曲
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum MusicTrack {
+    Title,
+    Game,
+    GameOver,
+    GameClear,
+}
+
+impl MusicTrack {
+    /// すべての曲
+    const ALL: [MusicTrack; 4] = [
+        MusicTrack::Title,
+        MusicTrack::Game,
+        MusicTrack::GameOver,
+        MusicTrack::GameClear,
+    ];
+
+    /// BGM ファイルのパス（assets/ からの相対パス）
+    const fn path(self) -> &'static str {
+        match self {
+            Self::Title => "audio/music/title.ogg",
+            Self::Game => "audio/music/game.ogg",
+            Self::GameOver => "audio/music/gameover.ogg",
+            Self::GameClear => "audio/music/clear.ogg",
+        }
+    }
+
+    /// その画面で流す曲（ステージ間の画面はゲーム中の曲を流し続ける）
+    const fn for_state(state: GameState) -> Self {
+        match state {
+            GameState::Title => Self::Title,
+            GameState::StageIntro | GameState::Game | GameState::StageResult => Self::Game,
+            GameState::GameOver => Self::GameOver,
+            GameState::GameClear => Self::GameClear,
+        }
+    }
+}
+
+/// 音量の設定（それぞれ 0.0 ~ 1.0）
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct AudioVolumes {
+    /// 全体の音量
+    pub master: f32,
+    /// BGM の音量
+    pub music: f32,
+    /// 効果音の音量
+    pub sfx: f32,
+}
+
+impl Default for AudioVolumes {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            music: 0.6,
+            sfx: 0.8,
+        }
+    }
+}
+
+impl AudioVolumes {
+    /// BGM の最終的な音量
+    fn music_volume(&self) -> f32 {
+        (self.master * self.music).clamp(0.0, 1.0)
+    }
+
+    /// 効果音の最終的な音量
+    fn sfx_volume(&self, sfx: Sfx) -> f32 {
+        (self.master * self.sfx * sfx.gain()).clamp(0.0, 1.0)
+    }
+}
+
+/// 1つの効果音の音源
+struct SfxSource {
+    /// 効果音ファイル（ファイルがなければ None）
+    file: Option<Handle<AudioSource>>,
+    /// ファイルが読み込めないときの代わりの音
+    tone: Handle<Pitch>,
+}
+
+/// 読み込んだ音声を保持するリソース
+#[derive(Resource)]
+struct AudioHandles {
+    sfx: HashMap<Sfx, SfxSource>,
+    music: HashMap<MusicTrack, Handle<AudioSource>>,
+}
+
+/// 再生中の効果音のコンポーネント（再生されずに残ったものを消すためのタイマー）
+#[derive(Component)]
+struct SfxVoice(Timer);
+
+/// 再生中の BGM のコンポーネント
+#[derive(Component)]
+struct Music {
+    /// 曲
+    track: MusicTrack,
+    /// フェードの進み具合（0.0 ~ 1.0）
+    level: f32,
+    /// フェードの目標（流す曲は 1.0、消える曲は 0.0）
+    target: f32,
+}
+
+/// アセットのフォルダに path のファイルがあるかどうか
+///
+/// ないファイルを読み込むと起動のたびにエラーが出るため、読み込む前に確かめる
+fn asset_file_exists(path: &str) -> bool {
+    let exists = FileAssetReader::get_base_path()
+        .join(ASSET_DIR)
+        .join(path)
+        .is_file();
+    if !exists {
+        debug!("audio file {path} not found, using fallback");
+    }
+    exists
+}
+
+/// 効果音と BGM を読み込む
+///
+/// ファイルがないものは読み込まず、効果音は代わりの音、BGM は無音にする
+fn load_audio(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut pitches: ResMut<Assets<Pitch>>,
+) {
+    let sfx = Sfx::ALL
+        .into_iter()
+        .map(|sfx| {
+            let (frequency, secs) = sfx.fallback_tone();
+            let source = SfxSource {
+                file: asset_file_exists(sfx.path()).then(|| asset_server.load(sfx.path())),
+                tone: pitches.add(Pitch::new(frequency, Duration::from_secs_f32(secs))),
+            };
+            (sfx, source)
+        })
+        .collect();
+    let music = MusicTrack::ALL
+        .into_iter()
+        .filter(|track| asset_file_exists(track.path()))
+        .map(|track| (track, asset_server.load(track.path())))
+        .collect();
+
+    commands.insert_resource(AudioHandles { sfx, music });
+}
+
+/// 効果音のメッセージを受け取って鳴らすシステム
+///
+/// 同じフレームに同じ効果音が何度届いても1回だけ鳴らし、同時に鳴らす数にも上限を設ける
+fn play_sfx(
+    mut commands: Commands,
+    mut messages: MessageReader<PlaySfx>,
+    handles: Option<Res<AudioHandles>>,
+    asset_server: Res<AssetServer>,
+    volumes: Res<AudioVolumes>,
+    voice_query: Query<(), With<SfxVoice>>,
+) {
+    let Some(handles) = handles else {
+        messages.clear();
+        return;
+    };
+
+    let mut played: Vec<Sfx> = Vec::new();
+    let mut voices = voice_query.iter().count();
+    for PlaySfx(sfx) in messages.read() {
+        if played.contains(sfx) || voices >= MAX_SFX_VOICES {
+            continue;
+        }
+        let Some(source) = handles.sfx.get(sfx) else {
+            continue;
+        };
+
+        let settings = PlaybackSettings {
+            mode: PlaybackMode::Despawn,
+            volume: Volume::Linear(volumes.sfx_volume(*sfx)),
+            ..PlaybackSettings::ONCE
+        };
+        let voice = SfxVoice(Timer::from_seconds(SFX_MAX_SECS, TimerMode::Once));
+        match &source.file {
+            Some(file) if asset_server.is_loaded(file) => {
+                commands.spawn((AudioPlayer(file.clone()), settings, voice));
+            }
+            // 読み込み中は鳴らさない
+            Some(file) if !asset_server.load_state(file).is_failed() => continue,
+            _ => {
+                commands.spawn((AudioPlayer(source.tone.clone()), settings, voice));
+            }
+        }
+        played.push(*sfx);
+        voices += 1;
+    }
+}
+
+/// 再生されないまま残った効果音を消すシステム
+///
+/// 音声デバイスがないと再生が始まらず、再生後の自動削除も行われないため。
+/// 再生が始まった（AudioSink が付いた）効果音は再生後に自動で削除されるので、ここでは消さない
+fn expire_sfx_voices(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut SfxVoice), Without<AudioSink>>,
+) {
+    for (entity, mut voice) in &mut query {
+        if voice.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).try_despawn();
+        }
+    }
+}
+
+/// 画面が切り替わったら BGM を切り替えるシステム
+///
+/// 流れている曲をフェードアウトさせ、次の曲をフェードインで始める
+fn switch_music(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    handles: Option<Res<AudioHandles>>,
+    mut music_query: Query<&mut Music>,
+) {
+    let Some(handles) = handles else {
+        return;
+    };
+    if !state.is_changed() && !handles.is_added() {
+        return;
+    }
+
+    let track = MusicTrack::for_state(*state.get());
+    let mut playing = false;
+    for mut music in &mut music_query {
+        if music.track == track && music.target > 0.0 {
+            playing = true;
+        } else {
+            music.target = 0.0;
+        }
+    }
+    if playing {
+        return;
+    }
+
+    if let Some(handle) = handles.music.get(&track) {
+        commands.spawn((
+            AudioPlayer(handle.clone()),
+            PlaybackSettings {
+                volume: Volume::Linear(0.0),
+                ..PlaybackSettings::LOOP
+            },
+            Music {
+                track,
+                level: 0.0,
+                target: 1.0,
+            },
+        ));
+    }
+}
+
+/// BGM のフェードを進め、音量の設定とポーズ中の音量の抑えを反映するシステム
+///
+/// フェードアウトし終わった曲は削除する
+fn fade_music(
+    mut commands: Commands,
+    time: Res<Time>,
+    volumes: Res<AudioVolumes>,
+    pause: Option<Res<State<PauseState>>>,
+    mut query: Query<(Entity, &mut Music, Option<&mut AudioSink>)>,
+) {
+    let step = time.delta_secs() / CROSSFADE_SECS;
+    let duck = if pause.is_some_and(|pause| *pause.get() == PauseState::Paused) {
+        PAUSE_DUCK
+    } else {
+        1.0
+    };
+
+    for (entity, mut music, sink) in &mut query {
+        music.level = if music.level < music.target {
+            (music.level + step).min(music.target)
+        } else {
+            (music.level - step).max(music.target)
+        };
+
+        if music.target == 0.0 && music.level == 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        if let Some(mut sink) = sink {
+            sink.set_volume(Volume::Linear(volumes.music_volume() * music.level * duck));
+        }
+    }
+}
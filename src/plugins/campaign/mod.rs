@@ -0,0 +1,313 @@
+/// キャンペーン（複数ステージの進行とステージ間の画面）
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::font::DefaultFont;
+use crate::plugins::game::{Score, result_breakdown};
+use crate::plugins::input::{Action, ActionState};
+use crate::plugins::locale::{LocalizedText, Strings};
+use crate::plugins::replay::ReplayPlayback;
+use crate::state::GameState;
+use crate::storage;
+
+/// キャンペーンを構成するステージ定義ファイル（assets/ からの相対パス、遊ぶ順）
+pub const CAMPAIGN_STAGES: [&str; 3] = [
+    "stages/stage1.stage.ron",
+    "stages/stage2.stage.ron",
+    "stages/stage3.stage.ron",
+];
+/// 進行状況ファイルの形式バージョン
+const PROGRESS_FILE_VERSION: u32 = 1;
+/// 進行状況ファイル名
+const PROGRESS_FILE_NAME: &str = "campaign.ron";
+/// ステージ開始前の画面を表示する秒数
+const STAGE_INTRO_SECS: f32 = 2.0;
+
+/// キャンペーンのプラグイン
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        // タイトル画面の OnEnter は Startup より先に走るため、ビルド時に読み込んでおく
+        app.insert_resource(Campaign::load());
+        app.add_systems(
+            OnEnter(GameState::StageIntro),
+            (setup_camera, setup_intro_ui, reset_intro_timer),
+        );
+        app.add_systems(
+            OnEnter(GameState::StageResult),
+            (
+                setup_camera,
+                setup_result_ui,
+                // リプレイの再生では進行状況を記録しない
+                record_stage_clear.run_if(not(resource_exists::<ReplayPlayback>)),
+            ),
+        );
+        app.add_systems(
+            Update,
+            stage_intro_update.run_if(in_state(GameState::StageIntro)),
+        );
+        app.add_systems(
+            Update,
+            stage_result_update.run_if(in_state(GameState::StageResult)),
+        );
+    }
+}
+
+/// 進行状況ファイルの中身
+#[derive(Serialize, Deserialize)]
+struct ProgressFile {
+    /// 形式バージョン
+    version: u32,
+    /// 遊べるステージの数（1以上）
+    #[serde(default = "default_unlocked")]
+    unlocked: usize,
+}
+
+fn default_unlocked() -> usize {
+    1
+}
+
+/// キャンペーンの進行状況を保持するリソース
+#[derive(Resource)]
+pub struct Campaign {
+    /// 現在のステージ（CAMPAIGN_STAGES の添字）
+    pub current: usize,
+    /// 遊べるステージの数（クリアするごとに増える）
+    unlocked: usize,
+    /// 保存先（保存できない環境では None）
+    path: Option<PathBuf>,
+}
+
+impl Default for Campaign {
+    /// 保存しない進行状況（最初のステージだけ遊べる状態。ヘッドレスのシミュレーション用）
+    fn default() -> Self {
+        Self {
+            current: 0,
+            unlocked: 1,
+            path: None,
+        }
+    }
+}
+
+impl Campaign {
+    /// 遊べるステージの数
+    pub fn unlocked(&self) -> usize {
+        self.unlocked
+    }
+
+    /// 現在のステージが最後のステージかどうか
+    pub fn is_final_stage(&self) -> bool {
+        self.current + 1 >= CAMPAIGN_STAGES.len()
+    }
+
+    /// 進行状況ファイルを読み込む（読み込めなければ最初のステージだけ遊べる状態）
+    fn load() -> Self {
+        let path = storage::data_dir().map(|dir| dir.join(PROGRESS_FILE_NAME));
+        let mut unlocked = default_unlocked();
+
+        if let Some(path) = &path {
+            match storage::read_file(path) {
+                Ok(Some(contents)) => match ron::from_str::<ProgressFile>(&contents) {
+                    Ok(file) => {
+                        if file.version > PROGRESS_FILE_VERSION {
+                            warn!(
+                                "campaign file version {} is newer than supported version {}",
+                                file.version, PROGRESS_FILE_VERSION
+                            );
+                        }
+                        unlocked = file.unlocked;
+                    }
+                    Err(err) => warn!("campaign file {} is corrupted: {}", path.display(), err),
+                },
+                Ok(None) => {}
+                Err(err) => warn!("failed to read campaign from {}: {}", path.display(), err),
+            }
+        }
+
+        Self {
+            current: 0,
+            unlocked: unlocked.clamp(1, CAMPAIGN_STAGES.len()),
+            path,
+        }
+    }
+
+    /// 進行状況をファイルに保存する
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let file = ProgressFile {
+            version: PROGRESS_FILE_VERSION,
+            unlocked: self.unlocked,
+        };
+        let result = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                storage::write_file_atomic(path, &contents).map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            warn!("failed to save campaign to {}: {}", path.display(), err);
+        }
+    }
+}
+
+/// ステージ開始前の画面の表示時間を管理するリソース
+#[derive(Resource)]
+struct StageIntroTimer(Timer);
+
+/// カメラのセットアップ（StageIntro・StageResult 共通）
+fn setup_camera(mut commands: Commands, state: Res<State<GameState>>) {
+    commands.spawn((Camera2d, DespawnOnExit(*state.get())));
+}
+
+/// ステージ開始前の画面の表示時間をリセットするシステム
+fn reset_intro_timer(mut commands: Commands) {
+    commands.insert_resource(StageIntroTimer(Timer::from_seconds(
+        STAGE_INTRO_SECS,
+        TimerMode::Once,
+    )));
+}
+
+/// ステージ開始前の画面のUIセットアップ
+fn setup_intro_ui(mut commands: Commands, asset: Res<DefaultFont>, campaign: Res<Campaign>) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            DespawnOnExit(GameState::StageIntro),
+        ))
+        .with_children(|parent| {
+            // ステージ番号
+            parent.spawn((
+                LocalizedText::new("campaign.stage").with_arg("stage", campaign.current + 1),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 80.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(40.0)),
+                    ..default()
+                },
+            ));
+
+            // 説明テキスト
+            parent.spawn((
+                LocalizedText::new("campaign.ready"),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 40.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            ));
+        });
+}
+
+/// ステージ開始前の画面の更新処理（一定時間経過か決定でゲームへ）
+fn stage_intro_update(
+    time: Res<Time>,
+    actions: Res<ActionState>,
+    mut timer: ResMut<StageIntroTimer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if timer.0.tick(time.delta()).just_finished() || actions.any_just_pressed(Action::Confirm) {
+        next_state.set(GameState::Game);
+    }
+}
+
+/// ステージクリア時に次のステージを解放して保存するシステム
+fn record_stage_clear(mut campaign: ResMut<Campaign>) {
+    let next = (campaign.current + 2).min(CAMPAIGN_STAGES.len());
+    if next > campaign.unlocked {
+        campaign.unlocked = next;
+        campaign.save();
+    }
+}
+
+/// ステージクリア後の結果画面のUIセットアップ
+fn setup_result_ui(
+    mut commands: Commands,
+    asset: Res<DefaultFont>,
+    campaign: Res<Campaign>,
+    score: Res<Score>,
+    strings: Res<Strings>,
+) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            DespawnOnExit(GameState::StageResult),
+        ))
+        .with_children(|parent| {
+            // ステージクリアテキスト
+            parent.spawn((
+                LocalizedText::new("campaign.stage_clear").with_arg("stage", campaign.current + 1),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 72.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.2, 1.0, 0.4)),
+                Node {
+                    margin: UiRect::bottom(Val::Px(40.0)),
+                    ..default()
+                },
+            ));
+
+            // ここまでのスコア内訳
+            parent.spawn((
+                Text::new(result_breakdown(&score, &strings)),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                TextLayout::new_with_justify(Justify::Center),
+                Node {
+                    margin: UiRect::bottom(Val::Px(40.0)),
+                    ..default()
+                },
+            ));
+
+            // 説明テキスト
+            parent.spawn((
+                LocalizedText::new("campaign.next"),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 40.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+/// ステージクリア後の結果画面の更新処理（決定で次のステージへ）
+fn stage_result_update(
+    actions: Res<ActionState>,
+    mut campaign: ResMut<Campaign>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if actions.any_just_pressed(Action::Confirm) {
+        campaign.current = (campaign.current + 1).min(CAMPAIGN_STAGES.len() - 1);
+        next_state.set(GameState::StageIntro);
+    }
+}
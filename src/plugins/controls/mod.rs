@@ -0,0 +1,461 @@
+/// 操作設定画面（タイトル画面とポーズ画面から開き、操作の割り当てを変更する）
+use bevy::prelude::*;
+
+use crate::font::DefaultFont;
+use crate::plugins::game::MAX_PLAYERS;
+use crate::plugins::input::{
+    Action, ActionState, BoundInput, InputBindings, button_name, gamepads_in_order, key_name,
+};
+use crate::plugins::locale::{LocalizedText, Strings};
+
+/// 選択中の行の背景色
+const SELECTED_ROW_COLOR: Color = Color::srgba(0.4, 0.8, 1.0, 0.3);
+/// 衝突している割り当ての文字色
+const CONFLICT_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+/// 入力待ちを取り消すキー（選択中の操作の既定の割り当てなら、取り消さずに割り当てる）
+const CANCEL_KEY: KeyCode = KeyCode::Escape;
+/// 入力待ちを取り消すゲームパッドのボタン（選択中の操作の既定の割り当てなら、取り消さずに割り当てる）
+const CANCEL_BUTTON: GamepadButton = GamepadButton::Start;
+
+/// 操作設定画面のプラグイン
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<ControlsMenuState>();
+        app.add_systems(
+            OnEnter(ControlsMenuState::Open),
+            (reset_controls_menu, setup_ui).chain(),
+        );
+        app.add_systems(OnExit(ControlsMenuState::Open), save_bindings);
+        app.add_systems(
+            Update,
+            (
+                controls_update,
+                (update_controls_ui, update_controls_labels),
+            )
+                .chain()
+                .run_if(in_state(ControlsMenuState::Open)),
+        );
+    }
+}
+
+/// 操作設定画面の開閉状態
+///
+/// ゲーム画面の上にも重ねて開けるよう、GameState とは独立した状態にする
+#[derive(States, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+pub enum ControlsMenuState {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// 操作設定画面の状態を保持するリソース
+#[derive(Resource, Default)]
+struct ControlsMenu {
+    /// 設定中のプレイヤー
+    player: usize,
+    /// 選択中の操作（Action::ALL の添字）
+    cursor: usize,
+    /// 新しいキー・ボタンの入力を待っているかどうか
+    waiting: bool,
+    /// 直前の操作の結果（入れ替えの通知など）
+    message: Option<ControlsMessage>,
+}
+
+/// 操作設定画面での操作の結果の通知
+#[derive(Clone, Debug)]
+enum ControlsMessage {
+    /// 他の操作の割り当てと入れ替えた（入れ替えたプレイヤーと操作）
+    Swapped(Vec<(usize, Action)>),
+    /// プレイヤーの割り当てを初期設定に戻した
+    Reset { player: usize },
+}
+
+/// 操作の行のコンポーネント（Action::ALL の添字）
+#[derive(Component)]
+struct ControlsRow(usize);
+
+/// キーの割り当ての表示のコンポーネント（Action::ALL の添字）
+#[derive(Component)]
+struct KeyCell(usize);
+
+/// ボタンの割り当ての表示のコンポーネント（Action::ALL の添字）
+#[derive(Component)]
+struct ButtonCell(usize);
+
+/// 設定中のプレイヤーの表示のマーカーコンポーネント
+#[derive(Component)]
+struct ControlsPlayerText;
+
+/// 操作説明・結果の表示のマーカーコンポーネント
+#[derive(Component)]
+struct ControlsStatusText;
+
+/// 画面を開くたびに選択位置を先頭に戻す
+fn reset_controls_menu(mut commands: Commands) {
+    commands.insert_resource(ControlsMenu::default());
+}
+
+/// 操作設定画面を閉じるときに割り当てを保存する
+fn save_bindings(bindings: Res<InputBindings>) {
+    bindings.save();
+}
+
+/// 操作設定画面の操作処理
+///
+/// 入力待ちの間は、次に押されたキーかボタンを選択中の操作に割り当てる。
+/// ボタンは設定中のプレイヤーのゲームパッドのものだけを受け付け、取り消しのキー・ボタン（cancel_inputs）が押されたら割り当てずに終える。
+/// 入力待ちを始めた決定操作を拾わないよう、入力待ちはこのシステムの中だけで切り替える
+fn controls_update(
+    actions: Res<ActionState>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepad_query: Query<(Entity, &Gamepad)>,
+    mut menu: ResMut<ControlsMenu>,
+    mut bindings: ResMut<InputBindings>,
+    mut next_state: ResMut<NextState<ControlsMenuState>>,
+) {
+    let action = Action::ALL[menu.cursor];
+
+    if menu.waiting {
+        let gamepad = gamepads_in_order(&gamepad_query).get(menu.player).copied();
+        let cancelled = cancel_inputs(menu.player, action)
+            .into_iter()
+            .any(|input| match input {
+                BoundInput::Key(key) => keyboard_input.just_pressed(key),
+                BoundInput::Button(button) => {
+                    gamepad.is_some_and(|gamepad| gamepad.just_pressed(button))
+                }
+            });
+        if cancelled {
+            menu.waiting = false;
+            return;
+        }
+
+        // 割り当てられるキー・ボタンだけを受け付ける
+        let key = keyboard_input
+            .get_just_pressed()
+            .find(|key| key_name(**key).is_some())
+            .map(|key| BoundInput::Key(*key));
+        let button = gamepad
+            .into_iter()
+            .flat_map(|gamepad| gamepad.get_just_pressed())
+            .find(|button| button_name(**button).is_some())
+            .map(|button| BoundInput::Button(*button));
+        let Some(input) = key.or(button) else {
+            return;
+        };
+
+        let player = menu.player;
+        menu.waiting = false;
+        let swapped = bindings.assign(player, action, input);
+        menu.message = (!swapped.is_empty()).then_some(ControlsMessage::Swapped(swapped));
+        return;
+    }
+
+    if actions.any_just_pressed(Action::Pause) {
+        next_state.set(ControlsMenuState::Closed);
+        return;
+    }
+
+    if actions.any_just_pressed(Action::MenuUp) {
+        menu.cursor = (menu.cursor + Action::ALL.len() - 1) % Action::ALL.len();
+        menu.message = None;
+    }
+    if actions.any_just_pressed(Action::MenuDown) {
+        menu.cursor = (menu.cursor + 1) % Action::ALL.len();
+        menu.message = None;
+    }
+    if actions.any_just_pressed(Action::MenuLeft) {
+        menu.player = (menu.player + MAX_PLAYERS - 1) % MAX_PLAYERS;
+        menu.message = None;
+    }
+    if actions.any_just_pressed(Action::MenuRight) {
+        menu.player = (menu.player + 1) % MAX_PLAYERS;
+        menu.message = None;
+    }
+    if actions.any_just_pressed(Action::Retry) {
+        let player = menu.player;
+        bindings.reset(player);
+        menu.message = Some(ControlsMessage::Reset { player });
+    }
+    if actions.any_just_pressed(Action::Confirm) {
+        menu.waiting = true;
+        menu.message = None;
+    }
+}
+
+/// 操作説明・結果の表示文字列
+fn status_label(menu: &ControlsMenu, bindings: &InputBindings, strings: &Strings) -> String {
+    if menu.waiting {
+        let action = Action::ALL[menu.cursor];
+        let cancel = cancel_inputs(menu.player, action)
+            .into_iter()
+            .map(|input| input_label(Some(input)))
+            .collect::<Vec<_>>()
+            .join("/");
+        let action = strings.get(action.label_key());
+        if cancel.is_empty() {
+            return strings.format("controls.waiting_no_cancel", &[("action", action)]);
+        }
+        return strings.format(
+            "controls.waiting",
+            &[("action", action), ("cancel", cancel)],
+        );
+    }
+    match &menu.message {
+        Some(ControlsMessage::Swapped(swapped)) => {
+            let targets = swapped
+                .iter()
+                .map(|&(player, action)| {
+                    let player =
+                        strings.format("hud.player", &[("player", (player + 1).to_string())]);
+                    format!("{player} {}", strings.get(action.label_key()))
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            strings.format("controls.swapped", &[("targets", targets)])
+        }
+        Some(ControlsMessage::Reset { player }) => {
+            strings.format("controls.reset", &[("player", (*player + 1).to_string())])
+        }
+        None => {
+            let label = |action| menu_input_label(bindings, action).to_string();
+            strings.format(
+                "controls.help",
+                &[
+                    ("up", label(Action::MenuUp)),
+                    ("down", label(Action::MenuDown)),
+                    ("left", label(Action::MenuLeft)),
+                    ("right", label(Action::MenuRight)),
+                    ("confirm", label(Action::Confirm)),
+                    ("reset", label(Action::Retry)),
+                    ("back", label(Action::Pause)),
+                ],
+            )
+        }
+    }
+}
+
+/// メニューの操作の割り当ての表示文字列
+///
+/// メニューはどのプレイヤーの割り当てでも操作できるため、1P から順にキー、なければボタンを探す
+fn menu_input_label(bindings: &InputBindings, action: Action) -> &'static str {
+    let bound = |player| bindings.binding(player, action);
+    let key = (0..MAX_PLAYERS).find_map(|player| bound(player).key.map(BoundInput::Key));
+    let button = (0..MAX_PLAYERS).find_map(|player| bound(player).button.map(BoundInput::Button));
+    input_label(key.or(button))
+}
+
+/// 割り当ての表示文字列（割り当てなしは "-"）
+fn input_label(input: Option<BoundInput>) -> &'static str {
+    match input {
+        Some(BoundInput::Key(key)) => key_name(key),
+        Some(BoundInput::Button(button)) => button_name(button),
+        None => None,
+    }
+    .unwrap_or("-")
+}
+
+/// player 番目のプレイヤーの action の入力待ちを取り消す入力
+///
+/// 取り消しのキー・ボタンがその操作の既定の割り当て（1P のポーズの Esc など）なら、
+/// 既定に戻せるよう取り消しには使わない
+fn cancel_inputs(player: usize, action: Action) -> Vec<BoundInput> {
+    let default = InputBindings::default_binding(player, action);
+    let mut inputs = Vec::new();
+    if default.key != Some(CANCEL_KEY) {
+        inputs.push(BoundInput::Key(CANCEL_KEY));
+    }
+    if default.button != Some(CANCEL_BUTTON) {
+        inputs.push(BoundInput::Button(CANCEL_BUTTON));
+    }
+    inputs
+}
+
+/// 操作設定画面の表示を更新するシステム
+fn update_controls_ui(
+    menu: Res<ControlsMenu>,
+    bindings: Res<InputBindings>,
+    mut row_query: Query<(&ControlsRow, &mut BackgroundColor)>,
+    mut key_query: Query<(&KeyCell, &mut Text, &mut TextColor), Without<ButtonCell>>,
+    mut button_query: Query<(&ButtonCell, &mut Text, &mut TextColor), Without<KeyCell>>,
+) {
+    if !menu.is_changed() && !bindings.is_changed() {
+        return;
+    }
+
+    let player = menu.player;
+    for (row, mut background) in &mut row_query {
+        background.0 = if row.0 == menu.cursor {
+            SELECTED_ROW_COLOR
+        } else {
+            Color::NONE
+        };
+    }
+    for (cell, mut text, mut color) in &mut key_query {
+        let action = Action::ALL[cell.0];
+        let input = bindings.binding(player, action).key.map(BoundInput::Key);
+        text.0 = input_label(input).to_string();
+        color.0 = cell_color(bindings.has_conflict(player, action, input));
+    }
+    for (cell, mut text, mut color) in &mut button_query {
+        let action = Action::ALL[cell.0];
+        let input = bindings
+            .binding(player, action)
+            .button
+            .map(BoundInput::Button);
+        text.0 = input_label(input).to_string();
+        color.0 = cell_color(bindings.has_conflict(player, action, input));
+    }
+}
+
+/// 設定中のプレイヤーと操作説明の表示を更新するシステム
+fn update_controls_labels(
+    menu: Res<ControlsMenu>,
+    bindings: Res<InputBindings>,
+    strings: Res<Strings>,
+    mut player_query: Query<&mut Text, (With<ControlsPlayerText>, Without<ControlsStatusText>)>,
+    mut status_query: Query<&mut Text, (With<ControlsStatusText>, Without<ControlsPlayerText>)>,
+) {
+    if !menu.is_changed() && !bindings.is_changed() && !strings.is_changed() {
+        return;
+    }
+    for mut text in &mut player_query {
+        text.0 = format!("< {}P >", menu.player + 1);
+    }
+    for mut text in &mut status_query {
+        text.0 = status_label(&menu, &bindings, &strings);
+    }
+}
+
+/// 割り当ての文字色（衝突していれば赤）
+fn cell_color(conflict: bool) -> Color {
+    if conflict {
+        CONFLICT_COLOR
+    } else {
+        Color::WHITE
+    }
+}
+
+/// UIのセットアップ（タイトル画面やポーズ画面の上に重ねて表示する）
+fn setup_ui(mut commands: Commands, asset: Res<DefaultFont>) {
+    let text_font = |size: f32| TextFont {
+        font: asset.font.clone(),
+        font_size: size,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.9)),
+            ZIndex(200),
+            DespawnOnExit(ControlsMenuState::Open),
+        ))
+        .with_children(|parent| {
+            // 見出し
+            parent.spawn((
+                LocalizedText::new("controls.title"),
+                text_font(48.0),
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(8.0)),
+                    ..default()
+                },
+            ));
+
+            // 設定中のプレイヤー
+            parent.spawn((
+                Text::new("< 1P >"),
+                text_font(28.0),
+                TextColor(Color::srgb(0.4, 0.8, 1.0)),
+                Node {
+                    margin: UiRect::bottom(Val::Px(12.0)),
+                    ..default()
+                },
+                ControlsPlayerText,
+            ));
+
+            // 操作ごとの割り当て（名前・キー・ボタン）
+            for (index, action) in Action::ALL.into_iter().enumerate() {
+                parent
+                    .spawn((
+                        Node {
+                            flex_direction: FlexDirection::Row,
+                            padding: UiRect::axes(Val::Px(12.0), Val::Px(2.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::NONE),
+                        ControlsRow(index),
+                    ))
+                    .with_children(|row| {
+                        row.spawn((
+                            LocalizedText::new(action.label_key()),
+                            text_font(22.0),
+                            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                            Node {
+                                width: Val::Px(220.0),
+                                ..default()
+                            },
+                        ));
+                        row.spawn((
+                            Text::new(""),
+                            text_font(22.0),
+                            TextColor(Color::WHITE),
+                            Node {
+                                width: Val::Px(140.0),
+                                ..default()
+                            },
+                            KeyCell(index),
+                        ));
+                        row.spawn((
+                            Text::new(""),
+                            text_font(22.0),
+                            TextColor(Color::WHITE),
+                            Node {
+                                width: Val::Px(140.0),
+                                ..default()
+                            },
+                            ButtonCell(index),
+                        ));
+                    });
+            }
+
+            // 操作説明・結果
+            parent.spawn((
+                Text::new(""),
+                text_font(20.0),
+                TextColor(Color::srgb(1.0, 0.85, 0.0)),
+                Node {
+                    margin: UiRect::top(Val::Px(16.0)),
+                    ..default()
+                },
+                ControlsStatusText,
+            ));
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_input_of_action_is_not_used_to_cancel() {
+        let key = BoundInput::Key(CANCEL_KEY);
+        let button = BoundInput::Button(CANCEL_BUTTON);
+        assert_eq!(cancel_inputs(0, Action::Fire), vec![key, button]);
+        // 1P のポーズは Esc と Start が既定なので、どちらも割り当てられる
+        assert_eq!(cancel_inputs(0, Action::Pause), Vec::new());
+        // 2P のポーズはキーの既定がない
+        assert_eq!(cancel_inputs(1, Action::Pause), vec![key]);
+    }
+}
@@ -0,0 +1,572 @@
+/// スプライトの絵とアニメーション（プレイヤー・弾・敵・爆発）
+///
+/// 絵の画像とアニメーションのコマ割りはアセットファイル（*.sprites.ron）で定義する。
+/// 定義ファイルや画像が読み込めないとき（描画のないヘッドレスを含む）は、これまでどおり色付きの四角形で表示する
+use std::fmt;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::image::{ImageLoaderSettings, ImageSampler};
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::boss::BOSS_SIZE;
+use super::{BossDefeated, BulletHit, EnemyKilled, GameEventSystems, PauseState, Player};
+use crate::plugins::input::FixedActionState;
+use crate::state::GameState;
+
+/// スプライトの定義ファイルのパス
+const SPRITE_SHEET_PATH: &str = "sprites/default.sprites.ron";
+/// スプライトの定義ファイルの拡張子
+const SPRITE_SHEET_EXTENSION: &str = "sprites.ron";
+/// コマ割りのラベル（定義ファイルの中で作るアセットの名前）
+const LAYOUT_LABEL: &str = "layout";
+/// 左右に傾いた絵にする、左右の入力の大きさ
+const BANK_THRESHOLD: f32 = 0.3;
+/// 爆発の大きさ（倒した敵のサイズに対する倍率）
+const EXPLOSION_SCALE: f32 = 1.5;
+/// 爆発の描画の奥行き（敵や弾より手前、パーティクルより奥）
+const EXPLOSION_Z: f32 = 0.4;
+
+/// スプライトの絵とアニメーションのプラグイン
+///
+/// 画像を扱うため、描画のあるアプリにだけ組み込む
+pub struct SpriteAnimationPlugin;
+
+impl Plugin for SpriteAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<SpriteSheet>();
+        app.init_asset_loader::<SpriteSheetLoader>();
+        app.add_systems(Startup, load_sprite_sheet);
+        app.add_systems(
+            FixedUpdate,
+            (flash_hit_enemies, spawn_explosions)
+                .in_set(GameEventSystems)
+                .run_if(in_state(PauseState::Running).and(sprite_art_ready)),
+        );
+        app.add_systems(
+            Update,
+            (bank_players, animate_sprites)
+                .chain()
+                .run_if(in_state(PauseState::Running).and(sprite_art_ready)),
+        );
+    }
+}
+
+/// アニメーションの種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(super) enum AnimationClip {
+    /// プレイヤーの通常の絵
+    PlayerIdle,
+    /// 左に移動中のプレイヤー
+    PlayerBankLeft,
+    /// 右に移動中のプレイヤー
+    PlayerBankRight,
+    /// プレイヤーの弾
+    Bullet,
+    /// 敵の通常の絵
+    EnemyIdle,
+    /// 弾が当たった敵（終わったら通常の絵に戻る）
+    EnemyHit,
+    /// 爆発（終わったら削除する）
+    Explosion,
+}
+
+impl AnimationClip {
+    /// 繰り返さないアニメーションが終わった後に続けるアニメーション
+    fn next(self) -> Option<Self> {
+        match self {
+            Self::EnemyHit => Some(Self::EnemyIdle),
+            _ => None,
+        }
+    }
+}
+
+/// アニメーション1種類の定義
+#[derive(Deserialize, Clone, Debug)]
+pub struct AnimationDefinition {
+    /// 表示するコマの番号（画像の左上から右へ、行の順に数える）
+    pub frames: Vec<usize>,
+    /// 1秒あたりのコマ数
+    pub fps: f32,
+    /// true なら最後のコマの後に最初のコマへ戻る
+    #[serde(default)]
+    pub looping: bool,
+}
+
+impl AnimationDefinition {
+    /// 定義の妥当性を確認し、問題があれば errors に追加する
+    fn validate(&self, field: &str, frame_count: usize, errors: &mut Vec<String>) {
+        if self.frames.is_empty() {
+            errors.push(format!("{field}.frames must not be empty"));
+        }
+        if let Some(frame) = self.frames.iter().find(|&&frame| frame >= frame_count) {
+            errors.push(format!(
+                "{field}.frames contains {frame} but the sheet has only {frame_count} frames"
+            ));
+        }
+        if !(self.fps > 0.0 && self.fps.is_finite()) {
+            errors.push(format!("{field}.fps must be positive"));
+        }
+    }
+
+    /// 再生を始めてから elapsed 秒後のコマと、再生が終わったかどうか
+    fn frame_at(&self, elapsed: f32) -> (usize, bool) {
+        let step = (elapsed * self.fps) as usize;
+        let last = self.frames.len() - 1;
+        if self.looping {
+            (self.frames[step % self.frames.len()], false)
+        } else {
+            (self.frames[step.min(last)], step > last)
+        }
+    }
+}
+
+/// アニメーションの定義一式
+#[derive(Deserialize, Clone, Debug)]
+pub struct SpriteAnimations {
+    /// プレイヤーの通常の絵
+    pub player_idle: AnimationDefinition,
+    /// 左に移動中のプレイヤー
+    pub player_bank_left: AnimationDefinition,
+    /// 右に移動中のプレイヤー
+    pub player_bank_right: AnimationDefinition,
+    /// プレイヤーの弾
+    pub bullet: AnimationDefinition,
+    /// 敵の通常の絵
+    pub enemy_idle: AnimationDefinition,
+    /// 弾が当たった敵
+    pub enemy_hit: AnimationDefinition,
+    /// 爆発
+    pub explosion: AnimationDefinition,
+}
+
+impl SpriteAnimations {
+    /// clip の定義
+    fn get(&self, clip: AnimationClip) -> &AnimationDefinition {
+        match clip {
+            AnimationClip::PlayerIdle => &self.player_idle,
+            AnimationClip::PlayerBankLeft => &self.player_bank_left,
+            AnimationClip::PlayerBankRight => &self.player_bank_right,
+            AnimationClip::Bullet => &self.bullet,
+            AnimationClip::EnemyIdle => &self.enemy_idle,
+            AnimationClip::EnemyHit => &self.enemy_hit,
+            AnimationClip::Explosion => &self.explosion,
+        }
+    }
+}
+
+/// スプライトの定義ファイルの内容
+#[derive(Deserialize, Clone, Debug)]
+pub struct SpriteSheetDefinition {
+    /// 画像のパス（assets からの相対パス）
+    pub image: String,
+    /// 1コマの大きさ（ピクセル）
+    pub tile_size: (u32, u32),
+    /// 横に並んだコマの数
+    pub columns: u32,
+    /// 縦に並んだコマの数
+    pub rows: u32,
+    /// アニメーションの定義
+    pub animations: SpriteAnimations,
+}
+
+impl SpriteSheetDefinition {
+    /// 定義の妥当性を確認し、問題の一覧を返す
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.image.is_empty() {
+            errors.push("image must not be empty".to_string());
+        }
+        if self.tile_size.0 == 0 || self.tile_size.1 == 0 {
+            errors.push("tile_size must be positive".to_string());
+        }
+        if self.columns == 0 || self.rows == 0 {
+            errors.push("columns and rows must be positive".to_string());
+        }
+
+        let frame_count = (self.columns * self.rows) as usize;
+        let animations = &self.animations;
+        for (field, animation) in [
+            ("player_idle", &animations.player_idle),
+            ("player_bank_left", &animations.player_bank_left),
+            ("player_bank_right", &animations.player_bank_right),
+            ("bullet", &animations.bullet),
+            ("enemy_idle", &animations.enemy_idle),
+            ("enemy_hit", &animations.enemy_hit),
+            ("explosion", &animations.explosion),
+        ] {
+            animation.validate(field, frame_count, &mut errors);
+        }
+        errors
+    }
+}
+
+/// 読み込んだスプライトの絵とアニメーション
+#[derive(Asset, TypePath, Debug)]
+pub struct SpriteSheet {
+    /// 絵の画像
+    #[dependency]
+    pub image: Handle<Image>,
+    /// 画像のコマ割り
+    #[dependency]
+    pub layout: Handle<TextureAtlasLayout>,
+    /// アニメーションの定義
+    pub animations: SpriteAnimations,
+}
+
+/// スプライトの定義ファイルの読み込みエラー
+#[derive(Debug)]
+pub enum SpriteSheetLoadError {
+    /// ファイルを読めなかった
+    Io(std::io::Error),
+    /// RON として解釈できなかった
+    Parse(ron::error::SpannedError),
+    /// 内容に問題があった
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for SpriteSheetLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read sprite sheet file: {err}"),
+            Self::Parse(err) => write!(f, "failed to parse sprite sheet file: {err}"),
+            Self::Invalid(errors) => {
+                write!(f, "invalid sprite sheet definition: {}", errors.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for SpriteSheetLoadError {}
+
+/// スプライトの定義ファイル（*.sprites.ron）のローダー
+///
+/// 画像は定義ファイルとは別に読み込む。画像がなくても定義ファイルの読み込みは失敗しない
+#[derive(Default, TypePath)]
+pub struct SpriteSheetLoader;
+
+impl AssetLoader for SpriteSheetLoader {
+    type Asset = SpriteSheet;
+    type Settings = ();
+    type Error = SpriteSheetLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(SpriteSheetLoadError::Io)?;
+        let definition: SpriteSheetDefinition =
+            ron::de::from_bytes(&bytes).map_err(SpriteSheetLoadError::Parse)?;
+
+        let errors = definition.validate();
+        if !errors.is_empty() {
+            return Err(SpriteSheetLoadError::Invalid(errors));
+        }
+
+        // ドット絵がぼやけないよう、拡大しても補間しない
+        let image = load_context
+            .loader()
+            .with_settings(|settings: &mut ImageLoaderSettings| {
+                settings.sampler = ImageSampler::nearest();
+            })
+            .load(definition.image);
+        let layout = TextureAtlasLayout::from_grid(
+            UVec2::new(definition.tile_size.0, definition.tile_size.1),
+            definition.columns,
+            definition.rows,
+            None,
+            None,
+        );
+        let layout = load_context.add_labeled_asset(LAYOUT_LABEL.to_string(), layout);
+
+        Ok(SpriteSheet {
+            image,
+            layout,
+            animations: definition.animations,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[SPRITE_SHEET_EXTENSION]
+    }
+}
+
+/// スプライトの定義のハンドルを保持するリソース
+#[derive(Resource)]
+struct SpriteSheetHandle(Handle<SpriteSheet>);
+
+/// 起動時にスプライトの定義の読み込みを始めるシステム
+fn load_sprite_sheet(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(SpriteSheetHandle(asset_server.load(SPRITE_SHEET_PATH)));
+}
+
+/// スプライトの定義と画像の読み込みが終わっているかどうか（終わるまでは四角形で表示する）
+fn sprite_art_ready(
+    handle: Option<Res<SpriteSheetHandle>>,
+    sheets: Res<Assets<SpriteSheet>>,
+    images: Res<Assets<Image>>,
+) -> bool {
+    handle
+        .and_then(|handle| sheets.get(&handle.0))
+        .is_some_and(|sheet| images.contains(&sheet.image))
+}
+
+/// 読み込んだ絵でアニメーションさせるスプライトのコンポーネント
+///
+/// 絵が使えないときは何もせず、スプライトは色付きの四角形のまま表示される
+#[derive(Component)]
+pub(super) struct SpriteAnimation {
+    /// 再生中のアニメーション
+    clip: AnimationClip,
+    /// 再生を始めてからの経過時間（秒）
+    elapsed: f32,
+    /// 繰り返さないアニメーションの再生が終わったかどうか
+    finished: bool,
+}
+
+impl SpriteAnimation {
+    /// clip を最初から再生する
+    pub(super) fn new(clip: AnimationClip) -> Self {
+        Self {
+            clip,
+            elapsed: 0.0,
+            finished: false,
+        }
+    }
+
+    /// clip に切り替える（同じアニメーションを再生中なら続ける）
+    fn play(&mut self, clip: AnimationClip) {
+        if self.clip != clip {
+            *self = Self::new(clip);
+        }
+    }
+
+    /// clip を最初から再生し直す
+    fn restart(&mut self, clip: AnimationClip) {
+        *self = Self::new(clip);
+    }
+}
+
+/// 再生が終わったら削除する爆発のコンポーネント
+#[derive(Component)]
+struct Explosion;
+
+/// 弾が当たった敵に、当たったときのアニメーションを再生させるシステム
+fn flash_hit_enemies(
+    mut bullet_hits: MessageReader<BulletHit>,
+    mut query: Query<&mut SpriteAnimation>,
+) {
+    for hit in bullet_hits.read() {
+        // ボスのようにアニメーションしないものには何もしない
+        if let Ok(mut animation) = query.get_mut(hit.target) {
+            animation.restart(AnimationClip::EnemyHit);
+        }
+    }
+}
+
+/// 敵やボスを倒した位置に爆発を出すシステム
+fn spawn_explosions(
+    mut commands: Commands,
+    mut killed: MessageReader<EnemyKilled>,
+    mut boss_defeated: MessageReader<BossDefeated>,
+) {
+    let explosions = killed.read().map(|kill| (kill.position, kill.size)).chain(
+        boss_defeated
+            .read()
+            .map(|defeated| (defeated.position, BOSS_SIZE)),
+    );
+
+    for (position, size) in explosions {
+        let side = size.max_element() * EXPLOSION_SCALE;
+        commands.spawn((
+            Sprite::from_color(Color::WHITE, Vec2::splat(side)),
+            Transform::from_translation(position.extend(EXPLOSION_Z)),
+            SpriteAnimation::new(AnimationClip::Explosion),
+            Explosion,
+            DespawnOnExit(GameState::Game),
+        ));
+    }
+}
+
+/// 左右の移動に合わせてプレイヤーの絵を傾けるシステム
+fn bank_players(actions: Res<FixedActionState>, mut query: Query<(&Player, &mut SpriteAnimation)>) {
+    for (player, mut animation) in &mut query {
+        let x = actions.movement(player.index).x;
+        animation.play(if x < -BANK_THRESHOLD {
+            AnimationClip::PlayerBankLeft
+        } else if x > BANK_THRESHOLD {
+            AnimationClip::PlayerBankRight
+        } else {
+            AnimationClip::PlayerIdle
+        });
+    }
+}
+
+/// アニメーションを進め、読み込んだ絵のコマをスプライトに反映するシステム
+///
+/// スプライトの大きさと色はそのまま使うので、当たり判定と見た目の大きさは変わらず、絵は元の色で染まる
+fn animate_sprites(
+    mut commands: Commands,
+    time: Res<Time>,
+    handle: Res<SpriteSheetHandle>,
+    sheets: Res<Assets<SpriteSheet>>,
+    mut query: Query<(Entity, &mut SpriteAnimation, &mut Sprite, Has<Explosion>)>,
+) {
+    let Some(sheet) = sheets.get(&handle.0) else {
+        return;
+    };
+
+    for (entity, mut animation, mut sprite, explosion) in &mut query {
+        if animation.finished {
+            continue;
+        }
+        animation.elapsed += time.delta_secs();
+
+        let (mut frame, finished) = sheet
+            .animations
+            .get(animation.clip)
+            .frame_at(animation.elapsed);
+        if finished {
+            if explosion {
+                commands.entity(entity).despawn();
+                continue;
+            }
+            match animation.clip.next() {
+                Some(next) => {
+                    animation.restart(next);
+                    frame = sheet.animations.get(next).frames[0];
+                }
+                None => animation.finished = true,
+            }
+        }
+
+        if sprite.image != sheet.image {
+            sprite.image = sheet.image.clone();
+        }
+        match &mut sprite.texture_atlas {
+            Some(atlas) => atlas.index = frame,
+            None => {
+                sprite.texture_atlas = Some(TextureAtlas {
+                    layout: sheet.layout.clone(),
+                    index: frame,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// 同梱のスプライトの定義
+    fn shipped_sheet() -> SpriteSheetDefinition {
+        let path = format!("{}/assets/{SPRITE_SHEET_PATH}", env!("CARGO_MANIFEST_DIR"));
+        ron::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    /// frames のコマを fps で再生するアニメーション
+    fn animation(frames: &[usize], fps: f32, looping: bool) -> AnimationDefinition {
+        AnimationDefinition {
+            frames: frames.to_vec(),
+            fps,
+            looping,
+        }
+    }
+
+    #[test]
+    fn looping_animation_wraps_around() {
+        let idle = animation(&[0, 1, 2], 10.0, true);
+        assert_eq!(idle.frame_at(0.0), (0, false));
+        assert_eq!(idle.frame_at(0.15), (1, false));
+        assert_eq!(idle.frame_at(0.25), (2, false));
+        assert_eq!(idle.frame_at(0.35), (0, false));
+        assert_eq!(idle.frame_at(3.05), (0, false));
+    }
+
+    #[test]
+    fn non_looping_animation_holds_last_frame_when_finished() {
+        let hit = animation(&[10, 8, 10], 20.0, false);
+        assert_eq!(hit.frame_at(0.0), (10, false));
+        assert_eq!(hit.frame_at(0.075), (8, false));
+        assert_eq!(hit.frame_at(0.125), (10, false));
+        assert_eq!(hit.frame_at(0.175), (10, true));
+        assert_eq!(hit.frame_at(5.0), (10, true));
+    }
+
+    #[test]
+    fn finished_clip_falls_back_to_next_clip() {
+        assert_eq!(
+            AnimationClip::EnemyHit.next(),
+            Some(AnimationClip::EnemyIdle)
+        );
+        assert_eq!(AnimationClip::Explosion.next(), None);
+        assert_eq!(AnimationClip::PlayerIdle.next(), None);
+    }
+
+    #[test]
+    fn shipped_sheet_is_valid() {
+        assert_eq!(shipped_sheet().validate(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn sheet_without_frames_is_rejected() {
+        let empty = SpriteSheetDefinition {
+            rows: 0,
+            ..shipped_sheet()
+        };
+        let errors = empty.validate();
+        assert!(errors.contains(&"columns and rows must be positive".to_string()));
+        // コマがないので、どのアニメーションのコマも範囲外になる
+        assert_eq!(errors.len(), 1 + 7);
+    }
+
+    #[test]
+    fn animation_definition_errors_are_reported() {
+        let mut sheet = shipped_sheet();
+        sheet.animations.bullet = animation(&[], 12.0, true);
+        sheet.animations.explosion = animation(&[11, 16], 0.0, false);
+        assert_eq!(
+            sheet.validate(),
+            vec![
+                "bullet.frames must not be empty".to_string(),
+                "explosion.frames contains 16 but the sheet has only 16 frames".to_string(),
+                "explosion.fps must be positive".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn sprites_stay_plain_until_sheet_is_loaded() {
+        let mut world = World::new();
+        world.init_resource::<Assets<SpriteSheet>>();
+        world.init_resource::<Assets<Image>>();
+        world.init_resource::<Time>();
+        assert!(!world.run_system_cached(sprite_art_ready).unwrap());
+
+        world.insert_resource(SpriteSheetHandle(Handle::default()));
+        assert!(!world.run_system_cached(sprite_art_ready).unwrap());
+
+        // 定義がない間は、スプライトを色付きの四角形のまま変えない
+        let enemy = world
+            .spawn((
+                Sprite::from_color(Color::WHITE, Vec2::splat(50.0)),
+                SpriteAnimation::new(AnimationClip::EnemyIdle),
+            ))
+            .id();
+        world.run_system_once(animate_sprites).unwrap();
+        let sprite = world.get::<Sprite>(enemy).unwrap();
+        assert!(sprite.texture_atlas.is_none());
+        assert_eq!(sprite.image, Handle::default());
+        assert_eq!(world.get::<SpriteAnimation>(enemy).unwrap().elapsed, 0.0);
+    }
+}
@@ -0,0 +1,410 @@
+/// ボス戦
+use bevy::prelude::*;
+
+use super::collision::{Collider, Collision, CollisionLayers};
+use super::enemy_bullet::EnemyBulletPool;
+use super::interpolation::Interpolated;
+use super::powerup::PiercingBullet;
+use super::{BossDefeated, Bullet, BulletHit, GamePhase, Player, Playfield, nearest_position};
+use crate::font::DefaultFont;
+use crate::plugins::audio::{PlaySfx, Sfx};
+use crate::plugins::locale::LocalizedText;
+use crate::state::GameState;
+
+/// ボス本体のサイズ
+pub(super) const BOSS_SIZE: Vec2 = Vec2::new(180.0, 80.0);
+/// ボス本体の色
+const BOSS_COLOR: Color = Color::srgb(0.5, 0.5, 0.6);
+/// ボスの最大HP
+const BOSS_MAX_HP: u32 = 120;
+/// ボスが登場後に止まるY座標
+const BOSS_HOME_Y: f32 = 250.0;
+/// 登場時の降下速度（ピクセル/秒）
+const BOSS_ENTRY_SPEED: f32 = 80.0;
+/// 撃破時のボーナス点（コンボ倍率が掛かる）
+pub(super) const BOSS_SCORE: u32 = 5000;
+
+/// 弱点のサイズ
+const WEAK_POINT_SIZE: Vec2 = Vec2::new(30.0, 30.0);
+/// 弱点の色
+const WEAK_POINT_COLOR: Color = Color::srgb(1.0, 0.85, 0.0);
+/// 弱点の本体からの位置
+const WEAK_POINT_OFFSETS: [Vec2; 2] = [Vec2::new(-60.0, -20.0), Vec2::new(60.0, -20.0)];
+/// 弱点に当たったときのダメージ倍率（本体は弾のダメージそのまま）
+const WEAK_POINT_DAMAGE: u32 = 3;
+
+/// ボスの弾の速度（ピクセル/秒）
+const BOSS_BULLET_SPEED: f32 = 240.0;
+
+/// HPバーの幅
+const HP_BAR_WIDTH: f32 = 400.0;
+/// HPバーの高さ
+const HP_BAR_HEIGHT: f32 = 14.0;
+
+/// ボスの攻撃
+#[derive(Clone, Copy, Debug)]
+enum BossAttack {
+    /// 真下に向けて扇状に撃つ
+    Fan {
+        /// 弾の数
+        count: u32,
+        /// 扇の開き（ラジアン）
+        angle: f32,
+    },
+    /// プレイヤーを狙って弱点から撃つ
+    Aimed,
+    /// 全方向に円形に撃つ
+    Ring {
+        /// 弾の数
+        count: u32,
+    },
+}
+
+/// ボスのフェーズごとの行動
+struct BossPhase {
+    /// このフェーズに入るHPの割合（これ以下になったら移行する）
+    hp_fraction: f32,
+    /// 左右移動の速さ（ピクセル/秒）
+    move_speed: f32,
+    /// 攻撃の間隔（秒）
+    attack_interval: f32,
+    /// 順番に繰り返す攻撃
+    attacks: &'static [BossAttack],
+}
+
+/// ボスのフェーズ表（HPが減るごとに次のフェーズへ進む）
+const BOSS_PHASES: [BossPhase; 3] = [
+    BossPhase {
+        hp_fraction: 1.0,
+        move_speed: 80.0,
+        attack_interval: 1.6,
+        attacks: &[
+            BossAttack::Fan {
+                count: 5,
+                angle: 1.0,
+            },
+            BossAttack::Aimed,
+        ],
+    },
+    BossPhase {
+        hp_fraction: 0.66,
+        move_speed: 130.0,
+        attack_interval: 1.2,
+        attacks: &[
+            BossAttack::Ring { count: 12 },
+            BossAttack::Aimed,
+            BossAttack::Fan {
+                count: 7,
+                angle: 1.4,
+            },
+        ],
+    },
+    BossPhase {
+        hp_fraction: 0.33,
+        move_speed: 180.0,
+        attack_interval: 0.8,
+        attacks: &[
+            BossAttack::Ring { count: 16 },
+            BossAttack::Aimed,
+            BossAttack::Aimed,
+            BossAttack::Fan {
+                count: 9,
+                angle: 1.8,
+            },
+        ],
+    },
+];
+
+/// ボスのコンポーネント
+#[derive(Component)]
+#[require(Interpolated)]
+pub struct Boss {
+    /// 残りHP
+    hp: u32,
+    /// 現在のフェーズ（BOSS_PHASES の添字）
+    phase: usize,
+    /// 次の攻撃までのタイマー
+    attack_timer: Timer,
+    /// 現在のフェーズで次に使う攻撃の番号
+    attack_index: usize,
+    /// 左右移動の向き（1.0 または -1.0）
+    direction_x: f32,
+}
+
+/// ボスの弱点のマーカーコンポーネント（ボスの子エンティティ）
+#[derive(Component)]
+pub(super) struct WeakPoint;
+
+/// ボスのHPバーの中身のマーカーコンポーネント
+#[derive(Component)]
+pub(super) struct BossHpFill;
+
+/// ボス戦の開始時にボスとHPバーをspawnするシステム
+pub(super) fn spawn_boss(
+    mut commands: Commands,
+    asset: Res<DefaultFont>,
+    playfield: Res<Playfield>,
+) {
+    let spawn_y = playfield.half_size.y + BOSS_SIZE.y;
+
+    commands
+        .spawn((
+            Sprite::from_color(BOSS_COLOR, BOSS_SIZE),
+            Transform::from_xyz(0.0, spawn_y, 0.0),
+            Boss {
+                hp: BOSS_MAX_HP,
+                phase: 0,
+                attack_timer: Timer::from_seconds(
+                    BOSS_PHASES[0].attack_interval,
+                    TimerMode::Repeating,
+                ),
+                attack_index: 0,
+                direction_x: 1.0,
+            },
+            Collider::aabb(BOSS_SIZE, CollisionLayers::BOSS, CollisionLayers::NONE),
+            DespawnOnExit(GameState::Game),
+        ))
+        .with_children(|parent| {
+            for offset in WEAK_POINT_OFFSETS {
+                parent.spawn((
+                    Sprite::from_color(WEAK_POINT_COLOR, WEAK_POINT_SIZE),
+                    Transform::from_xyz(offset.x, offset.y, 0.1),
+                    WeakPoint,
+                    Collider::aabb(
+                        WEAK_POINT_SIZE,
+                        CollisionLayers::WEAK_POINT,
+                        CollisionLayers::NONE,
+                    ),
+                ));
+            }
+        });
+
+    // 画面上部のHPバー
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            DespawnOnExit(GamePhase::Boss),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                LocalizedText::new("hud.boss"),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.3, 0.3)),
+            ));
+
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(HP_BAR_WIDTH),
+                        height: Val::Px(HP_BAR_HEIGHT),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                ))
+                .with_children(|bar| {
+                    bar.spawn((
+                        Node {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(1.0, 0.3, 0.3)),
+                        BossHpFill,
+                    ));
+                });
+        });
+}
+
+/// ボスを登場位置まで降下させ、その後左右に往復させるシステム
+pub(super) fn boss_movement(
+    time: Res<Time>,
+    playfield: Res<Playfield>,
+    mut query: Query<(&mut Transform, &mut Boss)>,
+) {
+    let half_w = playfield.half_size.x - BOSS_SIZE.x / 2.0;
+
+    for (mut transform, mut boss) in &mut query {
+        // 登場演出：定位置まで降りてくる
+        if transform.translation.y > BOSS_HOME_Y {
+            transform.translation.y =
+                (transform.translation.y - BOSS_ENTRY_SPEED * time.delta_secs()).max(BOSS_HOME_Y);
+            continue;
+        }
+
+        let speed = BOSS_PHASES[boss.phase].move_speed;
+        transform.translation.x += boss.direction_x * speed * time.delta_secs();
+        // 画面端で折り返す
+        if transform.translation.x.abs() > half_w {
+            transform.translation.x = transform.translation.x.clamp(-half_w, half_w);
+            boss.direction_x = -boss.direction_x;
+        }
+    }
+}
+
+/// フェーズの攻撃パターンを順番に繰り出すシステム
+pub(super) fn boss_attack(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<EnemyBulletPool>,
+    mut boss_query: Query<(&Transform, &mut Boss)>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    for (transform, mut boss) in &mut boss_query {
+        // 定位置に着くまでは攻撃しない
+        if transform.translation.y > BOSS_HOME_Y {
+            continue;
+        }
+        if !boss.attack_timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let phase = &BOSS_PHASES[boss.phase];
+        let attack = phase.attacks[boss.attack_index % phase.attacks.len()];
+        boss.attack_index += 1;
+
+        let origin = transform.translation.truncate();
+        match attack {
+            BossAttack::Fan { count, angle } => {
+                let count = count.max(2);
+                for i in 0..count {
+                    let t = i as f32 / (count - 1) as f32 - 0.5;
+                    let direction = Vec2::from_angle(t * angle).rotate(Vec2::NEG_Y);
+                    pool.fire(&mut commands, origin, direction * BOSS_BULLET_SPEED);
+                }
+            }
+            BossAttack::Aimed => {
+                // 両方の弱点から一番近いプレイヤーを狙う
+                for offset in WEAK_POINT_OFFSETS {
+                    let muzzle = origin + offset;
+                    let player_pos = nearest_position(
+                        muzzle,
+                        player_query.iter().map(|t| t.translation.truncate()),
+                    );
+                    let direction = player_pos
+                        .map(|p| (p - muzzle).normalize_or(Vec2::NEG_Y))
+                        .unwrap_or(Vec2::NEG_Y);
+                    pool.fire(&mut commands, muzzle, direction * BOSS_BULLET_SPEED);
+                }
+            }
+            BossAttack::Ring { count } => {
+                let count = count.max(1);
+                // 攻撃のたびに少し回転させて隙間の位置を変える
+                let rotation = boss.attack_index as f32 * 0.2;
+                for i in 0..count {
+                    let angle = rotation + i as f32 / count as f32 * std::f32::consts::TAU;
+                    let direction = Vec2::from_angle(angle);
+                    pool.fire(&mut commands, origin, direction * BOSS_BULLET_SPEED);
+                }
+            }
+        }
+    }
+}
+
+/// 弾とボスの当たり判定処理
+///
+/// 弱点に当たると大ダメージ。HPが減るとフェーズが進み、0になったら BossDefeated を送る
+pub(super) fn check_bullet_boss_collisions(
+    mut commands: Commands,
+    mut collisions: MessageReader<Collision>,
+    bullet_query: Query<(&Bullet, &Transform, Option<&PiercingBullet>)>,
+    mut boss_query: Query<(&mut Boss, &Transform)>,
+    weak_point_query: Query<&ChildOf, With<WeakPoint>>,
+    mut bullet_hits: MessageWriter<BulletHit>,
+    mut defeated: MessageWriter<BossDefeated>,
+) {
+    // 弾ごとに、当たったボスと弱点に当たったかどうかをまとめる（本体と弱点の両方に当たっても1回だけ数える）
+    let mut hits: Vec<(Entity, Entity, bool)> = Vec::new();
+    for collision in collisions.read() {
+        if !bullet_query.contains(collision.a) {
+            continue;
+        }
+        let (boss_entity, weak) = if let Ok(child_of) = weak_point_query.get(collision.b) {
+            (child_of.parent(), true)
+        } else if boss_query.contains(collision.b) {
+            (collision.b, false)
+        } else {
+            continue;
+        };
+        match hits
+            .iter_mut()
+            .find(|(bullet, _, _)| *bullet == collision.a)
+        {
+            Some((_, _, hit_weak)) => *hit_weak |= weak,
+            None => hits.push((collision.a, boss_entity, weak)),
+        }
+    }
+
+    for (bullet_entity, boss_entity, weak) in hits {
+        let (Ok((bullet, bullet_transform, piercing)), Ok((mut boss, boss_transform))) = (
+            bullet_query.get(bullet_entity),
+            boss_query.get_mut(boss_entity),
+        ) else {
+            continue;
+        };
+        // このティックですでに倒されたボスは無視する
+        if boss.hp == 0 {
+            continue;
+        }
+        let damage = if weak {
+            bullet.damage * WEAK_POINT_DAMAGE
+        } else {
+            bullet.damage
+        };
+
+        commands.entity(bullet_entity).despawn();
+        bullet_hits.write(BulletHit {
+            // 貫通する弾が先に敵に当たっていれば、命中はもう数えてある
+            first: piercing.is_none_or(|piercing| piercing.hit.is_empty()),
+            target: boss_entity,
+            position: bullet_transform.translation.truncate(),
+        });
+        boss.hp = boss.hp.saturating_sub(damage);
+
+        if boss.hp == 0 {
+            // 撃破したら削除する（ボーナス点と遷移は BossDefeated を受け取る側で処理する）
+            commands.entity(boss_entity).despawn();
+            defeated.write(BossDefeated {
+                owner: bullet.owner,
+                position: boss_transform.translation.truncate(),
+            });
+            continue;
+        }
+
+        commands.write_message(PlaySfx(Sfx::Hit));
+
+        // HPの割合に応じてフェーズを進める
+        let fraction = boss.hp as f32 / BOSS_MAX_HP as f32;
+        while boss.phase + 1 < BOSS_PHASES.len()
+            && fraction <= BOSS_PHASES[boss.phase + 1].hp_fraction
+        {
+            boss.phase += 1;
+            boss.attack_index = 0;
+            let interval = BOSS_PHASES[boss.phase].attack_interval;
+            boss.attack_timer = Timer::from_seconds(interval, TimerMode::Repeating);
+        }
+    }
+}
+
+/// ボスのHPバーを更新するシステム
+pub(super) fn update_boss_hp_ui(
+    boss_query: Query<&Boss, Changed<Boss>>,
+    mut fill_query: Query<&mut Node, With<BossHpFill>>,
+) {
+    let Ok(boss) = boss_query.single() else {
+        return;
+    };
+    if let Ok(mut node) = fill_query.single_mut() {
+        node.width = Val::Percent(boss.hp as f32 / BOSS_MAX_HP as f32 * 100.0);
+    }
+}
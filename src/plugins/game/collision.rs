@@ -0,0 +1,372 @@
+/// 当たり判定（コライダー・衝突レイヤー・ブロードフェーズ）
+///
+/// コライダーを持つエンティティをティックごとに一様グリッドへ登録し、同じセルに入った組だけを
+/// 形状どうしで判定する。当たった組は Collision メッセージとして送り、ダメージや得点などの処理は
+/// メッセージを受け取る側のシステムで行う。
+/// 弾のような速い物体は、ティックの始めの位置から今の位置までの掃引で判定し、すり抜けを防ぐ
+use std::collections::HashMap;
+use std::ops::BitOr;
+
+use bevy::ecs::lifecycle::HookContext;
+use bevy::ecs::world::DeferredWorld;
+use bevy::prelude::*;
+
+/// グリッドの1セルの大きさ（ピクセル。よく当たる物体の大きさ程度にする）
+const GRID_CELL_SIZE: f32 = 64.0;
+
+/// 当たり判定を行うシステムセット
+///
+/// 物体を動かすシステムはこのセットより前、Collision を受け取るシステムは後に実行する
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CollisionSystems;
+
+/// 衝突レイヤー（ビットの集合）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CollisionLayers(u32);
+
+impl CollisionLayers {
+    /// どのレイヤーも含まない
+    pub const NONE: Self = Self(0);
+    /// プレイヤー
+    pub const PLAYER: Self = Self(1 << 0);
+    /// プレイヤーの弾
+    pub const PLAYER_BULLET: Self = Self(1 << 1);
+    /// 敵
+    pub const ENEMY: Self = Self(1 << 2);
+    /// 敵の弾
+    pub const ENEMY_BULLET: Self = Self(1 << 3);
+    /// ボスの本体
+    pub const BOSS: Self = Self(1 << 4);
+    /// ボスの弱点
+    pub const WEAK_POINT: Self = Self(1 << 5);
+    /// パワーアップのアイテム
+    pub const POWER_UP: Self = Self(1 << 6);
+
+    /// 共通するレイヤーがあるかどうか
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for CollisionLayers {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// コライダーの形
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColliderShape {
+    /// 回転しない矩形（中心から辺までの距離）
+    Aabb { half_size: Vec2 },
+    /// 円
+    Circle { radius: f32 },
+}
+
+impl ColliderShape {
+    /// 中心が center のときに形を囲む矩形
+    fn bounds(self, center: Vec2) -> Rect {
+        let half_size = match self {
+            Self::Aabb { half_size } => half_size,
+            Self::Circle { radius } => Vec2::splat(radius),
+        };
+        Rect::from_center_half_size(center, half_size)
+    }
+
+    /// 中心が a_pos の self が other（中心 b_pos）に対して motion だけ動く間に、
+    /// 一度でも重なるかどうか（辺が接するだけなら重ならない）
+    ///
+    /// 動いている矩形と円の組は、円を囲む矩形として調べる
+    fn sweep_overlaps(self, a_pos: Vec2, motion: Vec2, other: Self, b_pos: Vec2) -> bool {
+        match (self, other) {
+            (Self::Circle { radius: a }, Self::Circle { radius: b }) => {
+                segment_near_point(a_pos, motion, b_pos, a + b)
+            }
+            (Self::Aabb { half_size }, Self::Circle { radius }) if motion == Vec2::ZERO => {
+                circle_overlaps_aabb(b_pos, radius, a_pos, half_size)
+            }
+            (Self::Circle { radius }, Self::Aabb { half_size }) if motion == Vec2::ZERO => {
+                circle_overlaps_aabb(a_pos, radius, b_pos, half_size)
+            }
+            _ => {
+                let reach =
+                    self.bounds(Vec2::ZERO).half_size() + other.bounds(Vec2::ZERO).half_size();
+                segment_enters_box(a_pos, motion, b_pos, reach)
+            }
+        }
+    }
+}
+
+/// 円と矩形が重なっているかどうか（矩形の中で円の中心に一番近い点との距離で調べる）
+fn circle_overlaps_aabb(circle: Vec2, radius: f32, center: Vec2, half_size: Vec2) -> bool {
+    let closest = circle.clamp(center - half_size, center + half_size);
+    circle.distance_squared(closest) < radius * radius
+}
+
+/// start から motion だけ動く点が、中心 center・半分の大きさ half_size の矩形の内側に入るかどうか
+///
+/// 軸ごとに矩形の内側にいる時間の範囲を求め、すべての軸で重なる時間が 0 ~ 1 の間にあれば入る
+fn segment_enters_box(start: Vec2, motion: Vec2, center: Vec2, half_size: Vec2) -> bool {
+    let (mut enter, mut exit) = (f32::NEG_INFINITY, f32::INFINITY);
+    for axis in 0..2 {
+        let (offset, speed) = (center[axis] - start[axis], motion[axis]);
+        if speed == 0.0 {
+            // この軸では動かないので、始めから内側にいなければ入らない
+            if offset.abs() >= half_size[axis] {
+                return false;
+            }
+            continue;
+        }
+        let t1 = (offset - half_size[axis]) / speed;
+        let t2 = (offset + half_size[axis]) / speed;
+        enter = enter.max(t1.min(t2));
+        exit = exit.min(t1.max(t2));
+    }
+    enter < exit && enter < 1.0 && exit > 0.0
+}
+
+/// start から motion だけ動く点が、point から radius 未満の距離まで近づくかどうか
+fn segment_near_point(start: Vec2, motion: Vec2, point: Vec2, radius: f32) -> bool {
+    let length_squared = motion.length_squared();
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        ((point - start).dot(motion) / length_squared).clamp(0.0, 1.0)
+    };
+    (start + motion * t).distance_squared(point) < radius * radius
+}
+
+/// 当たり判定を持つコンポーネント
+///
+/// 位置は Transform（子エンティティなら親の位置を足したもの）を使う。回転・拡大は考えない
+#[derive(Component, Clone, Copy, Debug)]
+#[component(on_insert = number_collider)]
+pub struct Collider {
+    /// 形
+    pub shape: ColliderShape,
+    /// 自分が属するレイヤー
+    pub layer: CollisionLayers,
+    /// 当たりを調べる相手のレイヤー
+    pub mask: CollisionLayers,
+    /// 当たり判定に加わった順番（付けたときに決まる。当たった組を送る順に使う）
+    order: u64,
+}
+
+/// コライダーに付ける順番の次の値を保持するリソース
+#[derive(Resource, Default)]
+pub(super) struct ColliderSequence(u64);
+
+/// Collider を付けたときに、当たり判定に加わった順番を決める
+///
+/// コライダーはティックの中で決まった順に付けるので、同じ操作なら記録時と再生時で同じ順番になる
+fn number_collider(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    let Some(mut sequence) = world.get_resource_mut::<ColliderSequence>() else {
+        return;
+    };
+    let order = sequence.0;
+    sequence.0 += 1;
+    if let Some(mut collider) = world.get_mut::<Collider>(entity) {
+        collider.order = order;
+    }
+}
+
+impl Collider {
+    /// 大きさ size の矩形のコライダー
+    pub fn aabb(size: Vec2, layer: CollisionLayers, mask: CollisionLayers) -> Self {
+        Self {
+            shape: ColliderShape::Aabb {
+                half_size: size / 2.0,
+            },
+            layer,
+            mask,
+            order: 0,
+        }
+    }
+
+    /// 半径 radius の円のコライダー
+    pub fn circle(radius: f32, layer: CollisionLayers, mask: CollisionLayers) -> Self {
+        Self {
+            shape: ColliderShape::Circle { radius },
+            layer,
+            mask,
+            order: 0,
+        }
+    }
+}
+
+/// ティックの始めの位置から今の位置までの掃引で当たりを調べるコンポーネント
+///
+/// 1ティックで自分の大きさ以上に動く弾などに付ける。追加したとき（プールから再利用するときも含む）は、
+/// その時点の位置から掃引を始める
+#[derive(Component, Default)]
+#[component(on_insert = snap_sweep_origin)]
+pub struct Swept {
+    /// ティックの始めの位置
+    origin: Vec2,
+}
+
+/// Swept を追加したときに、掃引の始点を今の位置に合わせる
+fn snap_sweep_origin(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    let Some(transform) = world.get::<Transform>(entity).copied() else {
+        return;
+    };
+    if let Some(mut swept) = world.get_mut::<Swept>(entity) {
+        swept.origin = transform.translation.truncate();
+    }
+}
+
+/// ティックの始めに、掃引の始点として今の位置を覚えるシステム
+pub(super) fn store_sweep_origins(mut query: Query<(&Transform, &mut Swept)>) {
+    for (transform, mut swept) in &mut query {
+        swept.origin = transform.translation.truncate();
+    }
+}
+
+/// 2つのコライダーが重なったときのメッセージ
+///
+/// a は b のレイヤーを当たりを調べる相手にしている側（両方ならレイヤーのビットが小さい側）。
+/// たとえばプレイヤーの弾と敵なら a が弾、b が敵になる。
+/// 同じティックの中では、a・b がそれぞれ当たり判定に加わった順（Collider を付けた順）に並ぶ
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Collision {
+    /// 当たりを調べた側
+    pub a: Entity,
+    /// 当たられた側
+    pub b: Entity,
+}
+
+/// 当たり判定に登録した物体
+struct Body {
+    entity: Entity,
+    position: Vec2,
+    /// ティックの始めの位置（掃引しない物体は今の位置と同じ）
+    origin: Vec2,
+    collider: Collider,
+}
+
+impl Body {
+    /// ティックの始めから今までの移動を覆う矩形
+    fn swept_bounds(&self) -> Rect {
+        let shape = self.collider.shape;
+        shape.bounds(self.origin).union(shape.bounds(self.position))
+    }
+
+    /// self と other がこのティックの間に重なったかどうか
+    ///
+    /// other から見た self の動き（2つの移動の差）で掃引する
+    fn hits(&self, other: &Body) -> bool {
+        let motion = (self.position - self.origin) - (other.position - other.origin);
+        self.collider
+            .shape
+            .sweep_overlaps(self.origin, motion, other.collider.shape, other.origin)
+    }
+
+    /// self が other を当たりを調べる相手にしているかどうか
+    fn targets(&self, other: &Body) -> bool {
+        self.collider.mask.intersects(other.collider.layer)
+    }
+}
+
+/// 一様グリッド（セルの座標ごとに、そのセルにかかる物体の番号を持つ）
+///
+/// 毎ティック作り直すが、確保したメモリは使い回す
+#[derive(Default)]
+pub(super) struct SpatialGrid {
+    cells: HashMap<IVec2, Vec<usize>>,
+}
+
+impl SpatialGrid {
+    /// すべてのセルを空にする
+    fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    /// bounds にかかるすべてのセルに物体の番号を登録する
+    fn insert(&mut self, index: usize, bounds: Rect) {
+        let min = (bounds.min / GRID_CELL_SIZE).floor().as_ivec2();
+        let max = (bounds.max / GRID_CELL_SIZE).floor().as_ivec2();
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    /// 同じセルに入っている物体の番号の組（小さい番号が先。重複あり）
+    fn candidate_pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.cells.values().flat_map(|cell| {
+            cell.iter()
+                .enumerate()
+                .flat_map(move |(n, &i)| cell[n + 1..].iter().map(move |&j| (i.min(j), i.max(j))))
+        })
+    }
+}
+
+/// コライダーどうしの当たりを調べて Collision を送るシステム
+pub(super) fn detect_collisions(
+    query: Query<(Entity, &Transform, &Collider, Option<&ChildOf>)>,
+    parent_query: Query<&Transform>,
+    swept_query: Query<&Swept>,
+    mut grid: Local<SpatialGrid>,
+    mut collisions: MessageWriter<Collision>,
+) {
+    let bodies: Vec<Body> = query
+        .iter()
+        .map(|(entity, transform, collider, child_of)| {
+            let parent_position = child_of
+                .and_then(|child_of| parent_query.get(child_of.parent()).ok())
+                .map_or(Vec2::ZERO, |parent| parent.translation.truncate());
+            let position = parent_position + transform.translation.truncate();
+            Body {
+                entity,
+                position,
+                origin: swept_query
+                    .get(entity)
+                    .map_or(position, |swept| swept.origin),
+                collider: *collider,
+            }
+        })
+        .collect();
+
+    // ブロードフェーズ：同じセルに入った組のうち、どちらかが相手を調べる組だけを候補にする
+    grid.clear();
+    for (index, body) in bodies.iter().enumerate() {
+        grid.insert(index, body.swept_bounds());
+    }
+    let mut pairs: Vec<(usize, usize)> = grid
+        .candidate_pairs()
+        .filter(|&(i, j)| bodies[i].targets(&bodies[j]) || bodies[j].targets(&bodies[i]))
+        .collect();
+    pairs.sort_unstable();
+    pairs.dedup();
+
+    // ナローフェーズ：形どうしで、ティックの間に重なったかを調べる
+    // （当たった組は、調べる側・調べられる側の物体の番号で表す）
+    let mut hits: Vec<(usize, usize)> = pairs
+        .into_iter()
+        .filter(|&(i, j)| bodies[i].hits(&bodies[j]))
+        .map(|(i, j)| {
+            let (a, b) = (&bodies[i], &bodies[j]);
+            let a_first = if a.targets(b) && b.targets(a) {
+                a.collider.layer.0 <= b.collider.layer.0
+            } else {
+                a.targets(b)
+            };
+            if a_first { (i, j) } else { (j, i) }
+        })
+        .collect();
+    // グリッドの並びに左右されないよう、当たり判定に加わった順に送る（リプレイで同じ展開にするため）
+    //
+    // エンティティの ID は UI やパーティクルのようにフレームごとに作り直すエンティティによって、
+    // クエリの並びは描画のためにフレームごとに付くコンポーネントによって変わるので、どちらも
+    // 記録時と再生時で同じになるとは限らない
+    hits.sort_unstable_by_key(|&(i, j)| (bodies[i].collider.order, bodies[j].collider.order));
+    collisions.write_batch(hits.into_iter().map(|(i, j)| Collision {
+        a: bodies[i].entity,
+        b: bodies[j].entity,
+    }));
+}
@@ -0,0 +1,341 @@
+/// 敵の種類（アーキタイプ）
+use bevy::prelude::*;
+use rand::RngExt;
+use serde::{Deserialize, Serialize};
+
+use super::animation::{AnimationClip, SpriteAnimation};
+use super::collision::{Collider, CollisionLayers};
+use super::enemy_bullet::{EnemyFire, FirePattern};
+use super::interpolation::Interpolated;
+use crate::state::GameState;
+
+/// 敵の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EnemyArchetype {
+    /// まっすぐ落ちてくる基本の敵
+    Grunt,
+    /// 大きくて硬い敵
+    Tank,
+    /// 小さくて速い敵
+    Dart,
+    /// 左右に揺れながら落ちてくる敵
+    ZigZag,
+    /// 倒すと2体の破片に分裂する敵
+    Splitter,
+    /// Splitter の破片
+    Shard,
+}
+
+/// 敵の動き方
+#[derive(Clone, Debug, PartialEq)]
+pub enum EnemyMotion {
+    /// 真下に落ちる
+    Straight,
+    /// 正弦波で左右に揺れながら落ちる
+    ZigZag {
+        /// 揺れ幅（ピクセル）
+        amplitude: f32,
+        /// 1秒あたりの往復回数
+        frequency: f32,
+    },
+    /// 斜めに落ち、画面端で跳ね返る
+    Drift {
+        /// 横方向の速さ（ピクセル/秒）
+        speed_x: f32,
+    },
+    /// 指定した座標を順番にたどり、最後の点からは真下に落ちる
+    Path(Vec<Vec2>),
+}
+
+/// アーキタイプごとの性能
+pub struct EnemyStats {
+    /// 撃破に必要な命中数
+    pub hp: u32,
+    /// 落下速度（ピクセル/秒）
+    pub speed: f32,
+    /// 経過時間から決まる基本サイズに対する倍率
+    pub size_scale: f32,
+    /// スプライトの色
+    pub color: Color,
+    /// 初期サイズで倒したときの基本点
+    pub score: f32,
+    /// 動き方
+    pub motion: EnemyMotion,
+    /// スポーン抽選の重み（0ならスポーナーからは出現しない）
+    pub spawn_weight: u32,
+    /// スポーン抽選に加わるまでの経過時間（秒）
+    pub unlock_secs: f32,
+    /// 撃破時に残り討伐数を減らすかどうか
+    pub counts_toward_total: bool,
+    /// 射撃パターンと基本の発射間隔（秒）。撃たない敵は None
+    pub fire: Option<(FirePattern, f32)>,
+}
+
+/// スポーン抽選の対象となるアーキタイプ
+const SPAWNABLE: [EnemyArchetype; 5] = [
+    EnemyArchetype::Grunt,
+    EnemyArchetype::Tank,
+    EnemyArchetype::Dart,
+    EnemyArchetype::ZigZag,
+    EnemyArchetype::Splitter,
+];
+
+/// 分裂時に生まれる破片の数
+const SPLIT_COUNT: usize = 2;
+
+impl EnemyArchetype {
+    /// アーキタイプの性能表
+    pub const fn stats(self) -> EnemyStats {
+        match self {
+            Self::Grunt => EnemyStats {
+                hp: 1,
+                speed: 200.0,
+                size_scale: 1.0,
+                color: Color::srgb(1.0, 0.2, 0.2),
+                score: 100.0,
+                motion: EnemyMotion::Straight,
+                spawn_weight: 10,
+                unlock_secs: 0.0,
+                counts_toward_total: true,
+                fire: Some((FirePattern::Aimed, 3.0)),
+            },
+            Self::Tank => EnemyStats {
+                hp: 3,
+                speed: 120.0,
+                size_scale: 1.4,
+                color: Color::srgb(0.6, 0.1, 0.4),
+                score: 300.0,
+                motion: EnemyMotion::Straight,
+                spawn_weight: 3,
+                unlock_secs: 20.0,
+                counts_toward_total: true,
+                fire: Some((
+                    FirePattern::Spread {
+                        count: 5,
+                        angle: 1.2,
+                    },
+                    3.5,
+                )),
+            },
+            Self::Dart => EnemyStats {
+                hp: 1,
+                speed: 380.0,
+                size_scale: 0.7,
+                color: Color::srgb(1.0, 0.55, 0.0),
+                score: 150.0,
+                motion: EnemyMotion::Straight,
+                spawn_weight: 4,
+                unlock_secs: 10.0,
+                counts_toward_total: true,
+                fire: None,
+            },
+            Self::ZigZag => EnemyStats {
+                hp: 1,
+                speed: 150.0,
+                size_scale: 0.9,
+                color: Color::srgb(0.9, 0.2, 0.9),
+                score: 150.0,
+                motion: EnemyMotion::ZigZag {
+                    amplitude: 120.0,
+                    frequency: 0.8,
+                },
+                spawn_weight: 4,
+                unlock_secs: 30.0,
+                counts_toward_total: true,
+                fire: Some((FirePattern::Volley { shots: 3, gap: 0.2 }, 3.0)),
+            },
+            Self::Splitter => EnemyStats {
+                hp: 2,
+                speed: 160.0,
+                size_scale: 1.2,
+                color: Color::srgb(0.2, 0.8, 0.3),
+                score: 200.0,
+                motion: EnemyMotion::Straight,
+                spawn_weight: 3,
+                unlock_secs: 40.0,
+                counts_toward_total: true,
+                fire: Some((FirePattern::Aimed, 2.5)),
+            },
+            Self::Shard => EnemyStats {
+                hp: 1,
+                speed: 240.0,
+                size_scale: 0.5,
+                color: Color::srgb(0.6, 1.0, 0.6),
+                score: 50.0,
+                motion: EnemyMotion::Drift { speed_x: 140.0 },
+                spawn_weight: 0,
+                unlock_secs: 0.0,
+                counts_toward_total: false,
+                fire: None,
+            },
+        }
+    }
+
+    /// 倒されたときに分裂して生まれるアーキタイプ
+    pub const fn splits_into(self) -> Option<EnemyArchetype> {
+        match self {
+            Self::Splitter => Some(Self::Shard),
+            _ => None,
+        }
+    }
+
+    /// 経過時間に応じて出現可能なアーキタイプから重み付きで抽選する
+    pub fn pick(rng: &mut impl rand::Rng, elapsed_secs: f32) -> EnemyArchetype {
+        let candidates = SPAWNABLE.iter().filter(|a| {
+            let stats = a.stats();
+            stats.spawn_weight > 0 && elapsed_secs >= stats.unlock_secs
+        });
+        let total: u32 = candidates.clone().map(|a| a.stats().spawn_weight).sum();
+        if total == 0 {
+            return Self::Grunt;
+        }
+
+        let mut roll = rng.random_range(0..total);
+        for archetype in candidates {
+            let weight = archetype.stats().spawn_weight;
+            if roll < weight {
+                return *archetype;
+            }
+            roll -= weight;
+        }
+        Self::Grunt
+    }
+}
+
+/// 敵のコンポーネント（どのアーキタイプか）
+#[derive(Component)]
+#[require(Interpolated)]
+pub struct Enemy {
+    pub archetype: EnemyArchetype,
+}
+
+/// 敵の残り耐久値
+#[derive(Component)]
+pub struct Health(pub u32);
+
+/// 敵の移動状態
+#[derive(Component)]
+pub struct EnemyMovement {
+    /// 動き方
+    pub motion: EnemyMotion,
+    /// 落下速度（ピクセル/秒）
+    pub speed: f32,
+    /// 揺れの基準となるX座標
+    pub origin_x: f32,
+    /// 出現してからの経過時間（秒）
+    pub age: f32,
+    /// Drift の横方向の向き（1.0 または -1.0）
+    pub direction_x: f32,
+    /// Path で次に向かう点の番号
+    pub path_index: usize,
+}
+
+impl EnemyMovement {
+    /// 1フレーム分の移動を transform に反映する
+    ///
+    /// half_w は敵が画面内に留まれるX座標の上限
+    pub fn step(&mut self, transform: &mut Transform, delta_secs: f32, half_w: f32) {
+        self.age += delta_secs;
+
+        // Path は経路をたどっている間だけ落下の代わりに点へ向かって進む
+        if let EnemyMotion::Path(points) = &self.motion
+            && let Some(&target) = points.get(self.path_index)
+        {
+            let position = transform.translation.truncate();
+            let step = self.speed * delta_secs;
+            let to_target = target - position;
+            if to_target.length() <= step {
+                transform.translation.x = target.x;
+                transform.translation.y = target.y;
+                self.path_index += 1;
+            } else {
+                let moved = position + to_target.normalize() * step;
+                transform.translation.x = moved.x;
+                transform.translation.y = moved.y;
+            }
+            return;
+        }
+
+        transform.translation.y -= self.speed * delta_secs;
+
+        match self.motion {
+            EnemyMotion::Straight | EnemyMotion::Path(_) => {}
+            EnemyMotion::ZigZag {
+                amplitude,
+                frequency,
+            } => {
+                let offset = amplitude * (self.age * frequency * std::f32::consts::TAU).sin();
+                transform.translation.x = (self.origin_x + offset).clamp(-half_w, half_w);
+            }
+            EnemyMotion::Drift { speed_x } => {
+                transform.translation.x += self.direction_x * speed_x * delta_secs;
+                // 画面端で跳ね返る
+                if transform.translation.x.abs() > half_w {
+                    transform.translation.x = transform.translation.x.clamp(-half_w, half_w);
+                    self.direction_x = -self.direction_x;
+                }
+            }
+        }
+    }
+}
+
+/// 敵をspawnする
+///
+/// base_side は経過時間から決まる基本サイズ。実際のサイズはアーキタイプの倍率を掛けたもの。
+/// motion を指定するとアーキタイプの既定の動きの代わりに使う
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    archetype: EnemyArchetype,
+    position: Vec2,
+    base_side: f32,
+    direction_x: f32,
+    motion: Option<EnemyMotion>,
+) {
+    let stats = archetype.stats();
+    let size = Vec2::splat(base_side * stats.size_scale);
+
+    let mut entity = commands.spawn((
+        Sprite::from_color(stats.color, size),
+        SpriteAnimation::new(AnimationClip::EnemyIdle),
+        Transform::from_xyz(position.x, position.y, 0.0),
+        Enemy { archetype },
+        Health(stats.hp),
+        EnemyMovement {
+            motion: motion.unwrap_or(stats.motion),
+            speed: stats.speed,
+            origin_x: position.x,
+            age: 0.0,
+            direction_x,
+            path_index: 0,
+        },
+        Collider::aabb(size, CollisionLayers::ENEMY, CollisionLayers::NONE),
+        DespawnOnExit(GameState::Game),
+    ));
+
+    if let Some((pattern, interval)) = stats.fire {
+        entity.insert(EnemyFire::new(pattern, interval));
+    }
+}
+
+/// 倒された敵が分裂する場合、破片をspawnする
+///
+/// 破片は元の敵の基本サイズを引き継ぎ、左右に散らばる
+pub fn spawn_split(commands: &mut Commands, archetype: EnemyArchetype, position: Vec2, size: Vec2) {
+    let Some(child) = archetype.splits_into() else {
+        return;
+    };
+
+    let base_side = size.x / archetype.stats().size_scale;
+    for i in 0..SPLIT_COUNT {
+        let direction_x = if i % 2 == 0 { -1.0 } else { 1.0 };
+        let offset = Vec2::new(direction_x * size.x / 4.0, 0.0);
+        spawn_enemy(
+            commands,
+            child,
+            position + offset,
+            base_side,
+            direction_x,
+            None,
+        );
+    }
+}
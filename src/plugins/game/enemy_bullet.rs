@@ -0,0 +1,296 @@
+/// 敵の弾
+use bevy::prelude::*;
+
+use super::collision::{Collider, Collision, CollisionLayers, Swept};
+use super::enemy::Enemy;
+use super::interpolation::Interpolated;
+use super::{DamageSource, GameElapsedTime, Player, PlayerHit, Playfield, nearest_position};
+use crate::state::GameState;
+
+/// 敵の弾のサイズ
+const ENEMY_BULLET_SIZE: Vec2 = Vec2::new(10.0, 10.0);
+/// 敵の弾の色
+const ENEMY_BULLET_COLOR: Color = Color::srgb(1.0, 0.5, 0.8);
+/// 敵の弾の速度（ピクセル/秒）
+const ENEMY_BULLET_SPEED: f32 = 260.0;
+/// ゲーム開始時にあらかじめ用意しておく弾の数
+const ENEMY_BULLET_POOL_SIZE: usize = 64;
+
+/// 何秒ごとに発射間隔を短縮するか
+const FIRE_RATE_STEP_SECS: f32 = 15.0;
+/// 1ステップあたりの発射間隔の短縮率
+const FIRE_RATE_STEP_AMOUNT: f32 = 0.05;
+/// 発射間隔の倍率の下限
+const FIRE_RATE_SCALE_MIN: f32 = 0.5;
+
+/// 敵の射撃パターン
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FirePattern {
+    /// プレイヤーを狙って1発撃つ
+    Aimed,
+    /// 真下を中心に扇状に撃つ
+    Spread {
+        /// 弾の数
+        count: u32,
+        /// 扇の開き（ラジアン）
+        angle: f32,
+    },
+    /// プレイヤーを狙って一定間隔で連射する
+    Volley {
+        /// 連射数
+        shots: u32,
+        /// 連射の間隔（秒）
+        gap: f32,
+    },
+}
+
+/// 敵の射撃状態
+#[derive(Component)]
+pub struct EnemyFire {
+    /// 射撃パターン
+    pattern: FirePattern,
+    /// 基本の発射間隔（秒）
+    interval: f32,
+    /// 次の発射までのタイマー
+    timer: Timer,
+    /// 連射の残り弾数
+    volley_remaining: u32,
+}
+
+impl EnemyFire {
+    /// 射撃パターンと基本の発射間隔から射撃状態を作る
+    pub fn new(pattern: FirePattern, interval: f32) -> Self {
+        Self {
+            pattern,
+            interval,
+            timer: Timer::from_seconds(interval, TimerMode::Once),
+            volley_remaining: 0,
+        }
+    }
+}
+
+/// 敵の弾のコンポーネント
+#[derive(Component)]
+#[require(Interpolated, Swept)]
+pub struct EnemyBullet {
+    /// 速度（ピクセル/秒）
+    velocity: Vec2,
+}
+
+/// 使用中でない（プールに戻された）弾のマーカーコンポーネント
+///
+/// プールに戻された弾はコライダーを外すので、当たり判定の対象にならない
+#[derive(Component)]
+pub struct Pooled;
+
+/// 敵の弾のコライダー（プレイヤーの側から当たりを調べる）
+fn enemy_bullet_collider() -> Collider {
+    Collider::aabb(
+        ENEMY_BULLET_SIZE,
+        CollisionLayers::ENEMY_BULLET,
+        CollisionLayers::NONE,
+    )
+}
+
+/// 使い回す敵の弾を保持するリソース
+#[derive(Resource, Default)]
+pub struct EnemyBulletPool {
+    free: Vec<Entity>,
+}
+
+impl EnemyBulletPool {
+    /// プールから弾を取り出して発射する（空なら新しくspawnする）
+    pub(super) fn fire(&mut self, commands: &mut Commands, position: Vec2, velocity: Vec2) {
+        let transform = Transform::from_xyz(position.x, position.y, 0.0);
+        let bullet = EnemyBullet { velocity };
+
+        if let Some(entity) = self.free.pop() {
+            commands
+                .entity(entity)
+                .insert((
+                    transform,
+                    bullet,
+                    enemy_bullet_collider(),
+                    Visibility::Inherited,
+                    // 前に使ったときの位置から補間・掃引しないよう、始点をやり直す
+                    Interpolated::default(),
+                    Swept::default(),
+                ))
+                .remove::<Pooled>();
+        } else {
+            commands.spawn((
+                Sprite::from_color(ENEMY_BULLET_COLOR, ENEMY_BULLET_SIZE),
+                transform,
+                bullet,
+                enemy_bullet_collider(),
+                DespawnOnExit(GameState::Game),
+            ));
+        }
+    }
+
+    /// 弾を非表示にしてプールに戻す
+    ///
+    /// すでにプールに戻っている弾（Pooled を付けた、または同じティックで付ける予定の弾）なら何もしない。
+    /// 同じ弾を2回戻すと、後で1つの弾を2発分として取り出してしまうため
+    fn release(&mut self, commands: &mut Commands, entity: Entity) {
+        if self.free.contains(&entity) {
+            return;
+        }
+        commands
+            .entity(entity)
+            .insert((Pooled, Visibility::Hidden))
+            .remove::<Collider>();
+        self.free.push(entity);
+    }
+}
+
+/// ゲーム開始時に弾のプールを用意するシステム
+///
+/// 前回のゲームの弾は DespawnOnExit で削除済みなので、プールは作り直す
+pub(super) fn setup_enemy_bullet_pool(mut commands: Commands, mut pool: ResMut<EnemyBulletPool>) {
+    pool.free.clear();
+    for _ in 0..ENEMY_BULLET_POOL_SIZE {
+        let entity = commands
+            .spawn((
+                Sprite::from_color(ENEMY_BULLET_COLOR, ENEMY_BULLET_SIZE),
+                Transform::default(),
+                Visibility::Hidden,
+                EnemyBullet {
+                    velocity: Vec2::ZERO,
+                },
+                Pooled,
+                DespawnOnExit(GameState::Game),
+            ))
+            .id();
+        pool.free.push(entity);
+    }
+}
+
+/// 経過時間に応じた発射間隔の倍率（時間が経つほど速く撃つ）
+fn fire_rate_scale(elapsed_secs: f32) -> f32 {
+    let steps = (elapsed_secs / FIRE_RATE_STEP_SECS).floor();
+    (1.0 - steps * FIRE_RATE_STEP_AMOUNT).max(FIRE_RATE_SCALE_MIN)
+}
+
+/// 敵が射撃パターンに従って弾を撃つシステム
+pub(super) fn enemy_fire(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_elapsed_time: Res<GameElapsedTime>,
+    playfield: Res<Playfield>,
+    mut pool: ResMut<EnemyBulletPool>,
+    mut enemy_query: Query<(&Transform, &mut EnemyFire), With<Enemy>>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let window_half_height = playfield.half_size.y;
+    let scale = fire_rate_scale(game_elapsed_time.0);
+
+    for (transform, mut fire) in &mut enemy_query {
+        let origin = transform.translation.truncate();
+
+        // 画面内に入るまでは撃たない
+        if origin.y > window_half_height {
+            continue;
+        }
+
+        if !fire.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        // 一番近いプレイヤーへの向き（プレイヤーがいなければ真下）
+        let player_pos = nearest_position(
+            origin,
+            player_query.iter().map(|t| t.translation.truncate()),
+        );
+        let aim = player_pos
+            .map(|p| (p - origin).normalize_or(Vec2::NEG_Y))
+            .unwrap_or(Vec2::NEG_Y);
+
+        let next_wait = match fire.pattern {
+            FirePattern::Aimed => {
+                pool.fire(&mut commands, origin, aim * ENEMY_BULLET_SPEED);
+                fire.interval * scale
+            }
+            FirePattern::Spread { count, angle } => {
+                let count = count.max(1);
+                for i in 0..count {
+                    // 真下を中心に等間隔で角度をずらす
+                    let t = if count == 1 {
+                        0.0
+                    } else {
+                        i as f32 / (count - 1) as f32 - 0.5
+                    };
+                    let direction = Vec2::from_angle(t * angle).rotate(Vec2::NEG_Y);
+                    pool.fire(&mut commands, origin, direction * ENEMY_BULLET_SPEED);
+                }
+                fire.interval * scale
+            }
+            FirePattern::Volley { shots, gap } => {
+                pool.fire(&mut commands, origin, aim * ENEMY_BULLET_SPEED);
+                // 連射の1発目なら残り弾数を設定する
+                if fire.volley_remaining == 0 {
+                    fire.volley_remaining = shots.max(1);
+                }
+                fire.volley_remaining -= 1;
+                if fire.volley_remaining > 0 {
+                    gap
+                } else {
+                    fire.interval * scale
+                }
+            }
+        };
+
+        fire.timer = Timer::from_seconds(next_wait, TimerMode::Once);
+    }
+}
+
+/// 敵の弾を移動させ、画面外に出たらプールに戻すシステム
+pub(super) fn enemy_bullet_movement(
+    mut commands: Commands,
+    time: Res<Time>,
+    playfield: Res<Playfield>,
+    mut pool: ResMut<EnemyBulletPool>,
+    mut query: Query<(Entity, &mut Transform, &EnemyBullet), Without<Pooled>>,
+) {
+    let bounds = playfield.half_size + ENEMY_BULLET_SIZE;
+
+    for (entity, mut transform, bullet) in &mut query {
+        transform.translation += (bullet.velocity * time.delta_secs()).extend(0.0);
+
+        let pos = transform.translation.truncate();
+        if pos.x.abs() > bounds.x || pos.y.abs() > bounds.y {
+            pool.release(&mut commands, entity);
+        }
+    }
+}
+
+/// 敵の弾とプレイヤーの当たり判定処理
+pub(super) fn check_enemy_bullet_player_collision(
+    mut commands: Commands,
+    mut collisions: MessageReader<Collision>,
+    mut pool: ResMut<EnemyBulletPool>,
+    player_query: Query<(), With<Player>>,
+    bullet_query: Query<&Transform, (With<EnemyBullet>, Without<Pooled>)>,
+    mut hits: MessageWriter<PlayerHit>,
+) {
+    // このティックで当たった弾（2人に同時に当たっても1人にだけ当てる）
+    let mut released = Vec::new();
+
+    for collision in collisions.read() {
+        if !player_query.contains(collision.a) || released.contains(&collision.b) {
+            continue;
+        }
+        let Ok(bullet_transform) = bullet_query.get(collision.b) else {
+            continue;
+        };
+
+        // 当たったら弾をプールに戻し、プレイヤーに攻撃を当てる
+        pool.release(&mut commands, collision.b);
+        released.push(collision.b);
+        hits.write(PlayerHit {
+            player: collision.a,
+            source: DamageSource::EnemyBullet,
+            origin: Some(bullet_transform.translation.truncate()),
+        });
+    }
+}
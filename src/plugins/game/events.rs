@@ -0,0 +1,285 @@
+/// ゲーム内の出来事のメッセージと、それを受け取るシステム
+///
+/// 当たり判定や敵の移動は出来事をメッセージとして送るだけにし、HPの増減・得点・破片やアイテム・
+/// 効果音・ゲームの状態の遷移は、それぞれメッセージを受け取るシステムで行う
+use bevy::prelude::*;
+
+use super::boss::BOSS_SCORE;
+use super::enemy::{self, EnemyArchetype};
+use super::feedback::Invulnerable;
+use super::powerup::{self, Shield};
+use super::{ActiveStage, EnemyCount, GamePhase, GameRng, HP, Player, Score};
+use crate::plugins::audio::{PlaySfx, Sfx};
+use crate::plugins::campaign::Campaign;
+use crate::state::GameState;
+
+/// 出来事を受け取って処理するシステムセット
+///
+/// 当たり判定の結果を受け取るシステムより後に実行する
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GameEventSystems;
+
+/// 敵を倒したときのメッセージ
+#[derive(Message, Clone, Copy, Debug)]
+pub struct EnemyKilled {
+    /// 倒した敵の種類
+    pub archetype: EnemyArchetype,
+    /// 倒した位置
+    pub position: Vec2,
+    /// 倒した敵のサイズ
+    pub size: Vec2,
+    /// 倒したプレイヤーの番号
+    pub owner: usize,
+}
+
+/// プレイヤーの弾が敵やボスに当たったときのメッセージ（倒したかどうかに関わらず送る）
+#[derive(Message, Clone, Copy, Debug)]
+pub struct BulletHit {
+    /// その弾が初めて当たったかどうか（貫通する弾が何体に当たっても、命中は1発として数える）
+    pub first: bool,
+    /// 当たった敵やボスのエンティティ
+    pub target: Entity,
+    /// 当たった位置（弾の位置）
+    pub position: Vec2,
+}
+
+/// 敵が画面の下端から出ていった（取り逃がした）ときのメッセージ
+#[derive(Message, Clone, Copy, Debug)]
+pub struct EnemyEscaped {
+    /// 取り逃がした敵の種類
+    pub archetype: EnemyArchetype,
+}
+
+/// プレイヤーがダメージを受けた原因
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DamageSource {
+    /// 敵との接触
+    Collision,
+    /// 敵の弾
+    EnemyBullet,
+    /// 敵の取り逃がし
+    Escape,
+}
+
+/// プレイヤーに攻撃が当たったときのメッセージ
+///
+/// シールドや無敵時間でダメージを防ぐかどうかは受け取る側で決める
+#[derive(Message, Clone, Copy, Debug)]
+pub struct PlayerHit {
+    /// 当たったプレイヤーのエンティティ
+    pub player: Entity,
+    /// 攻撃の原因
+    pub source: DamageSource,
+    /// 攻撃の位置（取り逃がしのように位置がなければ None）
+    pub origin: Option<Vec2>,
+}
+
+/// プレイヤーが実際にダメージを受けた（HPが減った）ときのメッセージ
+#[derive(Message, Clone, Copy, Debug)]
+pub struct PlayerDamaged {
+    /// ダメージを受けたプレイヤーのエンティティ
+    pub player: Entity,
+    /// ダメージの原因
+    pub source: DamageSource,
+    /// 攻撃の位置（取り逃がしのように位置がなければ None）
+    pub origin: Option<Vec2>,
+}
+
+/// プレイヤーのHPが尽きたときのメッセージ
+#[derive(Message, Clone, Copy, Debug)]
+pub struct PlayerDied {
+    /// 倒されたプレイヤーの番号
+    pub index: usize,
+    /// 最後に受けたダメージの原因
+    pub source: DamageSource,
+}
+
+/// ボスを倒したときのメッセージ
+#[derive(Message, Clone, Copy, Debug)]
+pub struct BossDefeated {
+    /// とどめを刺したプレイヤーの番号
+    pub owner: usize,
+    /// 倒した位置
+    pub position: Vec2,
+}
+
+/// 取り逃がした敵の分だけ、HPが一番多いプレイヤーに攻撃を当てるシステム
+///
+/// 2人プレイのときは、HPが一番多いプレイヤーが肩代わりする。同じティックに何体も取り逃がしたときは、
+/// それまでに肩代わりした分を差し引いたHPで選ぶ
+pub(super) fn hit_player_on_escape(
+    mut escaped: MessageReader<EnemyEscaped>,
+    player_query: Query<(Entity, &HP), With<Player>>,
+    mut hits: MessageWriter<PlayerHit>,
+) {
+    let mut players: Vec<(Entity, u32)> = player_query
+        .iter()
+        .map(|(entity, hp)| (entity, hp.0))
+        .collect();
+
+    for _ in escaped.read() {
+        let Some((player, hp)) = players.iter_mut().max_by_key(|(_, hp)| *hp) else {
+            continue;
+        };
+        *hp = hp.saturating_sub(1);
+        hits.write(PlayerHit {
+            player: *player,
+            source: DamageSource::Escape,
+            origin: None,
+        });
+    }
+}
+
+/// 当たった攻撃の分だけプレイヤーのHPを減らし、尽きたらプレイヤーを削除するシステム
+///
+/// シールドの効果中と被弾後の無敵時間中はダメージを受けない。ダメージを受けたら無敵時間を始めるので、
+/// 同じティックに重なった攻撃で減るHPは1だけになる
+pub(super) fn apply_player_hits(
+    mut commands: Commands,
+    mut hits: MessageReader<PlayerHit>,
+    mut player_query: Query<(&Player, &mut HP, Has<Shield>, Has<Invulnerable>)>,
+    active_stage: Res<ActiveStage>,
+    mut damaged: MessageWriter<PlayerDamaged>,
+    mut died: MessageWriter<PlayerDied>,
+) {
+    // このティックでダメージを受けたプレイヤー（無敵時間のコンポーネントはまだ付いていない）
+    let mut damaged_players = Vec::new();
+
+    for hit in hits.read() {
+        let Ok((player, mut hp, shielded, invulnerable)) = player_query.get_mut(hit.player) else {
+            continue;
+        };
+        if shielded || invulnerable || damaged_players.contains(&hit.player) || hp.0 == 0 {
+            continue;
+        }
+
+        hp.0 -= 1;
+        damaged_players.push(hit.player);
+        damaged.write(PlayerDamaged {
+            player: hit.player,
+            source: hit.source,
+            origin: hit.origin,
+        });
+        if hp.0 == 0 {
+            commands.entity(hit.player).despawn();
+            died.write(PlayerDied {
+                index: player.index,
+                source: hit.source,
+            });
+        } else {
+            commands
+                .entity(hit.player)
+                .insert(Invulnerable::new(active_stage.0.invulnerability_secs));
+        }
+    }
+}
+
+/// 弾が当たった数・倒した敵と取り逃がした敵・倒したボスを得点に反映するシステム
+pub(super) fn score_events(
+    mut bullet_hits: MessageReader<BulletHit>,
+    mut killed: MessageReader<EnemyKilled>,
+    mut escaped: MessageReader<EnemyEscaped>,
+    mut boss_defeated: MessageReader<BossDefeated>,
+    mut score: ResMut<Score>,
+) {
+    // 命中率のために当たった弾を数える（貫通する弾は最初に当たったときだけ数える）
+    score.hits += bullet_hits.read().filter(|hit| hit.first).count() as u32;
+    for kill in killed.read() {
+        // アーキタイプの基本点・敵のサイズ・コンボ倍率に応じて、倒したプレイヤーに加点する
+        let stats = kill.archetype.stats();
+        score.register_kill(kill.owner, stats.score, kill.size.x / stats.size_scale);
+    }
+    // 取り逃がしたらコンボは途切れる
+    if escaped.read().count() > 0 {
+        score.break_combo();
+    }
+    for defeated in boss_defeated.read() {
+        let bonus = BOSS_SCORE * score.multiplier();
+        score.add_points(defeated.owner, bonus);
+        score.kills += 1;
+    }
+}
+
+/// 倒した敵の数だけ残り討伐数を減らすシステム（分裂した破片は数えない）
+pub(super) fn count_down_enemies(
+    mut killed: MessageReader<EnemyKilled>,
+    mut enemy_count: ResMut<EnemyCount>,
+) {
+    for kill in killed.read() {
+        if kill.archetype.stats().counts_toward_total && enemy_count.0 > 0 {
+            enemy_count.0 -= 1;
+        }
+    }
+}
+
+/// 倒した敵の位置に、分裂する敵なら破片を出し、確率でパワーアップのアイテムを落とすシステム
+pub(super) fn spawn_kill_drops(
+    mut commands: Commands,
+    mut killed: MessageReader<EnemyKilled>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    for kill in killed.read() {
+        enemy::spawn_split(&mut commands, kill.archetype, kill.position, kill.size);
+        powerup::roll_drop(&mut commands, game_rng.rng(), kill.position);
+    }
+}
+
+/// 出来事に合わせて効果音を鳴らすシステム
+pub(super) fn play_event_sfx(
+    mut killed: MessageReader<EnemyKilled>,
+    mut escaped: MessageReader<EnemyEscaped>,
+    mut damaged: MessageReader<PlayerDamaged>,
+    mut boss_defeated: MessageReader<BossDefeated>,
+    mut sfx: MessageWriter<PlaySfx>,
+) {
+    for _ in killed.read() {
+        sfx.write(PlaySfx(Sfx::Explosion));
+    }
+    for _ in escaped.read() {
+        sfx.write(PlaySfx(Sfx::EnemyEscape));
+    }
+    for _ in damaged.read() {
+        sfx.write(PlaySfx(Sfx::Damage));
+    }
+    for _ in boss_defeated.read() {
+        sfx.write(PlaySfx(Sfx::Explosion));
+    }
+}
+
+/// ボス戦・ゲームオーバー・ステージクリア・ゲームクリアへの遷移を決めるシステム
+///
+/// 状態の遷移はすべてここで決める。同じティックにボスを倒してプレイヤーも全員倒されたときは
+/// ゲームオーバーにする
+pub(super) fn decide_outcome(
+    mut boss_defeated: MessageReader<BossDefeated>,
+    player_query: Query<&HP, With<Player>>,
+    enemy_count: Res<EnemyCount>,
+    phase: Res<State<GamePhase>>,
+    campaign: Res<Campaign>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+) {
+    let boss_defeated = boss_defeated.read().count() > 0;
+
+    // プレイヤーが全員倒されたらゲームオーバー
+    if player_query.iter().all(|hp| hp.0 == 0) {
+        next_state.set(GameState::GameOver);
+        return;
+    }
+
+    // ボスを倒したらステージクリア（最後のステージならゲームクリア）
+    if boss_defeated {
+        next_state.set(if campaign.is_final_stage() {
+            GameState::GameClear
+        } else {
+            GameState::StageResult
+        });
+        return;
+    }
+
+    // 残り討伐数が0になったらボス戦へ
+    // （同じ値を設定しても OnExit・OnEnter がやり直され、ボスが出直してしまうので、変わるときだけ設定する）
+    if *phase.get() == GamePhase::Waves && enemy_count.0 == 0 {
+        NextState::set_if_neq(&mut next_phase, GamePhase::Boss);
+    }
+}
@@ -0,0 +1,223 @@
+/// 被弾後の無敵時間と被弾の演出（点滅・ノックバック・画面の揺れ・赤いフラッシュ）
+use bevy::prelude::*;
+use rand::RngExt;
+
+use super::{PLAYER_SIZE, Player, PlayerDamaged, Playfield};
+use crate::plugins::settings::Settings;
+use crate::state::GameState;
+
+/// 被弾した後の無敵時間の既定値（秒。ステージ定義で上書きできる）
+pub(super) const INVULNERABILITY_SECS: f32 = 1.5;
+/// 無敵時間中の点滅の間隔（秒）
+const BLINK_INTERVAL_SECS: f32 = 0.1;
+
+/// ノックバックの初速（ピクセル/秒）
+const KNOCKBACK_SPEED: f32 = 600.0;
+/// ノックバックが続く時間（秒。この間に速度が0まで落ちる）
+const KNOCKBACK_SECS: f32 = 0.2;
+
+/// 画面が揺れる時間（秒）
+const SHAKE_SECS: f32 = 0.3;
+/// 画面の揺れの最大の幅（ピクセル。時間とともに小さくなる）
+const SHAKE_STRENGTH: f32 = 8.0;
+
+/// 赤いフラッシュが消えるまでの時間（秒）
+const FLASH_SECS: f32 = 0.3;
+/// 赤いフラッシュの最初の不透明度
+const FLASH_ALPHA: f32 = 0.4;
+/// 赤いフラッシュの色
+const FLASH_COLOR: Color = Color::srgb(1.0, 0.0, 0.0);
+
+/// 被弾した後の無敵時間中のプレイヤーのコンポーネント
+///
+/// 効果中はどの原因の攻撃でもダメージを受けない
+#[derive(Component)]
+pub struct Invulnerable {
+    /// 残りの無敵時間
+    timer: Timer,
+}
+
+impl Invulnerable {
+    /// secs 秒の無敵時間
+    pub(super) fn new(secs: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(secs, TimerMode::Once),
+        }
+    }
+}
+
+/// 攻撃の位置から押し戻されている最中のプレイヤーのコンポーネント
+#[derive(Component)]
+pub(super) struct Knockback {
+    /// 押し戻しの初速（ピクセル/秒）
+    velocity: Vec2,
+    /// 経過時間
+    timer: Timer,
+}
+
+/// 画面の揺れの残り時間を保持するリソース
+#[derive(Resource, Default)]
+pub(super) struct ScreenShake {
+    /// 残り時間（秒）
+    remaining: f32,
+}
+
+/// 被弾したときの赤いフラッシュのコンポーネント
+#[derive(Component)]
+pub(super) struct DamageFlash {
+    /// 経過時間
+    timer: Timer,
+}
+
+/// 無敵時間を進め、切れたら点滅をやめて元の表示に戻すシステム
+///
+/// ポーズ中は実行されないので、ポーズしている間は無敵時間が減らない
+pub(super) fn tick_invulnerability(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Invulnerable, &mut Visibility)>,
+) {
+    for (entity, mut invulnerable, mut visibility) in &mut query {
+        if invulnerable.timer.tick(time.delta()).is_finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+/// 無敵時間中のプレイヤーを一定間隔で点滅させるシステム
+pub(super) fn blink_invulnerable_players(
+    mut query: Query<(&Invulnerable, &mut Visibility), With<Player>>,
+) {
+    for (invulnerable, mut visibility) in &mut query {
+        let blinks = (invulnerable.timer.elapsed_secs() / BLINK_INTERVAL_SECS) as u32;
+        visibility.set_if_neq(if blinks.is_multiple_of(2) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        });
+    }
+}
+
+/// ダメージを受けたプレイヤーを攻撃の位置から押し戻し始めるシステム
+///
+/// 取り逃がしのように攻撃の位置がないダメージでは押し戻さない
+pub(super) fn start_knockback(
+    mut commands: Commands,
+    mut damaged: MessageReader<PlayerDamaged>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    for damage in damaged.read() {
+        let (Some(origin), Ok(transform)) = (damage.origin, player_query.get(damage.player)) else {
+            continue;
+        };
+        let direction = (transform.translation.truncate() - origin).normalize_or(Vec2::NEG_Y);
+        commands.entity(damage.player).try_insert(Knockback {
+            velocity: direction * KNOCKBACK_SPEED,
+            timer: Timer::from_seconds(KNOCKBACK_SECS, TimerMode::Once),
+        });
+    }
+}
+
+/// 押し戻し中のプレイヤーを動かし、時間が来たら止めるシステム
+///
+/// 速度は時間とともに0まで落とし、画面の外には出さない
+pub(super) fn apply_knockback(
+    mut commands: Commands,
+    time: Res<Time>,
+    playfield: Res<Playfield>,
+    mut query: Query<(Entity, &mut Transform, &mut Knockback)>,
+) {
+    let half = playfield.half_size - PLAYER_SIZE / 2.0;
+
+    for (entity, mut transform, mut knockback) in &mut query {
+        let speed_scale = knockback.timer.fraction_remaining();
+        let step = knockback.velocity * speed_scale * time.delta_secs();
+        transform.translation.x = (transform.translation.x + step.x).clamp(-half.x, half.x);
+        transform.translation.y = (transform.translation.y + step.y).clamp(-half.y, half.y);
+
+        if knockback.timer.tick(time.delta()).is_finished() {
+            commands.entity(entity).remove::<Knockback>();
+        }
+    }
+}
+
+/// ダメージを受けたら、画面を揺らして赤いフラッシュを出すシステム
+///
+/// 画面の揺れは設定で無効にできる
+pub(super) fn start_hit_effects(
+    mut commands: Commands,
+    mut damaged: MessageReader<PlayerDamaged>,
+    settings: Res<Settings>,
+    mut shake: ResMut<ScreenShake>,
+) {
+    if damaged.read().count() == 0 {
+        return;
+    }
+
+    if settings.screen_shake {
+        shake.remaining = SHAKE_SECS;
+    }
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        BackgroundColor(FLASH_COLOR.with_alpha(FLASH_ALPHA)),
+        // ポーズ画面（ZIndex 100）より下に出す
+        ZIndex(50),
+        DamageFlash {
+            timer: Timer::from_seconds(FLASH_SECS, TimerMode::Once),
+        },
+        DespawnOnExit(GameState::Game),
+    ));
+}
+
+/// 揺れの残り時間に応じてカメラをランダムにずらし、終わったら元の位置に戻すシステム
+///
+/// 見た目だけの揺れなので、ゲームの乱数（リプレイで再現する乱数）は使わない
+pub(super) fn shake_camera(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut shake: ResMut<ScreenShake>,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+) {
+    // 揺れの途中で設定を無効にしたら、すぐに止める
+    if !settings.screen_shake {
+        shake.remaining = 0.0;
+    }
+    shake.remaining = (shake.remaining - time.delta_secs()).max(0.0);
+
+    let strength = SHAKE_STRENGTH * shake.remaining / SHAKE_SECS;
+    let offset = if strength > 0.0 {
+        let mut rng = rand::rng();
+        Vec2::new(
+            rng.random_range(-strength..=strength),
+            rng.random_range(-strength..=strength),
+        )
+    } else {
+        Vec2::ZERO
+    };
+
+    for mut transform in &mut camera_query {
+        transform.translation.x = offset.x;
+        transform.translation.y = offset.y;
+    }
+}
+
+/// 赤いフラッシュを時間とともに薄くし、消えたら削除するシステム
+pub(super) fn fade_damage_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut DamageFlash, &mut BackgroundColor)>,
+) {
+    for (entity, mut flash, mut background) in &mut query {
+        if flash.timer.tick(time.delta()).is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        background.0 = FLASH_COLOR.with_alpha(FLASH_ALPHA * flash.timer.fraction_remaining());
+    }
+}
@@ -0,0 +1,66 @@
+/// 固定タイムステップで動かす物体の描画位置の補間
+///
+/// ゲームの処理は FixedUpdate で一定間隔ごとに進めるため、そのままでは描画のフレームと
+/// ずれて動きがカクつく。直前の2ティックの位置を覚えておき、描画の前に
+/// ティックの進み具合に応じて間の位置へ動かす。次のティックの前には元の位置に戻す
+use bevy::ecs::lifecycle::HookContext;
+use bevy::ecs::world::DeferredWorld;
+use bevy::prelude::*;
+
+/// 位置を補間して描画するコンポーネント
+///
+/// 追加したとき（プールから再利用するときも含む）は、その時点の Transform から始める
+#[derive(Component, Default)]
+#[component(on_insert = snap_to_transform)]
+pub struct Interpolated {
+    /// 1つ前のティックの終わりの位置と向き
+    previous: (Vec3, Quat),
+    /// 最後のティックの終わりの位置と向き
+    current: (Vec3, Quat),
+}
+
+/// Interpolated を追加したときに、補間の始点と終点を今の Transform に合わせる
+fn snap_to_transform(mut world: DeferredWorld, HookContext { entity, .. }: HookContext) {
+    let Some(transform) = world.get::<Transform>(entity).copied() else {
+        return;
+    };
+    if let Some(mut interpolated) = world.get_mut::<Interpolated>(entity) {
+        interpolated.previous = (transform.translation, transform.rotation);
+        interpolated.current = interpolated.previous;
+    }
+}
+
+/// 固定タイムステップの処理の前に、補間で動かした描画位置をティックの終わりの位置に戻すシステム
+pub(super) fn restore_simulated_transforms(mut query: Query<(&mut Transform, &Interpolated)>) {
+    for (mut transform, interpolated) in &mut query {
+        (transform.translation, transform.rotation) = interpolated.current;
+    }
+}
+
+/// ティックの始めに、直前のティックの終わりの位置を覚えるシステム
+pub(super) fn store_previous_transforms(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in &mut query {
+        interpolated.previous = (transform.translation, transform.rotation);
+    }
+}
+
+/// ティックの終わりに、そのティックで動いた後の位置を覚えるシステム
+pub(super) fn store_current_transforms(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in &mut query {
+        interpolated.current = (transform.translation, transform.rotation);
+    }
+}
+
+/// 描画の前に、ティックの進み具合に応じて2ティックの間の位置へ動かすシステム
+pub(super) fn interpolate_transforms(
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&mut Transform, &Interpolated)>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    for (mut transform, interpolated) in &mut query {
+        let (previous_translation, previous_rotation) = interpolated.previous;
+        let (current_translation, current_rotation) = interpolated.current;
+        transform.translation = previous_translation.lerp(current_translation, alpha);
+        transform.rotation = previous_rotation.slerp(current_rotation, alpha);
+    }
+}
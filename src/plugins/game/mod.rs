@@ -3,8 +3,48 @@ use bevy::prelude::*;
 use rand::RngExt;
 
 use crate::font::DefaultFont;
+use crate::plugins::audio::{PlaySfx, Sfx};
+use crate::plugins::controls::ControlsMenuState;
+use crate::plugins::input::{Action, ActionState, FixedActionState};
+use crate::plugins::locale::LocalizedText;
+use crate::plugins::settings::SettingsMenuState;
 use crate::state::GameState;
 
+mod animation;
+mod boss;
+mod collision;
+mod enemy;
+mod enemy_bullet;
+mod events;
+mod feedback;
+mod interpolation;
+mod particles;
+mod powerup;
+mod rng;
+mod score;
+mod stage;
+mod weapon;
+
+pub use animation::SpriteAnimationPlugin;
+use animation::{AnimationClip, SpriteAnimation};
+pub use boss::Boss;
+pub use collision::{Collider, ColliderShape, Collision, CollisionLayers, CollisionSystems, Swept};
+pub use enemy::{Enemy, EnemyArchetype, spawn_enemy};
+use enemy::{EnemyMovement, Health};
+pub use enemy_bullet::{EnemyBullet, Pooled};
+pub use events::{
+    BossDefeated, BulletHit, DamageSource, EnemyEscaped, EnemyKilled, GameEventSystems,
+    PlayerDamaged, PlayerDied, PlayerHit,
+};
+pub use feedback::Invulnerable;
+use interpolation::Interpolated;
+use powerup::PiercingBullet;
+pub use powerup::{Piercing, PowerUpKind, RapidCharge, Shield, SpreadShot, spawn_power_up};
+pub use rng::GameRng;
+pub use score::{Score, result_breakdown};
+pub use stage::{ActiveStage, StageDefinition, stages_loaded};
+pub use weapon::{BULLET_SIZE, Bullet, bullet_collider};
+
 /// ゲームプレイのプラグイン
 pub struct GamePlugin;
 
@@ -12,46 +52,288 @@ impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             OnEnter(GameState::Game),
-            (setup_camera, setup_ui, setup_player, reset_game_elapsed),
+            (
+                setup_camera,
+                setup_ui,
+                setup_player,
+                reset_game_elapsed,
+                enemy_bullet::setup_enemy_bullet_pool,
+                particles::setup_particle_pool,
+                stage::reset_stage_progress,
+            ),
         );
+        app.init_asset::<stage::StageDefinition>();
+        app.init_asset_loader::<stage::StageLoader>();
+        app.init_resource::<ActiveStage>();
+        app.init_resource::<stage::StageProgress>();
+        app.add_systems(Startup, stage::load_stages);
+        app.init_asset::<particles::ParticleLibrary>();
+        app.init_asset_loader::<particles::ParticleLoader>();
+        app.init_resource::<particles::ParticlePool>();
+        app.add_systems(Startup, particles::load_particle_library);
+        app.add_systems(PostUpdate, stage::sync_active_stage);
+        // スコアはキャンペーンの開始時（タイトルや結果画面から出るとき）にリセットし、ステージ間では引き継ぐ
+        app.add_systems(OnExit(GameState::Title), score::reset_score);
+        app.add_systems(OnExit(GameState::GameOver), score::reset_score);
+        app.add_systems(OnExit(GameState::GameClear), score::reset_score);
         app.init_resource::<EnemySpawnTimer>();
         app.init_resource::<EnemyCount>();
         app.init_resource::<GameElapsedTime>();
+        app.init_resource::<Score>();
+        app.init_resource::<PlayerCount>();
+        app.add_message::<EnemyKilled>();
+        app.add_message::<EnemyEscaped>();
+        app.add_message::<PlayerHit>();
+        app.add_message::<PlayerDamaged>();
+        app.add_message::<PlayerDied>();
+        app.add_message::<BossDefeated>();
+        app.add_message::<BulletHit>();
+        app.init_resource::<feedback::ScreenShake>();
+        app.add_message::<Collision>();
+        app.init_resource::<collision::ColliderSequence>();
+        app.init_resource::<enemy_bullet::EnemyBulletPool>();
         app.add_sub_state::<PauseState>();
+        app.add_sub_state::<GamePhase>();
+        app.add_systems(OnEnter(GamePhase::Boss), boss::spawn_boss);
         app.add_systems(OnEnter(PauseState::Paused), setup_pause_ui);
         app.add_systems(OnExit(PauseState::Paused), despawn_pause_ui);
-        app.add_systems(Update, pause_update.run_if(in_state(PauseState::Paused)));
-        app.add_systems(Update, toggle_pause.run_if(in_state(PauseState::Running)));
         app.add_systems(
             Update,
+            pause_update.run_if(
+                in_state(PauseState::Paused)
+                    .and(in_state(ControlsMenuState::Closed))
+                    .and(in_state(SettingsMenuState::Closed)),
+            ),
+        );
+        app.add_systems(Update, toggle_pause.run_if(in_state(PauseState::Running)));
+        // ゲームの処理は一定間隔のティックで進め、描画の前に位置を補間する
+        app.insert_resource(Time::<Fixed>::from_hz(FIXED_TIMESTEP_HZ));
+        app.init_resource::<rng::GameRng>();
+        app.add_systems(OnExit(GameState::Title), rng::start_run);
+        app.add_systems(OnExit(GameState::GameOver), rng::start_run);
+        app.add_systems(OnExit(GameState::GameClear), rng::start_run);
+        app.init_resource::<Playfield>();
+        app.add_systems(
+            OnEnter(GameState::Game),
+            (rng::start_stage, measure_playfield).in_set(StageStartSystems),
+        );
+        app.add_systems(
+            OnEnter(GameState::Game),
+            stage::check_stage_layout.after(StageStartSystems),
+        );
+        app.add_systems(
+            RunFixedMainLoop,
+            (
+                interpolation::restore_simulated_transforms
+                    .in_set(RunFixedMainLoopSystems::BeforeFixedMainLoop),
+                interpolation::interpolate_transforms
+                    .in_set(RunFixedMainLoopSystems::AfterFixedMainLoop),
+            ),
+        );
+        app.add_systems(
+            FixedFirst,
+            (
+                apply_state_transitions,
+                (
+                    interpolation::store_previous_transforms,
+                    collision::store_sweep_origins,
+                ),
+            )
+                .chain(),
+        );
+        app.add_systems(FixedLast, interpolation::store_current_transforms);
+        // 物体を動かしてから当たり判定を行い、当たった組を受け取って処理する。
+        // 同じ操作なら同じ展開になるよう、当たり判定までのシステムはすべて決まった順に実行する
+        app.add_systems(
+            FixedUpdate,
             (
                 tick_game_elapsed,
-                player_movement,
-                charge_bullets,
-                shoot_bullet,
-                bullet_movement,
-                enemy_spawner,
+                (
+                    (player_movement, feedback::apply_knockback).chain(),
+                    charge_bullets,
+                    weapon::cycle_weapon,
+                    // 飛んでいる弾を動かしてから撃つ（撃った弾は撃った位置から次のティックで動き出す）
+                    weapon::steer_homing,
+                    weapon::bullet_movement,
+                    weapon::fire_weapon,
+                )
+                    .chain(),
+                enemy_spawner.run_if(in_state(GamePhase::Waves)),
+                stage::run_stage_waves.run_if(in_state(GamePhase::Waves)),
                 enemy_movement,
-                check_player_enemy_collision,
+                enemy_bullet::enemy_bullet_movement,
+                enemy_bullet::enemy_fire,
+                powerup::power_up_movement,
+            )
+                .chain()
+                .before(CollisionSystems)
+                .run_if(in_state(PauseState::Running)),
+        );
+        app.add_systems(
+            FixedUpdate,
+            collision::detect_collisions
+                .in_set(CollisionSystems)
+                .run_if(in_state(PauseState::Running)),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (
+                // 弾で倒された敵にはプレイヤーが当たらないよう、弾の当たりを先に処理する
+                check_player_enemy_collision.after(check_bullet_enemy_collisions),
                 check_bullet_enemy_collisions,
+                // 同じティックに敵と敵の弾の両方に当たったときは、敵に当たったものとして扱う
+                enemy_bullet::check_enemy_bullet_player_collision
+                    .after(check_player_enemy_collision),
+                powerup::collect_power_ups,
+                // 同じティックに敵とボスの両方に当たった弾を、二重に数えないよう敵の後に処理する
+                boss::check_bullet_boss_collisions
+                    .after(check_bullet_enemy_collisions)
+                    .run_if(in_state(GamePhase::Boss)),
+            )
+                .after(CollisionSystems)
+                .before(GameEventSystems)
+                .run_if(in_state(PauseState::Running)),
+        );
+        // 出来事を受け取り、HP・得点・破片やアイテム・効果音に反映してから遷移を決める
+        app.add_systems(
+            FixedUpdate,
+            (
+                events::hit_player_on_escape,
+                events::apply_player_hits,
+                (
+                    events::score_events,
+                    events::count_down_enemies,
+                    events::spawn_kill_drops,
+                    events::play_event_sfx,
+                    feedback::start_knockback,
+                    feedback::start_hit_effects,
+                    particles::emit_event_particles,
+                ),
+                events::decide_outcome,
+            )
+                .chain()
+                .in_set(GameEventSystems)
+                .run_if(in_state(PauseState::Running)),
+        );
+        // 効果や無敵時間を外すのは、このティックでそれを見るシステム（発射・チャージ・被弾）がすべて終わってから行う。
+        // 順番を決めないと、切れるティックに効果が残るかどうかが実行のたびに変わり、同じ操作でも展開が変わる
+        app.add_systems(
+            FixedUpdate,
+            (
+                powerup::tick_effect::<SpreadShot>,
+                powerup::tick_effect::<RapidCharge>,
+                powerup::tick_effect::<Piercing>,
+                powerup::tick_effect::<Shield>,
+                feedback::tick_invulnerability,
+            )
+                .after(GameEventSystems)
+                .run_if(in_state(PauseState::Running)),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (boss::boss_movement, boss::boss_attack)
+                .chain()
+                .after(powerup::power_up_movement)
+                .before(CollisionSystems)
+                .run_if(in_state(PauseState::Running).and(in_state(GamePhase::Boss))),
+        );
+        app.add_systems(
+            Update,
+            (
+                weapon::update_weapon_ui,
                 update_enemy_count_ui,
+                score::update_score_ui,
                 update_hp_ui,
                 update_bullet_ui,
+                powerup::update_effect_gauge::<SpreadShot>,
+                powerup::update_effect_gauge::<RapidCharge>,
+                powerup::update_effect_gauge::<Piercing>,
+                powerup::update_effect_gauge::<Shield>,
+                feedback::blink_invulnerable_players,
+                feedback::shake_camera,
+                feedback::fade_damage_flash,
+                particles::emit_engine_trails,
+                particles::update_particles,
             )
                 .run_if(in_state(PauseState::Running)),
         );
+        app.add_systems(
+            Update,
+            boss::update_boss_hp_ui
+                .run_if(in_state(PauseState::Running).and(in_state(GamePhase::Boss))),
+        );
+    }
+}
+
+/// ゲームの処理を進める間隔（1秒あたりのティック数）
+pub const FIXED_TIMESTEP_HZ: f64 = 60.0;
+
+/// ティックの始めに、直前のティックで予約された状態の遷移を行うシステム
+///
+/// 通常の遷移はフレームに1回しか行われないため、ティックの中で予約した遷移（ボス戦の開始など）が
+/// 何ティック後に反映されるかがフレームの刻み方で変わってしまう。遷移をティックの単位で行い、
+/// 同じ操作なら同じティックで遷移するようにする。
+///
+/// StateTransition はすべての状態の遷移をまとめて行うスケジュールで、予約がなければ何もしない。
+/// そのため、フレームごとの通常の遷移（PreUpdate の後）と二重に実行しても同じ遷移が二度起きることはなく、
+/// ティックのないフレームでもメニューなどの遷移は通常どおり行われる
+fn apply_state_transitions(world: &mut World) {
+    world.run_schedule(StateTransition);
+}
+
+/// ステージの開始時に、乱数と画面の広さを決めるシステムセット
+///
+/// リプレイの記録・再生はこのセットの後で行う
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StageStartSystems;
+
+/// ウィンドウがないときに使う画面の広さ（中心から画面端までの距離）
+const PLAYFIELD_HALF_SIZE_DEFAULT: Vec2 = Vec2::splat(400.0);
+
+/// ゲームの処理で使う画面の広さを保持するリソース
+///
+/// プレイ中にウィンドウの大きさを変えても展開が変わらないよう、ステージの開始時に測って固定する
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct Playfield {
+    /// 中心から画面端までの距離
+    pub half_size: Vec2,
+}
+
+impl Default for Playfield {
+    fn default() -> Self {
+        Self {
+            half_size: PLAYFIELD_HALF_SIZE_DEFAULT,
+        }
+    }
+}
+
+/// ステージの開始時にウィンドウの大きさから画面の広さを決めるシステム
+fn measure_playfield(window_query: Query<&Window>, mut playfield: ResMut<Playfield>) {
+    if let Ok(window) = window_query.single() {
+        playfield.half_size = Vec2::new(window.width(), window.height()) / 2.0;
     }
 }
 
 /// ポーズ状態（GameState::Gameのサブステート）
 #[derive(SubStates, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
 #[source(GameState = GameState::Game)]
-enum PauseState {
+pub enum PauseState {
     #[default]
     Running,
     Paused,
 }
 
+/// ゲームの進行段階（GameState::Gameのサブステート）
+#[derive(SubStates, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+#[source(GameState = GameState::Game)]
+enum GamePhase {
+    /// 通常の敵が出現する段階
+    #[default]
+    Waves,
+    /// 通常の敵を倒しきった後のボス戦
+    Boss,
+}
+
 /// ポーズUI用マーカーコンポーネント
 #[derive(Component)]
 struct PauseScreen;
@@ -76,7 +358,7 @@ fn setup_pause_ui(mut commands: Commands, asset: Res<DefaultFont>) {
         .with_children(|parent| {
             // PAUSED テキスト
             parent.spawn((
-                Text::new("PAUSED"),
+                LocalizedText::new("pause.title"),
                 TextFont {
                     font: asset.font.clone(),
                     font_size: 80.0,
@@ -91,7 +373,7 @@ fn setup_pause_ui(mut commands: Commands, asset: Res<DefaultFont>) {
 
             // 操作説明
             parent.spawn((
-                Text::new("Escapeで続行"),
+                LocalizedText::new("pause.resume"),
                 TextFont {
                     font: asset.font.clone(),
                     font_size: 36.0,
@@ -105,7 +387,21 @@ fn setup_pause_ui(mut commands: Commands, asset: Res<DefaultFont>) {
             ));
 
             parent.spawn((
-                Text::new("Enterでタイトルへ"),
+                LocalizedText::new("pause.to_title"),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 36.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                Node {
+                    margin: UiRect::bottom(Val::Px(16.0)),
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                LocalizedText::new("pause.menus"),
                 TextFont {
                     font: asset.font.clone(),
                     font_size: 36.0,
@@ -123,53 +419,65 @@ fn despawn_pause_ui(mut commands: Commands, query: Query<Entity, With<PauseScree
     }
 }
 
-/// Escapeキーでポーズを開始する（Running中のみ）
-fn toggle_pause(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    mut next_pause: ResMut<NextState<PauseState>>,
-) {
-    if keyboard.just_pressed(KeyCode::Escape) {
+/// ポーズ操作（Escape・Start）でポーズを開始する（Running中のみ）
+fn toggle_pause(actions: Res<ActionState>, mut next_pause: ResMut<NextState<PauseState>>) {
+    if actions.any_just_pressed(Action::Pause) {
         next_pause.set(PauseState::Paused);
     }
 }
 
-/// ポーズ中の操作処理
+/// ポーズ中の操作処理（操作設定画面を開いている間は実行しない）
 fn pause_update(
-    keyboard: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     mut next_pause: ResMut<NextState<PauseState>>,
     mut next_game: ResMut<NextState<GameState>>,
+    mut next_controls: ResMut<NextState<ControlsMenuState>>,
+    mut next_settings: ResMut<NextState<SettingsMenuState>>,
 ) {
-    if keyboard.just_pressed(KeyCode::Escape) {
-        // Escapeで続行
+    if actions.any_just_pressed(Action::Controls) {
+        // 操作設定画面・設定画面をポーズ画面の上に開く
+        next_controls.set(ControlsMenuState::Open);
+    } else if actions.any_just_pressed(Action::Settings) {
+        next_settings.set(SettingsMenuState::Open);
+    } else if actions.any_just_pressed(Action::Pause) {
+        // ポーズ操作で続行
         next_pause.set(PauseState::Running);
-    } else if keyboard.just_pressed(KeyCode::Enter) {
-        // Enterでタイトルへ戻る
+    } else if actions.any_just_pressed(Action::Confirm) {
+        // 決定操作でタイトルへ戻る
         next_game.set(GameState::Title);
     }
 }
 
-/// カメラのセットアップ
-fn setup_camera(mut commands: Commands) {
-    commands.spawn((Camera2d, DespawnOnExit(GameState::Game)));
+/// カメラのセットアップ（背景はステージ定義の色）
+fn setup_camera(mut commands: Commands, active_stage: Res<ActiveStage>) {
+    commands.spawn((
+        Camera2d,
+        Camera {
+            clear_color: ClearColorConfig::Custom(active_stage.0.background_color()),
+            ..default()
+        },
+        DespawnOnExit(GameState::Game),
+    ));
 }
 
 /// スコアのUI用マーカーコンポーネント
 #[derive(Component)]
 struct ScoreText;
 
-/// HP アイコン行のマーカーコンポーネント
+/// HP アイコン行のコンポーネント（何人目のプレイヤーの表示か）
 #[derive(Component)]
-struct HpIcons;
+struct HpIcons(usize);
 
-/// 残弾アイコン行のマーカーコンポーネント
+/// 残弾アイコン行のコンポーネント（何人目のプレイヤーの表示か）
 #[derive(Component)]
-struct BulletIcons;
+struct BulletIcons(usize);
 
-/// 残り討伐数を保持するリソース（100体からカウントダウン）
+/// 残り討伐数の既定値（ステージ定義で上書きできる）
 const ENEMY_TOTAL: u32 = 100;
 
+/// 残り討伐数を保持するリソース（ステージの討伐数からカウントダウン）
 #[derive(Resource)]
-struct EnemyCount(u32);
+pub struct EnemyCount(pub u32);
 
 impl Default for EnemyCount {
     fn default() -> Self {
@@ -183,7 +491,12 @@ const ICON_SIZE: f32 = 20.0;
 const ICON_MARGIN: f32 = 4.0;
 
 /// ゲーム画面のUIセットアップ
-fn setup_ui(mut commands: Commands, asset: Res<DefaultFont>) {
+fn setup_ui(
+    mut commands: Commands,
+    asset: Res<DefaultFont>,
+    active_stage: Res<ActiveStage>,
+    player_count: Res<PlayerCount>,
+) {
     commands
         .spawn((
             Node {
@@ -197,126 +510,246 @@ fn setup_ui(mut commands: Commands, asset: Res<DefaultFont>) {
             DespawnOnExit(GameState::Game),
         ))
         .with_children(|parent| {
-            // 残り敵数表示
+            // 残り敵数・スコア表示行
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::bottom(Val::Px(6.0)),
+                    ..default()
+                })
+                .with_children(|row| {
+                    // 残り敵数表示
+                    row.spawn((
+                        LocalizedText::new("hud.enemy")
+                            .with_arg("count", active_stage.0.enemy_total),
+                        TextFont {
+                            font: asset.font.clone(),
+                            font_size: 30.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                        Node {
+                            margin: UiRect::right(Val::Px(24.0)),
+                            ..default()
+                        },
+                        ScoreText,
+                    ));
+
+                    // 獲得点数表示
+                    row.spawn((
+                        score::points_label(&Score::default()),
+                        TextFont {
+                            font: asset.font.clone(),
+                            font_size: 30.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                        score::PointsText,
+                    ));
+                });
+
+            // コンボ表示
             parent.spawn((
-                Text::new(format!("ENEMY: {}", ENEMY_TOTAL)),
+                score::combo_label(&Score::default()),
                 TextFont {
                     font: asset.font.clone(),
-                    font_size: 30.0,
+                    font_size: 22.0,
                     ..default()
                 },
-                TextColor(Color::WHITE),
+                TextColor(Color::srgb(1.0, 0.85, 0.0)),
                 Node {
                     margin: UiRect::bottom(Val::Px(6.0)),
                     ..default()
                 },
-                ScoreText,
+                score::ComboText,
             ));
 
+            // プレイヤーごとの表示
+            for index in 0..player_count.0 {
+                spawn_player_hud(parent, &asset, index, player_count.0 > 1);
+            }
+        });
+}
+
+/// プレイヤー1人分の HUD（HP・残弾・武器・パワーアップ）を作る
+///
+/// 2人プレイのときは誰の表示か分かるように見出しを付ける
+fn spawn_player_hud(
+    parent: &mut ChildSpawnerCommands,
+    asset: &DefaultFont,
+    index: usize,
+    show_label: bool,
+) {
+    parent
+        .spawn(Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::FlexStart,
+            margin: UiRect::bottom(Val::Px(10.0)),
+            ..default()
+        })
+        .with_children(|block| {
+            // 見出し
+            if show_label {
+                block.spawn((
+                    LocalizedText::new("hud.player").with_arg("player", index + 1),
+                    TextFont {
+                        font: asset.font.clone(),
+                        font_size: 20.0,
+                        ..default()
+                    },
+                    TextColor(PLAYER_COLORS[index]),
+                    Node {
+                        margin: UiRect::bottom(Val::Px(4.0)),
+                        ..default()
+                    },
+                ));
+            }
+
             // HP アイコン行
-            parent.spawn((
+            block.spawn((
                 Node {
                     flex_direction: FlexDirection::Row,
                     align_items: AlignItems::Center,
                     margin: UiRect::bottom(Val::Px(4.0)),
                     ..default()
                 },
-                HpIcons,
+                HpIcons(index),
             ));
 
             // 残弾アイコン行
-            parent.spawn((
+            block.spawn((
                 Node {
                     flex_direction: FlexDirection::Row,
                     align_items: AlignItems::Center,
                     ..default()
                 },
-                BulletIcons,
+                BulletIcons(index),
             ));
+
+            // 装備中の武器の表示
+            weapon::spawn_weapon_label(block, asset, index);
+
+            // パワーアップの効果ゲージ行
+            powerup::spawn_power_up_icons(block, index);
         });
 }
 
 /// 残り敵数のUI表示を更新するシステム
 fn update_enemy_count_ui(
     enemy_count: Res<EnemyCount>,
-    mut query: Query<&mut Text, With<ScoreText>>,
+    mut query: Query<&mut LocalizedText, With<ScoreText>>,
 ) {
-    if enemy_count.is_changed() {
-        if let Ok(mut text) = query.single_mut() {
-            **text = format!("ENEMY: {}", enemy_count.0);
-        }
+    if enemy_count.is_changed()
+        && let Ok(mut text) = query.single_mut()
+    {
+        text.set_arg("count", enemy_count.0);
     }
 }
 
-/// HP アイコンを再描画するシステム
-fn update_hp_ui(
-    mut commands: Commands,
-    player_query: Query<&HP, (With<Player>, Changed<HP>)>,
-    icons_query: Query<Entity, With<HpIcons>>,
-) {
-    let Ok(hp) = player_query.single() else {
-        return;
-    };
-    let Ok(container) = icons_query.single() else {
-        return;
-    };
-
-    // 既存の子エンティティをすべて削除して再描画
+/// アイコン行の子エンティティをすべて削除し、count 個のアイコンで再描画する
+fn redraw_icons(commands: &mut Commands, container: Entity, count: u32, width: f32, color: Color) {
     commands.entity(container).despawn_related::<Children>();
 
     commands.entity(container).with_children(|parent| {
-        for _ in 0..hp.0 {
+        for _ in 0..count {
             parent.spawn((
                 Node {
-                    width: Val::Px(ICON_SIZE),
+                    width: Val::Px(width),
                     height: Val::Px(ICON_SIZE),
                     margin: UiRect::right(Val::Px(ICON_MARGIN)),
                     ..default()
                 },
-                BackgroundColor(Color::WHITE),
+                BackgroundColor(color),
             ));
         }
     });
 }
 
+/// HP アイコンを再描画するシステム
+///
+/// 倒されたプレイヤーの行は空にする
+fn update_hp_ui(
+    mut commands: Commands,
+    player_query: Query<(&Player, Ref<HP>)>,
+    icons_query: Query<(Entity, &HpIcons, Option<&Children>)>,
+) {
+    for (container, icons, children) in &icons_query {
+        match player_query
+            .iter()
+            .find(|(player, _)| player.index == icons.0)
+        {
+            Some((_, hp)) if hp.is_changed() => {
+                redraw_icons(&mut commands, container, hp.0, ICON_SIZE, Color::WHITE);
+            }
+            None if children.is_some_and(|c| !c.is_empty()) => {
+                commands.entity(container).despawn_related::<Children>();
+            }
+            _ => {}
+        }
+    }
+}
+
 /// 残弾アイコンを再描画するシステム
+///
+/// 倒されたプレイヤーの行は空にする
 fn update_bullet_ui(
     mut commands: Commands,
-    player_query: Query<&BulletStock, (With<Player>, Changed<BulletStock>)>,
-    icons_query: Query<Entity, With<BulletIcons>>,
+    player_query: Query<(&Player, Ref<BulletStock>)>,
+    icons_query: Query<(Entity, &BulletIcons, Option<&Children>)>,
 ) {
-    let Ok(stock) = player_query.single() else {
-        return;
-    };
-    let Ok(container) = icons_query.single() else {
-        return;
-    };
-
-    // 既存の子エンティティをすべて削除して再描画
-    commands.entity(container).despawn_related::<Children>();
-
-    commands.entity(container).with_children(|parent| {
-        for _ in 0..stock.current {
-            parent.spawn((
-                Node {
-                    width: Val::Px(ICON_SIZE * 0.6),
-                    height: Val::Px(ICON_SIZE),
-                    margin: UiRect::right(Val::Px(ICON_MARGIN)),
-                    ..default()
-                },
-                BackgroundColor(Color::srgb(1.0, 0.85, 0.0)),
-            ));
+    for (container, icons, children) in &icons_query {
+        match player_query
+            .iter()
+            .find(|(player, _)| player.index == icons.0)
+        {
+            Some((_, stock)) if stock.is_changed() => {
+                redraw_icons(
+                    &mut commands,
+                    container,
+                    stock.current,
+                    ICON_SIZE * 0.6,
+                    Color::srgb(1.0, 0.85, 0.0),
+                );
+            }
+            None if children.is_some_and(|c| !c.is_empty()) => {
+                commands.entity(container).despawn_related::<Children>();
+            }
+            _ => {}
         }
-    });
+    }
 }
 
-/// プレイヤーのマーカーコンポーネント
+/// プレイヤーのコンポーネント（何人目のプレイヤーか）
 #[derive(Component)]
-pub struct Player;
+#[require(Interpolated)]
+pub struct Player {
+    /// 0 が1P、1 が2P
+    pub index: usize,
+}
+
+/// 同時に遊べるプレイヤーの最大人数
+pub const MAX_PLAYERS: usize = 2;
+
+/// 遊ぶ人数を保持するリソース（タイトル画面で切り替える）
+#[derive(Resource)]
+pub struct PlayerCount(pub usize);
+
+impl Default for PlayerCount {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// プレイヤーごとの機体の色
+const PLAYER_COLORS: [Color; MAX_PLAYERS] = [Color::WHITE, Color::srgb(0.4, 0.8, 1.0)];
+
+/// 2人プレイのときのプレイヤーの初期位置の左右の間隔
+const PLAYER_SPACING: f32 = 200.0;
 
 /// HPコンポーネント
 #[derive(Component)]
-pub struct HP(u32);
+pub struct HP(pub u32);
 
 /// プレイヤーの移動速度（ピクセル/秒）
 const PLAYER_SPEED: f32 = 350.0;
@@ -328,12 +761,14 @@ const PLAYER_HP: u32 = 3;
 const MAX_BULLET_STOCK: u32 = 3;
 /// 弾が1発チャージされるまでの秒数
 const BULLET_CHARGE_SECS: f32 = 1.0;
+/// RapidCharge の効果中のチャージ時間の倍率
+const RAPID_CHARGE_SCALE: f32 = 0.4;
 
 /// 弾のストックを管理するコンポーネント
 #[derive(Component)]
-struct BulletStock {
+pub struct BulletStock {
     /// 現在の残弾数
-    current: u32,
+    pub current: u32,
     /// 次のチャージまでの経過時間（秒）
     charge_timer: f32,
 }
@@ -347,22 +782,60 @@ impl Default for BulletStock {
     }
 }
 
-/// プレイヤーのセットアップ
-fn setup_player(mut commands: Commands) {
-    commands.spawn((
-        Sprite::from_color(Color::WHITE, PLAYER_SIZE),
-        Transform::from_xyz(0.0, -250.0, 0.0),
-        Player,
-        HP(PLAYER_HP),
-        BulletStock::default(),
-        DespawnOnExit(GameState::Game),
-    ));
+/// プレイヤーのセットアップ（遊ぶ人数分を横に並べる）
+fn setup_player(mut commands: Commands, player_count: Res<PlayerCount>, mut score: ResMut<Score>) {
+    let count = player_count.0.clamp(1, MAX_PLAYERS);
+    score.players = count;
+
+    for (index, color) in PLAYER_COLORS.into_iter().enumerate().take(count) {
+        let x = (index as f32 - (count - 1) as f32 / 2.0) * PLAYER_SPACING;
+        commands.spawn((
+            Sprite::from_color(color, PLAYER_SIZE),
+            SpriteAnimation::new(AnimationClip::PlayerIdle),
+            Transform::from_xyz(x, -250.0, 0.0),
+            Player { index },
+            HP(PLAYER_HP),
+            BulletStock::default(),
+            weapon::Weapon::default(),
+            weapon::WeaponCooldown::default(),
+            Collider::aabb(
+                PLAYER_SIZE,
+                CollisionLayers::PLAYER,
+                CollisionLayers::ENEMY | CollisionLayers::ENEMY_BULLET | CollisionLayers::POWER_UP,
+            ),
+            DespawnOnExit(GameState::Game),
+        ));
+    }
+}
+
+/// positions のうち origin に一番近い位置
+pub(super) fn nearest_position(
+    origin: Vec2,
+    positions: impl Iterator<Item = Vec2>,
+) -> Option<Vec2> {
+    positions.min_by(|a, b| {
+        a.distance_squared(origin)
+            .total_cmp(&b.distance_squared(origin))
+    })
 }
 
 /// 時間経過で弾をチャージするシステム
-fn charge_bullets(time: Res<Time>, mut query: Query<&mut BulletStock, With<Player>>) {
-    let Ok(mut stock) = query.single_mut() else {
-        return;
+fn charge_bullets(
+    time: Res<Time>,
+    mut query: Query<(&mut BulletStock, Has<RapidCharge>), With<Player>>,
+) {
+    for (mut stock, rapid) in &mut query {
+        charge_stock(&mut stock, rapid, time.delta_secs());
+    }
+}
+
+/// 1人分の弾のストックをチャージする
+fn charge_stock(stock: &mut BulletStock, rapid: bool, delta_secs: f32) {
+    // RapidCharge の効果中はチャージ時間が短くなる
+    let charge_secs = if rapid {
+        BULLET_CHARGE_SECS * RAPID_CHARGE_SCALE
+    } else {
+        BULLET_CHARGE_SECS
     };
 
     // すでに最大ストックなら何もしない
@@ -372,11 +845,11 @@ fn charge_bullets(time: Res<Time>, mut query: Query<&mut BulletStock, With<Playe
     }
 
     // 経過時間を加算
-    stock.charge_timer += time.delta_secs();
+    stock.charge_timer += delta_secs;
     // チャージ時間を経過した場合
-    if stock.charge_timer >= BULLET_CHARGE_SECS {
+    if stock.charge_timer >= charge_secs {
         // 経過時間をリセット
-        stock.charge_timer -= BULLET_CHARGE_SECS;
+        stock.charge_timer -= charge_secs;
         // 弾をチャージ
         stock.current += 1;
     }
@@ -384,137 +857,38 @@ fn charge_bullets(time: Res<Time>, mut query: Query<&mut BulletStock, With<Playe
 
 /// プレイヤーの移動処理
 fn player_movement(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<FixedActionState>,
     time: Res<Time>,
-    window_query: Query<&Window>,
-    mut query: Query<(&mut Transform, &Sprite), With<Player>>,
+    playfield: Res<Playfield>,
+    mut query: Query<(&mut Transform, &Sprite, &Player)>,
 ) {
-    // プレイヤーのtransformとspriteを取得
-    let Ok((mut transform, sprite)) = query.single_mut() else {
-        return;
-    };
-
-    // どの方向に向かって進かの情報を初期化
-    let mut direction = Vec2::ZERO;
-
-    // Wを押すと上方向に進む
-    if keyboard_input.pressed(KeyCode::KeyW) {
-        direction.y += 1.0;
-    }
-    // Sを押すと下方向に進む
-    if keyboard_input.pressed(KeyCode::KeyS) {
-        direction.y -= 1.0;
-    }
-    // Aを押すと左方向に進む
-    if keyboard_input.pressed(KeyCode::KeyA) {
-        direction.x -= 1.0;
-    }
-    // Dを押すと右方向に進む
-    if keyboard_input.pressed(KeyCode::KeyD) {
-        direction.x += 1.0;
-    }
-
-    // 斜め移動などで方向ベクトルの長さが1.0を超えた場合、
-    // 斜め移動の時に、縦横移動より多くの距離を進むことを防ぐため
-    // ベクトルの長さ（大きさ）がちょうど 1.0 になるように正規化する
-    // （結果として、xとyはそれぞれ -1.0 ~ 1.0 の間の値になる）
-    if direction.length() > 0.0 {
-        direction = direction.normalize();
-    }
-
-    // 移動先のx座標とy座標を設定
-    // direction（-1.0 ~ 1.0） * プレイヤーの移動速度 * 前フレームからの経過時間
-    // を掛けることで、「1秒間に約300ピクセル進む」一定の速度になる
-    transform.translation.x += direction.x * PLAYER_SPEED * time.delta_secs();
-    transform.translation.y += direction.y * PLAYER_SPEED * time.delta_secs();
-
-    // プレイヤーが画面外に出ないようにクランプ（範囲制限）する
-    // スプライトのcustom_sizeの半分を考慮して端にぴったり止まるようにする
-    if let Ok(window) = window_query.single() {
+    // プレイヤーごとのtransformとspriteを取得
+    for (mut transform, sprite, player) in &mut query {
+        // 移動キー・十字キー・アナログスティックから進む方向を求める
+        // 斜め移動の時に、縦横移動より多くの距離を進むことを防ぐため、
+        // ベクトルの長さ（大きさ）は 1.0 以下に制限されている
+        // （スティックを少しだけ倒したときはゆっくり進む）
+        let direction = actions.movement(player.index);
+
+        // 移動先のx座標とy座標を設定
+        // direction（-1.0 ~ 1.0） * プレイヤーの移動速度 * 前フレームからの経過時間
+        // を掛けることで、「1秒間に約300ピクセル進む」一定の速度になる
+        transform.translation.x += direction.x * PLAYER_SPEED * time.delta_secs();
+        transform.translation.y += direction.y * PLAYER_SPEED * time.delta_secs();
+
+        // プレイヤーが画面外に出ないようにクランプ（範囲制限）する
+        // スプライトのcustom_sizeの半分を考慮して端にぴったり止まるようにする
         // スプライトの半分のサイズを計算
         let sprite_half = sprite.custom_size.unwrap_or(Vec2::ZERO) / 2.0;
-        // ウィンドウの半分のサイズを計算
-        let half_w = window.width() / 2.0 - sprite_half.x;
-        let half_h = window.height() / 2.0 - sprite_half.y;
+        // 画面の半分のサイズからスプライトの分を除く
+        let half = playfield.half_size - sprite_half;
         // プレイヤーの位置を指定範囲内に制限する
-        transform.translation.x = transform.translation.x.clamp(-half_w, half_w);
-        transform.translation.y = transform.translation.y.clamp(-half_h, half_h);
+        transform.translation.x = transform.translation.x.clamp(-half.x, half.x);
+        transform.translation.y = transform.translation.y.clamp(-half.y, half.y);
     }
 }
 
-/// 弾のマーカーコンポーネント
-#[derive(Component)]
-pub struct Bullet;
-
-/// 弾のサイズ
-const BULLET_SIZE: Vec2 = Vec2::new(10.0, 20.0);
-
-/// 弾の移動速度（ピクセル/秒）
-const BULLET_SPEED: f32 = 600.0;
-
-/// Enterキーで弾を発射する処理
-fn shoot_bullet(
-    mut commands: Commands,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut query: Query<(&Transform, &mut BulletStock), With<Player>>,
-) {
-    // Enterキーが押された時だけ発射する
-    if !keyboard_input.just_pressed(KeyCode::Enter) {
-        return;
-    }
-
-    // プレイヤーの位置と弾ストックを取得
-    let Ok((player_transform, mut stock)) = query.single_mut() else {
-        return;
-    };
-
-    // 残弾がなければ発射しない
-    if stock.current == 0 {
-        return;
-    }
-
-    // 残弾を1消費して弾をspawnする
-    stock.current -= 1;
-
-    commands.spawn((
-        Sprite::from_color(Color::srgb(1.0, 1.0, 0.0), BULLET_SIZE),
-        Transform::from_translation(player_transform.translation),
-        Bullet,
-        DespawnOnExit(GameState::Game),
-    ));
-}
-
-/// 弾を上方向に移動させる処理
-fn bullet_movement(
-    mut commands: Commands,
-    time: Res<Time>,
-    window_query: Query<&Window>,
-    mut query: Query<(Entity, &mut Transform), With<Bullet>>,
-) {
-    // ウィンドウの高さの半分を画面上端のY座標として算出
-    // （Bevyの2D座標はY=0が画面中央のため）
-    let window_half_height = window_query
-        .single()
-        .map(|w| w.height() / 2.0)
-        .unwrap_or(400.0); // ほぼあり得ないが、ウィンドウの高さを取得できない場合は400.0を代入
-
-    for (entity, mut transform) in &mut query {
-        // 弾を上方向に移動
-        transform.translation.y += BULLET_SPEED * time.delta_secs();
-
-        // 画面外（上端）に出たら削除する
-        if transform.translation.y > window_half_height {
-            commands.entity(entity).despawn();
-        }
-    }
-}
-
-/// 敵のマーカーコンポーネント
-#[derive(Component)]
-pub struct Enemy;
-
-/// 敵の移動速度（ピクセル/秒）
-const ENEMY_SPEED: f32 = 200.0;
+// 以下の敵のサイズ・スポーン間隔の定数は、ステージ定義で省略されたときの既定値
 
 /// 敵のスプライトサイズの初期値（正方形）
 const ENEMY_SIZE_INITIAL: Vec2 = Vec2::splat(50.0);
@@ -547,11 +921,16 @@ const SPAWN_INTERVAL_STEP_AMOUNT: f32 = 0.1;
 
 /// ゲーム開始からの経過時間（秒）を管理するリソース
 #[derive(Resource, Default)]
-struct GameElapsedTime(f32);
+pub struct GameElapsedTime(pub f32);
 
-/// 毎フレーム経過時間を加算するシステム
-fn tick_game_elapsed(time: Res<Time>, mut game_elapsed_time: ResMut<GameElapsedTime>) {
+/// 毎ティック経過時間を加算するシステム
+fn tick_game_elapsed(
+    time: Res<Time>,
+    mut game_elapsed_time: ResMut<GameElapsedTime>,
+    mut score: ResMut<Score>,
+) {
     game_elapsed_time.0 += time.delta_secs();
+    score.time += time.delta_secs();
 }
 
 /// ゲーム開始時に経過時間・残り敵数をリセットするシステム
@@ -559,13 +938,15 @@ fn reset_game_elapsed(
     mut game_elapsed_time: ResMut<GameElapsedTime>,
     mut enemy_spawn_timer: ResMut<EnemySpawnTimer>,
     mut enemy_count: ResMut<EnemyCount>,
+    active_stage: Res<ActiveStage>,
 ) {
     // ゲーム内経過時間を0.0秒にリセット
     game_elapsed_time.0 = 0.0;
     // 敵のスポーン間隔タイマーも初期間隔に戻す
-    enemy_spawn_timer.0 = Timer::from_seconds(SPAWN_INTERVAL_INITIAL, TimerMode::Repeating);
-    // 残り敵数を初期値に戻す
-    enemy_count.0 = ENEMY_TOTAL;
+    enemy_spawn_timer.0 =
+        Timer::from_seconds(active_stage.0.spawn_interval.initial, TimerMode::Repeating);
+    // 残り敵数をステージの討伐数に戻す
+    enemy_count.0 = active_stage.0.enemy_total;
 }
 
 /// 一定間隔でランダムなX座標に敵をspawnする処理
@@ -574,82 +955,82 @@ fn enemy_spawner(
     time: Res<Time>,
     mut enemy_spawn_timer: ResMut<EnemySpawnTimer>,
     game_elapsed_time: Res<GameElapsedTime>,
-    window_query: Query<&Window>,
+    active_stage: Res<ActiveStage>,
+    mut game_rng: ResMut<GameRng>,
+    playfield: Res<Playfield>,
 ) {
+    let stage = &active_stage.0;
+
+    // ランダムなスポーンを行わないステージなら何もしない
+    if !stage.random_spawns {
+        return;
+    }
+
     // 敵のスポーン間隔タイマーを進めて、まだ発火していなければ何もしない
     if !enemy_spawn_timer.0.tick(time.delta()).just_finished() {
         return;
     }
 
-    // ウィンドウのサイズを取得（取得できなければ何もしない）
-    let Ok(window) = window_query.single() else {
-        return;
-    };
+    // 経過時間に応じて敵の基本サイズを計算する
+    let enemy_side = stage.enemy_size.at(game_elapsed_time.0);
 
-    // 経過時間に応じて敵のサイズを計算する
-    let size_steps = (game_elapsed_time.0 / ENEMY_SIZE_STEP_SECS).floor();
-    let enemy_side =
-        (ENEMY_SIZE_INITIAL.x - size_steps * ENEMY_SIZE_STEP_AMOUNT).max(ENEMY_SIZE_MIN);
-    let enemy_size = Vec2::splat(enemy_side);
+    // 経過時間で解禁されたアーキタイプから出現する敵を抽選する
+    let rng = game_rng.rng();
+    let archetype = EnemyArchetype::pick(rng, game_elapsed_time.0);
+    let enemy_size = Vec2::splat(enemy_side * archetype.stats().size_scale);
 
-    // rand クレートを使ってランダムなX座標（画面幅の範囲内）を生成する
+    // シード付きの乱数でランダムなX座標（画面幅の範囲内）を生成する
     // enemy_sizeの半分を差し引いて、敵が画面端からはみ出さないようにする
-    let half_w = window.width() / 2.0 - enemy_size.x / 2.0;
-    let random_x = rand::rng().random_range(-half_w..=half_w);
+    let half_w = playfield.half_size.x - enemy_size.x / 2.0;
+    let random_x = rng.random_range(-half_w..=half_w);
 
     // 画面上端のY座標を計算（スプライトの分だけ上にオフセット）
-    let spawn_y = window.height() / 2.0 + enemy_size.y / 2.0;
-
-    // 敵をspawnする（赤い四角形）
-    commands.spawn((
-        Sprite::from_color(Color::srgb(1.0, 0.2, 0.2), enemy_size),
-        Transform::from_xyz(random_x, spawn_y, 0.0),
-        Enemy,
-        DespawnOnExit(GameState::Game),
-    ));
+    let spawn_y = playfield.half_size.y + enemy_size.y / 2.0;
+
+    // 敵をspawnする
+    enemy::spawn_enemy(
+        &mut commands,
+        archetype,
+        Vec2::new(random_x, spawn_y),
+        enemy_side,
+        1.0,
+        None,
+    );
 
     // ゲーム内経過時間に応じて敵スポーンタイマーの間隔を更新する
-    let interval_steps = (game_elapsed_time.0 / SPAWN_INTERVAL_STEP_SECS).floor();
-    let new_interval = (SPAWN_INTERVAL_INITIAL - interval_steps * SPAWN_INTERVAL_STEP_AMOUNT)
-        .max(SPAWN_INTERVAL_MIN);
+    let new_interval = stage.spawn_interval.at(game_elapsed_time.0);
     enemy_spawn_timer
         .0
         .set_duration(std::time::Duration::from_secs_f32(new_interval));
 }
 
-/// 敵を下方向に移動させ、画面外に出たらHPを減らす処理
+/// 敵をアーキタイプごとの動きで移動させ、画面外に出たら取り逃がしとして削除する処理
 fn enemy_movement(
     mut commands: Commands,
     time: Res<Time>,
-    window_query: Query<&Window>,
-    mut query: Query<(Entity, &mut Transform), With<Enemy>>,
-    mut player_query: Query<(Entity, &mut HP), With<Player>>,
-    mut next_state: ResMut<NextState<GameState>>,
+    playfield: Res<Playfield>,
+    mut query: Query<(Entity, &mut Transform, &mut EnemyMovement, &Sprite, &Enemy)>,
+    mut escaped: MessageWriter<EnemyEscaped>,
 ) {
-    // 画面下端のY座標を取得
-    let window_half_height = window_query
-        .single()
-        .map(|w| -(w.height() / 2.0))
-        .unwrap_or(-400.0);
-
-    for (entity, mut transform) in &mut query {
-        // 敵を下方向に移動
-        transform.translation.y -= ENEMY_SPEED * time.delta_secs();
-
-        // 画面外（下端）に出たら削除し、プレイヤーのHPを1減らす
-        if transform.translation.y < window_half_height - ENEMY_SIZE_INITIAL.y / 2.0 {
-            commands.entity(entity).despawn();
+    // 画面下端のY座標と画面幅の半分を取得
+    let (window_half_height, window_half_width) = (-playfield.half_size.y, playfield.half_size.x);
 
-            if let Ok((player_entity, mut hp)) = player_query.single_mut() {
-                if hp.0 > 1 {
-                    // HPが残っていれば1減らす
-                    hp.0 -= 1;
-                } else {
-                    // HPが0になったらゲームオーバー
-                    commands.entity(player_entity).despawn();
-                    next_state.set(GameState::GameOver);
-                }
-            }
+    for (entity, mut transform, mut movement, sprite, enemy) in &mut query {
+        let enemy_size = sprite.custom_size.unwrap_or(ENEMY_SIZE_INITIAL);
+
+        // アーキタイプごとの動きで移動
+        movement.step(
+            &mut transform,
+            time.delta_secs(),
+            window_half_width - enemy_size.x / 2.0,
+        );
+
+        // 画面外（下端）に出たら削除する（ダメージやコンボの処理は EnemyEscaped を受け取る側で行う）
+        if transform.translation.y < window_half_height - enemy_size.y / 2.0 {
+            commands.entity(entity).despawn();
+            escaped.write(EnemyEscaped {
+                archetype: enemy.archetype,
+            });
         }
     }
 }
@@ -657,107 +1038,97 @@ fn enemy_movement(
 /// プレイヤーと敵の当たり判定処理
 fn check_player_enemy_collision(
     mut commands: Commands,
-    mut player_query: Query<(Entity, &Transform, &Sprite, &mut HP), With<Player>>,
-    enemy_query: Query<(Entity, &Transform, &Sprite), With<Enemy>>,
-    mut next_state: ResMut<NextState<GameState>>,
+    mut collisions: MessageReader<Collision>,
+    player_query: Query<(), With<Player>>,
+    mut enemy_query: Query<(&Transform, &mut Health), With<Enemy>>,
+    mut hits: MessageWriter<PlayerHit>,
 ) {
-    for (player_entity, player_transform, player_sprite, mut player_hp) in &mut player_query {
-        // プレイヤーのサイズ
-        let player_size = player_sprite.custom_size.unwrap_or(PLAYER_SIZE);
-        // プレイヤーの位置
-        let p_pos = player_transform.translation;
-
-        // プレイヤーの上下左右の座標
-        let p_left = p_pos.x - player_size.x / 2.0;
-        let p_right = p_pos.x + player_size.x / 2.0;
-        let p_bottom = p_pos.y - player_size.y / 2.0;
-        let p_top = p_pos.y + player_size.y / 2.0;
-
-        for (enemy_entity, enemy_transform, enemy_sprite) in &enemy_query {
-            // 敵のサイズ
-            let enemy_size = enemy_sprite.custom_size.unwrap_or(ENEMY_SIZE_INITIAL);
-            // 敵の位置
-            let e_pos = enemy_transform.translation;
-
-            // 敵の上下左右の座標
-            let e_left = e_pos.x - enemy_size.x / 2.0;
-            let e_right = e_pos.x + enemy_size.x / 2.0;
-            let e_bottom = e_pos.y - enemy_size.y / 2.0;
-            let e_top = e_pos.y + enemy_size.y / 2.0;
-
-            // シンプルな矩形（AABB）による当たり判定
-            let collision =
-                p_left < e_right && p_right > e_left && p_bottom < e_top && p_top > e_bottom;
-
-            if collision {
-                // 当たったら敵を削除する
-                commands.entity(enemy_entity).despawn();
-                // プレイヤーのHPが1以上ならHPを減らす、0ならゲームオーバー
-                if player_hp.0 > 1 {
-                    player_hp.0 -= 1;
-                } else {
-                    commands.entity(player_entity).despawn();
-                    next_state.set(GameState::GameOver);
-                }
-            }
+    for collision in collisions.read() {
+        if !player_query.contains(collision.a) {
+            continue;
+        }
+        let Ok((enemy_transform, mut health)) = enemy_query.get_mut(collision.b) else {
+            continue;
+        };
+        // このティックですでに弾で倒された敵や、ほかのプレイヤーに当たった敵は無視する
+        if health.0 == 0 {
+            continue;
         }
+
+        // 当たったら敵を削除し（耐久値を0にして処理済みにする）、プレイヤーに攻撃を当てる
+        health.0 = 0;
+        commands.entity(collision.b).despawn();
+        hits.write(PlayerHit {
+            player: collision.a,
+            source: DamageSource::Collision,
+            origin: Some(enemy_transform.translation.truncate()),
+        });
     }
 }
 
 /// 弾と敵の当たり判定処理
 fn check_bullet_enemy_collisions(
     mut commands: Commands,
-    bullet_query: Query<(Entity, &Transform, &Sprite), With<Bullet>>,
-    enemy_query: Query<(Entity, &Transform, &Sprite), With<Enemy>>,
-    mut enemy_count: ResMut<EnemyCount>,
-    mut next_state: ResMut<NextState<GameState>>,
+    mut collisions: MessageReader<Collision>,
+    mut bullet_query: Query<(&Bullet, &Transform, Option<&mut PiercingBullet>)>,
+    mut enemy_query: Query<(&Transform, &Sprite, &Enemy, &mut Health)>,
+    mut bullet_hits: MessageWriter<BulletHit>,
+    mut killed: MessageWriter<EnemyKilled>,
 ) {
-    for (bullet_entity, bullet_transform, bullet_sprite) in &bullet_query {
-        // 弾のサイズ
-        let bullet_size = bullet_sprite.custom_size.unwrap_or(BULLET_SIZE);
-        // 弾の位置
-        let b_pos = bullet_transform.translation;
-
-        for (enemy_entity, enemy_transform, enemy_sprite) in &enemy_query {
-            // 敵のサイズ
-            let enemy_size = enemy_sprite.custom_size.unwrap_or(ENEMY_SIZE_INITIAL);
-            // 敵の位置
-            let e_pos = enemy_transform.translation;
-
-            // 弾の上下左右の座標
-            let b_left = b_pos.x - bullet_size.x / 2.0;
-            let b_right = b_pos.x + bullet_size.x / 2.0;
-            let b_bottom = b_pos.y - bullet_size.y / 2.0;
-            let b_top = b_pos.y + bullet_size.y / 2.0;
-
-            // 敵の上下左右の座標
-            let e_left = e_pos.x - enemy_size.x / 2.0;
-            let e_right = e_pos.x + enemy_size.x / 2.0;
-            let e_bottom = e_pos.y - enemy_size.y / 2.0;
-            let e_top = e_pos.y + enemy_size.y / 2.0;
-
-            // シンプルな矩形（AABB）による当たり判定
-            let collision =
-                b_left < e_right && b_right > e_left && b_bottom < e_top && b_top > e_bottom;
-
-            if collision {
-                // 当たったら両者を削除する
-                commands.entity(bullet_entity).despawn();
-                commands.entity(enemy_entity).despawn();
+    // このティックで削除予約した（貫通しない弾が敵に当たった）弾
+    let mut spent = Vec::new();
 
-                // 残り敵数を1減らす
-                if enemy_count.0 > 0 {
-                    enemy_count.0 -= 1;
-                }
-
-                // 残り敵数が0になったらゲームクリア
-                if enemy_count.0 == 0 {
-                    next_state.set(GameState::GameClear);
-                }
+    for collision in collisions.read() {
+        let (bullet_entity, enemy_entity) = (collision.a, collision.b);
+        if spent.contains(&bullet_entity) {
+            continue;
+        }
+        let Ok((bullet, bullet_transform, mut piercing)) = bullet_query.get_mut(bullet_entity)
+        else {
+            continue;
+        };
+        let Ok((enemy_transform, enemy_sprite, enemy, mut health)) =
+            enemy_query.get_mut(enemy_entity)
+        else {
+            continue;
+        };
+        // このティックですでに倒された敵は無視する
+        if health.0 == 0 {
+            continue;
+        }
 
-                // この弾は削除予約されたので、他へは当たらないとして次の弾の処理へ移行
-                break;
+        // 当たったら弾を削除し（貫通する弾は当たった敵を覚えて飛び続ける）、敵の耐久値を弾のダメージ分減らす
+        let first = piercing
+            .as_ref()
+            .is_none_or(|piercing| piercing.hit.is_empty());
+        match piercing.as_mut() {
+            Some(piercing) if piercing.hit.contains(&enemy_entity) => continue,
+            Some(piercing) => piercing.hit.push(enemy_entity),
+            None => {
+                commands.entity(bullet_entity).despawn();
+                spent.push(bullet_entity);
             }
         }
+        bullet_hits.write(BulletHit {
+            first,
+            target: enemy_entity,
+            position: bullet_transform.translation.truncate(),
+        });
+        health.0 = health.0.saturating_sub(bullet.damage);
+
+        // 耐久値が残っていれば、この敵への処理は終わり
+        if health.0 > 0 {
+            commands.write_message(PlaySfx(Sfx::Hit));
+            continue;
+        }
+
+        // 耐久値が0になったら敵を削除する（得点・破片・アイテムは EnemyKilled を受け取る側で処理する）
+        commands.entity(enemy_entity).despawn();
+        killed.write(EnemyKilled {
+            archetype: enemy.archetype,
+            position: enemy_transform.translation.truncate(),
+            size: enemy_sprite.custom_size.unwrap_or(ENEMY_SIZE_INITIAL),
+            owner: bullet.owner,
+        });
     }
 }
@@ -0,0 +1,530 @@
+/// パーティクルの演出（爆発・着弾・エンジンの噴射・破片）
+///
+/// 演出ごとの数・寿命・速度・色の変化などはアセットファイル（*.particles.ron）で定義する。
+/// パーティクルのエンティティはプールで使い回し、ゲーム画面を出るときにまとめて削除する。
+/// 見た目だけの演出なので、ゲームの乱数（リプレイで再現する乱数）は使わない
+use std::f32::consts::TAU;
+use std::fmt;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::color::Mix;
+use bevy::prelude::*;
+use rand::RngExt;
+use serde::Deserialize;
+
+use super::{BossDefeated, BulletHit, EnemyKilled, PLAYER_SIZE, Player};
+use crate::state::GameState;
+
+/// パーティクル定義ファイルのパス
+const PARTICLE_LIBRARY_PATH: &str = "effects/default.particles.ron";
+/// パーティクル定義ファイルの拡張子
+const PARTICLE_EXTENSION: &str = "particles.ron";
+/// ゲーム開始時にあらかじめ用意しておくパーティクルの数
+const PARTICLE_POOL_SIZE: usize = 256;
+/// 同時に出せるパーティクルの最大数（超えた分は出さない）
+const PARTICLE_MAX: usize = 1024;
+/// パーティクルの描画の奥行き（敵や弾より手前）
+const PARTICLE_Z: f32 = 0.5;
+
+/// パーティクルの演出1種類の定義
+#[derive(Deserialize, Clone, Debug)]
+pub struct ParticleEffect {
+    /// 1回に出す数
+    pub count: u32,
+    /// 寿命の範囲（秒。最小, 最大）
+    pub lifetime: (f32, f32),
+    /// 初速の範囲（ピクセル/秒。最小, 最大）
+    pub speed: (f32, f32),
+    /// 飛ぶ向きの中心（上向きが (0.0, 1.0)）
+    #[serde(default = "default_direction")]
+    pub direction: (f32, f32),
+    /// 向きのばらつき（ラジアン。TAU（約6.28）で全方向）
+    #[serde(default = "default_spread")]
+    pub spread: f32,
+    /// 速度の減衰の速さ（1秒で速度が e^-drag 倍になる。0.0 で減衰しない）
+    #[serde(default)]
+    pub drag: f32,
+    /// 大きさ（ピクセル。出たとき, 消えるとき）
+    pub size: (f32, f32),
+    /// 色（sRGB と不透明度の 0.0 ~ 1.0。出たとき）
+    pub start_color: (f32, f32, f32, f32),
+    /// 色（sRGB と不透明度の 0.0 ~ 1.0。消えるとき）
+    pub end_color: (f32, f32, f32, f32),
+    /// true なら、色を出したもの（倒した敵など）の色にする（不透明度は定義の値）
+    #[serde(default)]
+    pub tint_with_source: bool,
+    /// 続けて出す演出（エンジンの噴射）の間隔（秒）
+    #[serde(default = "default_interval")]
+    pub interval: f32,
+}
+
+fn default_direction() -> (f32, f32) {
+    (0.0, 1.0)
+}
+
+fn default_spread() -> f32 {
+    TAU
+}
+
+fn default_interval() -> f32 {
+    0.05
+}
+
+impl ParticleEffect {
+    /// 定義の妥当性を確認し、問題があれば errors に追加する
+    fn validate(&self, field: &str, errors: &mut Vec<String>) {
+        let (lifetime_min, lifetime_max) = self.lifetime;
+        if !(lifetime_min > 0.0 && lifetime_min <= lifetime_max && lifetime_max.is_finite()) {
+            errors.push(format!("{field}.lifetime must be positive with min <= max"));
+        }
+        let (speed_min, speed_max) = self.speed;
+        if !(speed_min >= 0.0 && speed_min <= speed_max && speed_max.is_finite()) {
+            errors.push(format!(
+                "{field}.speed must be non-negative with min <= max"
+            ));
+        }
+        if !(self.drag >= 0.0 && self.drag.is_finite()) {
+            errors.push(format!("{field}.drag must be a non-negative number"));
+        }
+        if !(self.size.0 >= 0.0 && self.size.1 >= 0.0) {
+            errors.push(format!("{field}.size must not be negative"));
+        }
+        let (r, g, b, a) = self.start_color;
+        let (end_r, end_g, end_b, end_a) = self.end_color;
+        if [r, g, b, a, end_r, end_g, end_b, end_a]
+            .iter()
+            .any(|c| !(0.0..=1.0).contains(c))
+        {
+            errors.push(format!("{field} color components must be within 0.0..=1.0"));
+        }
+        if !(self.interval > 0.0 && self.interval.is_finite()) {
+            errors.push(format!("{field}.interval must be positive"));
+        }
+    }
+
+    /// 出たときと消えるときの色（tint が指定されれば、その色に定義の不透明度を使う）
+    fn colors(&self, tint: Option<Color>) -> (LinearRgba, LinearRgba) {
+        let color = |(r, g, b, a): (f32, f32, f32, f32)| match tint {
+            Some(tint) if self.tint_with_source => tint.with_alpha(a).to_linear(),
+            _ => Color::srgba(r, g, b, a).to_linear(),
+        };
+        (color(self.start_color), color(self.end_color))
+    }
+}
+
+/// パーティクルの演出の定義一式
+#[derive(Asset, TypePath, Deserialize, Clone, Debug)]
+pub struct ParticleLibrary {
+    /// 敵やボスを倒したときの爆発
+    pub explosion: ParticleEffect,
+    /// 弾が当たったときの火花
+    pub impact: ParticleEffect,
+    /// プレイヤーのエンジンの噴射
+    pub trail: ParticleEffect,
+    /// 倒した敵の破片
+    pub debris: ParticleEffect,
+}
+
+impl ParticleLibrary {
+    /// 定義の妥当性を確認し、問題の一覧を返す
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        self.explosion.validate("explosion", &mut errors);
+        self.impact.validate("impact", &mut errors);
+        self.trail.validate("trail", &mut errors);
+        self.debris.validate("debris", &mut errors);
+        errors
+    }
+}
+
+/// パーティクル定義ファイルの読み込みエラー
+#[derive(Debug)]
+pub enum ParticleLoadError {
+    /// ファイルを読めなかった
+    Io(std::io::Error),
+    /// RON として解釈できなかった
+    Parse(ron::error::SpannedError),
+    /// 内容に問題があった
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ParticleLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read particle file: {err}"),
+            Self::Parse(err) => write!(f, "failed to parse particle file: {err}"),
+            Self::Invalid(errors) => {
+                write!(f, "invalid particle definition: {}", errors.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParticleLoadError {}
+
+/// パーティクル定義ファイル（*.particles.ron）のローダー
+#[derive(Default, TypePath)]
+pub struct ParticleLoader;
+
+impl AssetLoader for ParticleLoader {
+    type Asset = ParticleLibrary;
+    type Settings = ();
+    type Error = ParticleLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(ParticleLoadError::Io)?;
+        let library: ParticleLibrary =
+            ron::de::from_bytes(&bytes).map_err(ParticleLoadError::Parse)?;
+
+        let errors = library.validate();
+        if !errors.is_empty() {
+            return Err(ParticleLoadError::Invalid(errors));
+        }
+        Ok(library)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[PARTICLE_EXTENSION]
+    }
+}
+
+/// パーティクル定義のハンドルを保持するリソース
+#[derive(Resource)]
+pub(super) struct ParticleLibraryHandle(Handle<ParticleLibrary>);
+
+/// 起動時にパーティクル定義の読み込みを始めるシステム
+pub(super) fn load_particle_library(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ParticleLibraryHandle(
+        asset_server.load(PARTICLE_LIBRARY_PATH),
+    ));
+}
+
+/// 動いているパーティクルのコンポーネント
+#[derive(Component)]
+pub(super) struct Particle {
+    /// 速度（ピクセル/秒）
+    velocity: Vec2,
+    /// 速度の減衰の速さ（ParticleEffect::drag）
+    drag: f32,
+    /// 出てからの経過時間（秒）
+    age: f32,
+    /// 寿命（秒）
+    lifetime: f32,
+    /// 大きさ（出たとき, 消えるとき）
+    size: (f32, f32),
+    /// 色（出たとき, 消えるとき）
+    colors: (LinearRgba, LinearRgba),
+}
+
+/// 使い回すパーティクルを保持するリソース
+#[derive(Resource, Default)]
+pub(super) struct ParticlePool {
+    /// 使っていないパーティクル
+    free: Vec<Entity>,
+    /// 作ったパーティクルの数（使用中と未使用の合計）
+    total: usize,
+}
+
+impl ParticlePool {
+    /// effect の定義に従って position からパーティクルを出す（tint は出したものの色）
+    fn emit(
+        &mut self,
+        commands: &mut Commands,
+        effect: &ParticleEffect,
+        position: Vec2,
+        tint: Option<Color>,
+    ) {
+        let mut rng = rand::rng();
+        let colors = effect.colors(tint);
+        let direction = Vec2::from(effect.direction).normalize_or(Vec2::Y);
+
+        for _ in 0..effect.count {
+            let angle = (rng.random::<f32>() - 0.5) * effect.spread;
+            let speed = random_between(&mut rng, effect.speed);
+            let particle = Particle {
+                velocity: Vec2::from_angle(angle).rotate(direction) * speed,
+                drag: effect.drag,
+                age: 0.0,
+                lifetime: random_between(&mut rng, effect.lifetime),
+                size: effect.size,
+                colors,
+            };
+            let transform = Transform::from_translation(position.extend(PARTICLE_Z));
+            let sprite = Sprite::from_color(colors.0, Vec2::splat(effect.size.0));
+
+            if let Some(entity) = self.free.pop() {
+                commands.entity(entity).insert((
+                    particle,
+                    transform,
+                    sprite,
+                    Visibility::Inherited,
+                ));
+            } else if self.total < PARTICLE_MAX {
+                commands.spawn((particle, transform, sprite, DespawnOnExit(GameState::Game)));
+                self.total += 1;
+            }
+        }
+    }
+
+    /// パーティクルを非表示にしてプールに戻す
+    fn release(&mut self, commands: &mut Commands, entity: Entity) {
+        commands
+            .entity(entity)
+            .insert(Visibility::Hidden)
+            .remove::<Particle>();
+        self.free.push(entity);
+    }
+}
+
+/// delta 秒の間に速度に掛ける減衰の倍率
+///
+/// 指数関数で減らすので、ティックの刻み方によらず同じ時間で同じだけ減る
+fn drag_factor(drag: f32, delta: f32) -> f32 {
+    (-drag * delta).exp()
+}
+
+/// range（最小, 最大）の間のランダムな値
+fn random_between(rng: &mut impl rand::Rng, (min, max): (f32, f32)) -> f32 {
+    min + (max - min) * rng.random::<f32>()
+}
+
+/// ゲーム開始時にパーティクルのプールを用意するシステム
+///
+/// 前回のゲームのパーティクルは DespawnOnExit で削除済みなので、プールは作り直す
+pub(super) fn setup_particle_pool(mut commands: Commands, mut pool: ResMut<ParticlePool>) {
+    pool.free.clear();
+    for _ in 0..PARTICLE_POOL_SIZE {
+        let entity = commands
+            .spawn((
+                Sprite::default(),
+                Transform::default(),
+                Visibility::Hidden,
+                DespawnOnExit(GameState::Game),
+            ))
+            .id();
+        pool.free.push(entity);
+    }
+    pool.total = PARTICLE_POOL_SIZE;
+}
+
+/// 敵やボスを倒したとき・弾が当たったときにパーティクルを出すシステム
+///
+/// 定義の読み込みが終わるまで（読み込めなかった場合も）は何も出さない
+pub(super) fn emit_event_particles(
+    mut commands: Commands,
+    mut killed: MessageReader<EnemyKilled>,
+    mut bullet_hits: MessageReader<BulletHit>,
+    mut boss_defeated: MessageReader<BossDefeated>,
+    handle: Res<ParticleLibraryHandle>,
+    libraries: Res<Assets<ParticleLibrary>>,
+    mut pool: ResMut<ParticlePool>,
+) {
+    let Some(library) = libraries.get(&handle.0) else {
+        return;
+    };
+
+    for kill in killed.read() {
+        let color = kill.archetype.stats().color;
+        pool.emit(&mut commands, &library.explosion, kill.position, None);
+        pool.emit(&mut commands, &library.debris, kill.position, Some(color));
+    }
+    for hit in bullet_hits.read() {
+        pool.emit(&mut commands, &library.impact, hit.position, None);
+    }
+    for defeated in boss_defeated.read() {
+        pool.emit(&mut commands, &library.explosion, defeated.position, None);
+    }
+}
+
+/// プレイヤーの後ろからエンジンの噴射を一定間隔で出すシステム
+pub(super) fn emit_engine_trails(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut elapsed: Local<f32>,
+    player_query: Query<&Transform, With<Player>>,
+    handle: Res<ParticleLibraryHandle>,
+    libraries: Res<Assets<ParticleLibrary>>,
+    mut pool: ResMut<ParticlePool>,
+) {
+    let Some(library) = libraries.get(&handle.0) else {
+        return;
+    };
+    let trail = &library.trail;
+
+    *elapsed += time.delta_secs();
+    while *elapsed >= trail.interval {
+        *elapsed -= trail.interval;
+        for transform in &player_query {
+            let position = transform.translation.truncate() - Vec2::new(0.0, PLAYER_SIZE.y / 2.0);
+            pool.emit(&mut commands, trail, position, None);
+        }
+    }
+}
+
+/// パーティクルを動かして大きさと色を寿命に合わせて変え、寿命が来たらプールに戻すシステム
+pub(super) fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<ParticlePool>,
+    mut query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    let delta = time.delta_secs();
+
+    for (entity, mut particle, mut transform, mut sprite) in &mut query {
+        particle.age += delta;
+        if particle.age >= particle.lifetime {
+            pool.release(&mut commands, entity);
+            continue;
+        }
+
+        let drag = drag_factor(particle.drag, delta);
+        particle.velocity *= drag;
+        transform.translation += (particle.velocity * delta).extend(0.0);
+
+        let t = particle.age / particle.lifetime;
+        let (start_size, end_size) = particle.size;
+        sprite.custom_size = Some(Vec2::splat(start_size.lerp(end_size, t)));
+        let (start_color, end_color) = particle.colors;
+        sprite.color = start_color.mix(&end_color, t).into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 妥当な演出の定義
+    fn effect() -> ParticleEffect {
+        ParticleEffect {
+            count: 3,
+            lifetime: (0.5, 1.0),
+            speed: (10.0, 20.0),
+            direction: default_direction(),
+            spread: default_spread(),
+            drag: 2.0,
+            size: (4.0, 1.0),
+            start_color: (1.0, 1.0, 1.0, 1.0),
+            end_color: (1.0, 1.0, 1.0, 0.0),
+            tint_with_source: false,
+            interval: default_interval(),
+        }
+    }
+
+    /// 演出の定義の問題の一覧
+    fn errors_of(effect: &ParticleEffect) -> Vec<String> {
+        let mut errors = Vec::new();
+        effect.validate("effect", &mut errors);
+        errors
+    }
+
+    #[test]
+    fn valid_effect_has_no_errors() {
+        assert_eq!(errors_of(&effect()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn invalid_effect_reports_each_field() {
+        let invalid = ParticleEffect {
+            lifetime: (1.0, 0.5),
+            speed: (-1.0, 1.0),
+            drag: f32::NAN,
+            size: (-1.0, 1.0),
+            start_color: (2.0, 0.0, 0.0, 1.0),
+            interval: 0.0,
+            ..effect()
+        };
+        assert_eq!(errors_of(&invalid).len(), 6);
+    }
+
+    #[test]
+    fn shipped_library_is_valid() {
+        let path = format!(
+            "{}/assets/{PARTICLE_LIBRARY_PATH}",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let library: ParticleLibrary =
+            ron::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(library.validate(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn drag_does_not_depend_on_step_size() {
+        let whole = drag_factor(3.0, 0.5);
+        let halves = drag_factor(3.0, 0.25) * drag_factor(3.0, 0.25);
+        assert!((whole - halves).abs() < 1e-6);
+        assert_eq!(drag_factor(0.0, 0.5), 1.0);
+        // 大きな刻みでも向きが反転しない
+        assert!(drag_factor(10.0, 1.0) > 0.0);
+    }
+
+    #[test]
+    fn emit_reuses_free_particles_before_spawning() {
+        let mut world = World::new();
+        let free = world.spawn(Visibility::Hidden).id();
+        let mut pool = ParticlePool {
+            free: vec![free],
+            total: 1,
+        };
+
+        pool.emit(&mut world.commands(), &effect(), Vec2::ZERO, None);
+        world.flush();
+
+        assert!(pool.free.is_empty());
+        assert_eq!(pool.total, 3);
+        assert!(world.get::<Particle>(free).is_some());
+        assert_eq!(world.query::<&Particle>().iter(&world).count(), 3);
+    }
+
+    #[test]
+    fn emit_stops_at_particle_max() {
+        let mut world = World::new();
+        let mut pool = ParticlePool {
+            free: Vec::new(),
+            total: PARTICLE_MAX,
+        };
+
+        pool.emit(&mut world.commands(), &effect(), Vec2::ZERO, None);
+        world.flush();
+
+        assert_eq!(pool.total, PARTICLE_MAX);
+        assert_eq!(world.query::<&Particle>().iter(&world).count(), 0);
+    }
+
+    #[test]
+    fn released_particle_is_hidden_and_reused() {
+        let mut world = World::new();
+        let mut pool = ParticlePool::default();
+        let single = ParticleEffect {
+            count: 1,
+            ..effect()
+        };
+        pool.emit(&mut world.commands(), &single, Vec2::ZERO, None);
+        world.flush();
+        let entity = world
+            .query_filtered::<Entity, With<Particle>>()
+            .single(&world)
+            .unwrap();
+
+        pool.release(&mut world.commands(), entity);
+        world.flush();
+        assert_eq!(pool.free, vec![entity]);
+        assert!(world.get::<Particle>(entity).is_none());
+        assert_eq!(world.get::<Visibility>(entity), Some(&Visibility::Hidden));
+
+        pool.emit(&mut world.commands(), &single, Vec2::ZERO, None);
+        world.flush();
+        assert!(pool.free.is_empty());
+        assert_eq!(pool.total, 1);
+        assert!(world.get::<Particle>(entity).is_some());
+    }
+}
@@ -0,0 +1,365 @@
+/// パワーアップ（敵が落とすアイテムとその効果）
+use bevy::ecs::component::Mutable;
+use bevy::prelude::*;
+use rand::RngExt;
+
+use super::collision::{Collider, Collision, CollisionLayers};
+use super::interpolation::Interpolated;
+use super::{HP, Player, Playfield};
+use crate::plugins::audio::{PlaySfx, Sfx};
+use crate::state::GameState;
+
+/// 敵を倒したときにアイテムを落とす確率
+const DROP_CHANCE: f32 = 0.1;
+/// アイテムのサイズ
+const POWER_UP_SIZE: Vec2 = Vec2::new(24.0, 24.0);
+/// アイテムの落下速度（ピクセル/秒）
+const POWER_UP_FALL_SPEED: f32 = 120.0;
+/// 回復で増やせるHPの上限
+pub const PLAYER_MAX_HP: u32 = 5;
+
+/// HUD の効果ゲージの最大の幅
+const GAUGE_WIDTH: f32 = 60.0;
+/// HUD の効果ゲージの高さ
+const GAUGE_HEIGHT: f32 = 10.0;
+
+/// アイテムの種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUpKind {
+    /// 一定時間、3方向に弾を撃つ
+    SpreadShot,
+    /// 一定時間、弾のチャージが速くなる
+    RapidCharge,
+    /// 一定時間、弾が敵を貫通する
+    Piercing,
+    /// 一定時間、敵や敵の弾に当たってもダメージを受けない
+    Shield,
+    /// HPを1回復する
+    Heal,
+}
+
+/// 抽選の対象となるアイテムと重み
+const DROP_TABLE: [(PowerUpKind, u32); 5] = [
+    (PowerUpKind::SpreadShot, 3),
+    (PowerUpKind::RapidCharge, 3),
+    (PowerUpKind::Piercing, 2),
+    (PowerUpKind::Shield, 2),
+    (PowerUpKind::Heal, 1),
+];
+
+/// HUD にゲージを表示する時間制限付きの効果（表示順）
+const TIMED_KINDS: [PowerUpKind; 4] = [
+    PowerUpKind::SpreadShot,
+    PowerUpKind::RapidCharge,
+    PowerUpKind::Piercing,
+    PowerUpKind::Shield,
+];
+
+impl PowerUpKind {
+    /// アイテムとゲージの色
+    pub const fn color(self) -> Color {
+        match self {
+            Self::SpreadShot => Color::srgb(1.0, 0.6, 0.0),
+            Self::RapidCharge => Color::srgb(1.0, 1.0, 0.3),
+            Self::Piercing => Color::srgb(0.7, 0.4, 1.0),
+            Self::Shield => Color::srgb(0.3, 0.9, 1.0),
+            Self::Heal => Color::srgb(0.3, 1.0, 0.4),
+        }
+    }
+
+    /// 効果の持続時間（秒）。すぐに効果が出るものは None
+    pub const fn duration(self) -> Option<f32> {
+        match self {
+            Self::SpreadShot => Some(10.0),
+            Self::RapidCharge => Some(12.0),
+            Self::Piercing => Some(8.0),
+            Self::Shield => Some(6.0),
+            Self::Heal => None,
+        }
+    }
+
+    /// 重み付きでアイテムの種類を抽選する
+    fn pick(rng: &mut impl rand::Rng) -> PowerUpKind {
+        let total: u32 = DROP_TABLE.iter().map(|(_, weight)| weight).sum();
+        let mut roll = rng.random_range(0..total);
+        for (kind, weight) in DROP_TABLE {
+            if roll < weight {
+                return kind;
+            }
+            roll -= weight;
+        }
+        Self::Heal
+    }
+}
+
+/// 落下中のアイテムのコンポーネント
+#[derive(Component)]
+#[require(Interpolated)]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+}
+
+/// 時間制限付きの効果のコンポーネントが実装するトレイト
+pub(super) trait TimedEffect: Component<Mutability = Mutable> + Sized {
+    /// 対応するアイテムの種類
+    const KIND: PowerUpKind;
+
+    /// 持続時間のタイマーから効果を作る
+    fn from_timer(timer: Timer) -> Self;
+    /// 持続時間のタイマー
+    fn timer(&self) -> &Timer;
+    /// 持続時間のタイマー（変更用）
+    fn timer_mut(&mut self) -> &mut Timer;
+
+    /// 持続時間いっぱいの効果を作る
+    fn full() -> Self {
+        let secs = Self::KIND.duration().unwrap_or_default();
+        Self::from_timer(Timer::from_seconds(secs, TimerMode::Once))
+    }
+}
+
+/// 3方向に弾を撃つ効果
+#[derive(Component)]
+pub struct SpreadShot(Timer);
+
+/// 弾のチャージが速くなる効果
+#[derive(Component)]
+pub struct RapidCharge(Timer);
+
+/// 弾が敵を貫通する効果
+#[derive(Component)]
+pub struct Piercing(Timer);
+
+/// ダメージを受けなくなる効果
+#[derive(Component)]
+pub struct Shield(Timer);
+
+impl TimedEffect for SpreadShot {
+    const KIND: PowerUpKind = PowerUpKind::SpreadShot;
+    fn from_timer(timer: Timer) -> Self {
+        Self(timer)
+    }
+    fn timer(&self) -> &Timer {
+        &self.0
+    }
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.0
+    }
+}
+
+impl TimedEffect for RapidCharge {
+    const KIND: PowerUpKind = PowerUpKind::RapidCharge;
+    fn from_timer(timer: Timer) -> Self {
+        Self(timer)
+    }
+    fn timer(&self) -> &Timer {
+        &self.0
+    }
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.0
+    }
+}
+
+impl TimedEffect for Piercing {
+    const KIND: PowerUpKind = PowerUpKind::Piercing;
+    fn from_timer(timer: Timer) -> Self {
+        Self(timer)
+    }
+    fn timer(&self) -> &Timer {
+        &self.0
+    }
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.0
+    }
+}
+
+impl TimedEffect for Shield {
+    const KIND: PowerUpKind = PowerUpKind::Shield;
+    fn from_timer(timer: Timer) -> Self {
+        Self(timer)
+    }
+    fn timer(&self) -> &Timer {
+        &self.0
+    }
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.0
+    }
+}
+
+/// 敵を貫通する弾のコンポーネント（同じ敵に何度も当たらないよう、当たった敵を覚える）
+#[derive(Component, Default)]
+pub struct PiercingBullet {
+    pub hit: Vec<Entity>,
+}
+
+/// 効果ゲージの行のマーカーコンポーネント
+#[derive(Component)]
+pub struct PowerUpIcons;
+
+/// 効果ゲージのコンポーネント
+#[derive(Component)]
+pub struct PowerUpGauge {
+    /// 何人目のプレイヤーの表示か
+    player: usize,
+    /// どの効果のゲージか
+    kind: PowerUpKind,
+}
+
+/// 倒した敵の位置に確率でアイテムを落とす
+pub fn roll_drop(commands: &mut Commands, rng: &mut impl rand::Rng, position: Vec2) {
+    if rng.random::<f32>() >= DROP_CHANCE {
+        return;
+    }
+
+    spawn_power_up(commands, PowerUpKind::pick(rng), position);
+}
+
+/// position に kind のアイテムを出す
+pub fn spawn_power_up(commands: &mut Commands, kind: PowerUpKind, position: Vec2) {
+    commands.spawn((
+        Sprite::from_color(kind.color(), POWER_UP_SIZE),
+        Transform::from_xyz(position.x, position.y, 0.0),
+        PowerUp { kind },
+        Collider::aabb(
+            POWER_UP_SIZE,
+            CollisionLayers::POWER_UP,
+            CollisionLayers::NONE,
+        ),
+        DespawnOnExit(GameState::Game),
+    ));
+}
+
+/// HUD に効果ゲージの行を作る（効果が切れているゲージは非表示）
+pub(super) fn spawn_power_up_icons(parent: &mut ChildSpawnerCommands, player: usize) {
+    parent
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                margin: UiRect::top(Val::Px(6.0)),
+                ..default()
+            },
+            PowerUpIcons,
+        ))
+        .with_children(|row| {
+            for kind in TIMED_KINDS {
+                row.spawn((
+                    Node {
+                        display: Display::None,
+                        width: Val::Px(GAUGE_WIDTH),
+                        height: Val::Px(GAUGE_HEIGHT),
+                        margin: UiRect::right(Val::Px(8.0)),
+                        ..default()
+                    },
+                    BackgroundColor(kind.color()),
+                    PowerUpGauge { player, kind },
+                ));
+            }
+        });
+}
+
+/// アイテムを落下させ、画面外に出たら削除するシステム
+pub(super) fn power_up_movement(
+    mut commands: Commands,
+    time: Res<Time>,
+    playfield: Res<Playfield>,
+    mut query: Query<(Entity, &mut Transform), With<PowerUp>>,
+) {
+    let window_half_height = playfield.half_size.y;
+
+    for (entity, mut transform) in &mut query {
+        transform.translation.y -= POWER_UP_FALL_SPEED * time.delta_secs();
+
+        if transform.translation.y < -window_half_height - POWER_UP_SIZE.y {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// プレイヤーがアイテムに触れたら効果を与えるシステム
+///
+/// 同じ効果をもう一度取ると持続時間が最初からになる
+pub(super) fn collect_power_ups(
+    mut commands: Commands,
+    mut collisions: MessageReader<Collision>,
+    mut player_query: Query<&mut HP, With<Player>>,
+    power_up_query: Query<&PowerUp>,
+    mut sfx: MessageWriter<PlaySfx>,
+) {
+    // このティックで拾われたアイテム（2人に同時に当たっても1人だけが拾う）
+    let mut collected = Vec::new();
+
+    for collision in collisions.read() {
+        let Ok(mut hp) = player_query.get_mut(collision.a) else {
+            continue;
+        };
+        let Ok(power_up) = power_up_query.get(collision.b) else {
+            continue;
+        };
+        if collected.contains(&collision.b) {
+            continue;
+        }
+        collected.push(collision.b);
+
+        commands.entity(collision.b).despawn();
+        sfx.write(PlaySfx(Sfx::PowerUp));
+        let mut player = commands.entity(collision.a);
+        match power_up.kind {
+            PowerUpKind::SpreadShot => {
+                player.insert(SpreadShot::full());
+            }
+            PowerUpKind::RapidCharge => {
+                player.insert(RapidCharge::full());
+            }
+            PowerUpKind::Piercing => {
+                player.insert(Piercing::full());
+            }
+            PowerUpKind::Shield => {
+                player.insert(Shield::full());
+            }
+            PowerUpKind::Heal => {
+                hp.0 = (hp.0 + 1).min(PLAYER_MAX_HP);
+            }
+        }
+    }
+}
+
+/// 効果の持続時間を進め、切れたらコンポーネントを外すシステム
+///
+/// ポーズ中は実行されないので、ポーズしている間は効果が減らない
+pub(super) fn tick_effect<T: TimedEffect>(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut T)>,
+) {
+    for (entity, mut effect) in &mut query {
+        if effect.timer_mut().tick(time.delta()).is_finished() {
+            commands.entity(entity).remove::<T>();
+        }
+    }
+}
+
+/// 効果ゲージの表示を残り時間に合わせて更新するシステム
+///
+/// 倒されたプレイヤーのゲージは非表示にする
+pub(super) fn update_effect_gauge<T: TimedEffect>(
+    player_query: Query<(&Player, Option<&T>)>,
+    mut gauge_query: Query<(&PowerUpGauge, &mut Node)>,
+) {
+    for (gauge, mut node) in &mut gauge_query {
+        if gauge.kind != T::KIND {
+            continue;
+        }
+        let effect = player_query
+            .iter()
+            .find(|(player, _)| player.index == gauge.player)
+            .and_then(|(_, effect)| effect);
+        match effect {
+            Some(effect) => {
+                node.display = Display::Flex;
+                node.width = Val::Px(GAUGE_WIDTH * effect.timer().fraction_remaining());
+            }
+            None => node.display = Display::None,
+        }
+    }
+}
@@ -0,0 +1,81 @@
+/// ゲームプレイ用の乱数
+///
+/// プレイごとにシードを決め、ステージの開始時にシードとステージ番号から乱数生成器を初期化する。
+/// 同じシードで同じ操作をすれば、同じ展開になる
+use bevy::prelude::*;
+use rand::rngs::Xoshiro256PlusPlus;
+use rand::{RngExt, SeedableRng};
+
+use crate::plugins::campaign::Campaign;
+
+/// ステージ番号をシードに混ぜるための定数（黄金比から作った奇数）
+const STAGE_SEED_MIX: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// ゲームプレイ用の乱数を保持するリソース
+///
+/// 環境によって結果が変わらないよう、アルゴリズムが固定された Xoshiro256++ を使う
+#[derive(Resource)]
+pub struct GameRng {
+    /// 固定するシード（指定がなければプレイごとにランダムに決める）
+    fixed_seed: Option<u64>,
+    /// 今回のプレイのシード
+    seed: u64,
+    /// 乱数生成器（ステージの開始時に初期化する）
+    rng: Xoshiro256PlusPlus,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl GameRng {
+    /// 乱数のリソースを作る（fixed_seed を指定すると毎回そのシードで遊ぶ）
+    pub fn new(fixed_seed: Option<u64>) -> Self {
+        let seed = fixed_seed.unwrap_or_else(|| rand::rng().random());
+        Self {
+            fixed_seed,
+            seed,
+            rng: Xoshiro256PlusPlus::seed_from_u64(seed),
+        }
+    }
+
+    /// 今回のプレイのシード
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// 今回のプレイのシードを指定する（リプレイの再生用。固定するシードは変えない）
+    pub fn set_run_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// 乱数生成器
+    pub(super) fn rng(&mut self) -> &mut Xoshiro256PlusPlus {
+        &mut self.rng
+    }
+
+    /// ステージの乱数生成器を初期化する
+    fn start_stage(&mut self, stage: usize) {
+        let stage_seed = self.seed ^ (stage as u64 + 1).wrapping_mul(STAGE_SEED_MIX);
+        self.rng = Xoshiro256PlusPlus::seed_from_u64(stage_seed);
+    }
+}
+
+/// プレイの開始時（タイトルや結果画面から出るとき）にシードを決め直すシステム
+pub(super) fn start_run(mut game_rng: ResMut<GameRng>) {
+    if game_rng.fixed_seed.is_none() {
+        game_rng.seed = rand::rng().random();
+    }
+}
+
+/// ステージの開始時に乱数生成器を初期化するシステム
+pub(super) fn start_stage(mut game_rng: ResMut<GameRng>, campaign: Res<Campaign>) {
+    game_rng.start_stage(campaign.current);
+    info!(
+        "stage {} started with seed {}",
+        campaign.current + 1,
+        game_rng.seed()
+    );
+}
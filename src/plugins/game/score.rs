@@ -0,0 +1,161 @@
+/// スコア関連
+use bevy::prelude::*;
+
+use super::{ENEMY_SIZE_INITIAL, MAX_PLAYERS};
+use crate::plugins::locale::{LocalizedText, Strings};
+
+/// コンボ倍率が1段階上がるまでの連続撃破数
+const COMBO_STEP: u32 = 5;
+/// コンボ倍率の上限
+const COMBO_MULTIPLIER_MAX: u32 = 8;
+
+/// 1プレイ分のスコアを保持するリソース
+#[derive(Resource, Default, Clone, Debug)]
+pub struct Score {
+    /// 獲得点数
+    pub points: u32,
+    /// 撃破数
+    pub kills: u32,
+    /// 発射した弾の数
+    pub shots: u32,
+    /// 命中した弾の数
+    pub hits: u32,
+    /// 現在の連続撃破数
+    pub combo: u32,
+    /// 最大連続撃破数
+    pub max_combo: u32,
+    /// プレイ時間（秒）。ステージをまたいで累計する
+    pub time: f32,
+    /// 遊んだ人数
+    pub players: usize,
+    /// プレイヤーごとの獲得点数（points の内訳）
+    pub player_points: [u32; MAX_PLAYERS],
+}
+
+impl Score {
+    /// 現在のコンボ倍率（COMBO_STEP 体ごとに1上がる）
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.combo / COMBO_STEP).min(COMBO_MULTIPLIER_MAX)
+    }
+
+    /// 命中率（0.0 ~ 1.0）。1発も撃っていなければ0.0
+    pub fn accuracy(&self) -> f32 {
+        if self.shots == 0 {
+            0.0
+        } else {
+            (self.hits as f32 / self.shots as f32).min(1.0)
+        }
+    }
+
+    /// player 番目のプレイヤーに点数を加える
+    pub fn add_points(&mut self, player: usize, points: u32) {
+        self.points += points;
+        if let Some(player_points) = self.player_points.get_mut(player) {
+            *player_points += points;
+        }
+    }
+
+    /// player 番目のプレイヤーが敵を撃破したときの加点処理。加算した点数を返す
+    ///
+    /// base_points はアーキタイプの基本点、base_side はアーキタイプの倍率を除いた敵のサイズ。
+    /// 小さい敵ほど高得点になるよう、初期サイズとの比で基本点を拡大する。
+    /// コンボは2人で共有する
+    pub fn register_kill(&mut self, player: usize, base_points: f32, base_side: f32) -> u32 {
+        let base = (base_points * ENEMY_SIZE_INITIAL.x / base_side.max(1.0)).round() as u32;
+        let gained = base * self.multiplier();
+
+        self.add_points(player, gained);
+        self.kills += 1;
+        self.combo += 1;
+        self.max_combo = self.max_combo.max(self.combo);
+
+        gained
+    }
+
+    /// 敵を取り逃がしたときにコンボをリセットする
+    pub fn break_combo(&mut self) {
+        self.combo = 0;
+    }
+}
+
+/// 獲得点数表示のマーカーコンポーネント
+#[derive(Component)]
+pub(super) struct PointsText;
+
+/// コンボ表示のマーカーコンポーネント
+#[derive(Component)]
+pub(super) struct ComboText;
+
+/// 新しいプレイを始めるときにスコアをリセットするシステム
+///
+/// ステージ間では引き継ぐため、タイトル・結果画面を出るときに呼ぶ
+pub(super) fn reset_score(mut score: ResMut<Score>) {
+    *score = Score::default();
+}
+
+/// スコアとコンボのUI表示を更新するシステム
+pub(super) fn update_score_ui(
+    score: Res<Score>,
+    mut points_query: Query<&mut LocalizedText, (With<PointsText>, Without<ComboText>)>,
+    mut combo_query: Query<&mut LocalizedText, (With<ComboText>, Without<PointsText>)>,
+) {
+    if !score.is_changed() {
+        return;
+    }
+    if let Ok(mut text) = points_query.single_mut() {
+        *text = points_label(&score);
+    }
+    if let Ok(mut text) = combo_query.single_mut() {
+        *text = combo_label(&score);
+    }
+}
+
+/// 獲得点数の表示
+pub(super) fn points_label(score: &Score) -> LocalizedText {
+    LocalizedText::new("hud.score").with_arg("points", score.points)
+}
+
+/// コンボの表示
+pub(super) fn combo_label(score: &Score) -> LocalizedText {
+    LocalizedText::new("hud.combo")
+        .with_arg("combo", score.combo)
+        .with_arg("multiplier", score.multiplier())
+}
+
+/// リザルト画面に表示するスコアの内訳
+///
+/// 2人プレイのときはプレイヤーごとの点数も表示する
+pub fn result_breakdown(score: &Score, strings: &Strings) -> String {
+    let total_secs = score.time.max(0.0) as u32;
+    let mut lines = vec![
+        strings.format("result.score", &[("points", score.points.to_string())]),
+        strings.format("result.kills", &[("kills", score.kills.to_string())]),
+        strings.format(
+            "result.accuracy",
+            &[("accuracy", format!("{:.1}", score.accuracy() * 100.0))],
+        ),
+        strings.format(
+            "result.max_combo",
+            &[("combo", score.max_combo.to_string())],
+        ),
+        strings.format(
+            "result.time",
+            &[(
+                "time",
+                format!("{:02}:{:02}", total_secs / 60, total_secs % 60),
+            )],
+        ),
+    ];
+    if score.players > 1 {
+        for (index, points) in score.player_points.iter().take(score.players).enumerate() {
+            lines.push(strings.format(
+                "result.player_points",
+                &[
+                    ("player", (index + 1).to_string()),
+                    ("points", points.to_string()),
+                ],
+            ));
+        }
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,604 @@
+/// ステージ定義（アセットファイルによるウェーブのスクリプト）
+use std::fmt;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::enemy::{self, EnemyArchetype, EnemyMotion};
+use super::feedback::INVULNERABILITY_SECS;
+use super::{
+    ENEMY_SIZE_INITIAL, ENEMY_SIZE_MIN, ENEMY_SIZE_STEP_AMOUNT, ENEMY_SIZE_STEP_SECS, ENEMY_TOTAL,
+    GameElapsedTime, Playfield, SPAWN_INTERVAL_INITIAL, SPAWN_INTERVAL_MIN,
+    SPAWN_INTERVAL_STEP_AMOUNT, SPAWN_INTERVAL_STEP_SECS,
+};
+use crate::plugins::campaign::{CAMPAIGN_STAGES, Campaign};
+
+/// ステージ定義ファイルの拡張子
+const STAGE_EXTENSION: &str = "stage.ron";
+/// 1つのフォーメーションに並べられる敵の最大数
+const FORMATION_COUNT_MAX: u32 = 32;
+/// 指紋の計算（FNV-1a）の初期値
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+/// 指紋の計算（FNV-1a）で掛ける素数
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// ステージ定義
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone, Debug)]
+pub struct StageDefinition {
+    /// ステージ名
+    #[serde(default = "default_stage_name")]
+    pub name: String,
+    /// 倒すべき敵の数
+    #[serde(default = "default_enemy_total")]
+    pub enemy_total: u32,
+    /// ランダムなスポーンの間隔（秒）
+    #[serde(default = "StepCurve::default_spawn_interval")]
+    pub spawn_interval: StepCurve,
+    /// 敵の基本サイズ（ピクセル）
+    #[serde(default = "StepCurve::default_enemy_size")]
+    pub enemy_size: StepCurve,
+    /// スクリプトのウェーブとは別にランダムなスポーンを行うかどうか
+    #[serde(default = "default_random_spawns")]
+    pub random_spawns: bool,
+    /// 背景色（sRGB の 0.0 ~ 1.0）
+    #[serde(default)]
+    pub background: (f32, f32, f32),
+    /// 被弾した後の無敵時間（秒）
+    #[serde(default = "default_invulnerability_secs")]
+    pub invulnerability_secs: f32,
+    /// 経過時間順に出現するウェーブ
+    #[serde(default)]
+    pub waves: Vec<Wave>,
+}
+
+impl Default for StageDefinition {
+    fn default() -> Self {
+        Self {
+            name: default_stage_name(),
+            enemy_total: default_enemy_total(),
+            spawn_interval: StepCurve::default_spawn_interval(),
+            enemy_size: StepCurve::default_enemy_size(),
+            random_spawns: default_random_spawns(),
+            background: (0.0, 0.0, 0.0),
+            invulnerability_secs: default_invulnerability_secs(),
+            waves: Vec::new(),
+        }
+    }
+}
+
+fn default_stage_name() -> String {
+    "STAGE".to_string()
+}
+
+fn default_enemy_total() -> u32 {
+    ENEMY_TOTAL
+}
+
+fn default_random_spawns() -> bool {
+    true
+}
+
+fn default_invulnerability_secs() -> f32 {
+    INVULNERABILITY_SECS
+}
+
+/// 経過時間に応じて段階的に減っていく値
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct StepCurve {
+    /// 初期値
+    pub initial: f32,
+    /// 下限
+    pub min: f32,
+    /// 何秒ごとに減らすか
+    pub step_secs: f32,
+    /// 1ステップあたりの減少量
+    pub step_amount: f32,
+}
+
+impl StepCurve {
+    /// スポーン間隔の既定値
+    fn default_spawn_interval() -> Self {
+        Self {
+            initial: SPAWN_INTERVAL_INITIAL,
+            min: SPAWN_INTERVAL_MIN,
+            step_secs: SPAWN_INTERVAL_STEP_SECS,
+            step_amount: SPAWN_INTERVAL_STEP_AMOUNT,
+        }
+    }
+
+    /// 敵の基本サイズの既定値
+    fn default_enemy_size() -> Self {
+        Self {
+            initial: ENEMY_SIZE_INITIAL.x,
+            min: ENEMY_SIZE_MIN,
+            step_secs: ENEMY_SIZE_STEP_SECS,
+            step_amount: ENEMY_SIZE_STEP_AMOUNT,
+        }
+    }
+
+    /// 経過時間に対する値
+    pub fn at(&self, elapsed_secs: f32) -> f32 {
+        let steps = (elapsed_secs / self.step_secs).floor();
+        (self.initial - steps * self.step_amount).max(self.min)
+    }
+
+    /// 値の妥当性を確認し、問題があれば errors に追加する
+    fn validate(&self, field: &str, errors: &mut Vec<String>) {
+        if !is_positive(self.initial) {
+            errors.push(format!("{field}.initial must be a positive number"));
+        }
+        if !is_positive(self.min) {
+            errors.push(format!("{field}.min must be a positive number"));
+        }
+        if self.min > self.initial {
+            errors.push(format!("{field}.min must not exceed initial"));
+        }
+        if !is_positive(self.step_secs) {
+            errors.push(format!("{field}.step_secs must be a positive number"));
+        }
+        if !is_non_negative(self.step_amount) {
+            errors.push(format!("{field}.step_amount must be a non-negative number"));
+        }
+    }
+}
+
+/// スクリプトで出現する敵の一団
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Wave {
+    /// 出現する時刻（ゲーム開始からの秒数）
+    pub time: f32,
+    /// 敵の種類
+    pub archetype: EnemyArchetype,
+    /// フォーメーションの中心のX座標（画面中央が0）
+    #[serde(default)]
+    pub x: f32,
+    /// 並び方
+    #[serde(default)]
+    pub formation: Formation,
+    /// 動き方（省略時はアーキタイプの既定の動き）
+    #[serde(default)]
+    pub path: Option<MovementPath>,
+}
+
+/// ウェーブの並び方
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub enum Formation {
+    /// 1体だけ
+    #[default]
+    Single,
+    /// 横一列
+    Line { count: u32, spacing: f32 },
+    /// 縦一列（後ろの敵ほど画面外の上から遅れて入ってくる）
+    Column { count: u32, spacing: f32 },
+    /// 中央を先頭にしたV字
+    V { count: u32, spacing: f32 },
+}
+
+impl Formation {
+    /// フォーメーション内の各敵の位置（先頭からのオフセット）
+    fn offsets(&self) -> Vec<Vec2> {
+        match *self {
+            Self::Single => vec![Vec2::ZERO],
+            Self::Line { count, spacing } => (0..count)
+                .map(|i| Vec2::new(centered(i, count) * spacing, 0.0))
+                .collect(),
+            Self::Column { count, spacing } => (0..count)
+                .map(|i| Vec2::new(0.0, i as f32 * spacing))
+                .collect(),
+            Self::V { count, spacing } => (0..count)
+                .map(|i| {
+                    let x = centered(i, count);
+                    Vec2::new(x * spacing, x.abs() * spacing)
+                })
+                .collect(),
+        }
+    }
+
+    /// 並べる敵の数と間隔
+    fn count_and_spacing(&self) -> Option<(u32, f32)> {
+        match *self {
+            Self::Single => None,
+            Self::Line { count, spacing }
+            | Self::Column { count, spacing }
+            | Self::V { count, spacing } => Some((count, spacing)),
+        }
+    }
+}
+
+/// count 個を中央揃えで並べたときの i 番目の位置（-n/2 ~ n/2）
+fn centered(i: u32, count: u32) -> f32 {
+    i as f32 - (count as f32 - 1.0) / 2.0
+}
+
+/// ウェーブの動き方
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum MovementPath {
+    /// 真下に落ちる
+    Straight,
+    /// 正弦波で左右に揺れながら落ちる
+    Sine { amplitude: f32, frequency: f32 },
+    /// 指定した座標を順番にたどり、最後の点からは真下に落ちる
+    Waypoints(Vec<(f32, f32)>),
+}
+
+impl MovementPath {
+    /// 敵の動き方に変換する
+    fn to_motion(&self) -> EnemyMotion {
+        match self {
+            Self::Straight => EnemyMotion::Straight,
+            Self::Sine {
+                amplitude,
+                frequency,
+            } => EnemyMotion::ZigZag {
+                amplitude: *amplitude,
+                frequency: *frequency,
+            },
+            Self::Waypoints(points) => {
+                EnemyMotion::Path(points.iter().map(|&(x, y)| Vec2::new(x, y)).collect())
+            }
+        }
+    }
+}
+
+impl StageDefinition {
+    /// 背景色
+    pub fn background_color(&self) -> Color {
+        let (r, g, b) = self.background;
+        Color::srgb(r, g, b)
+    }
+
+    /// 内容から計算した指紋（リプレイが同じステージ定義で記録されたかを確かめるのに使う）
+    ///
+    /// 実行環境やビルドによって変わらないよう、RON に書き出した文字列から FNV-1a で計算する
+    pub fn fingerprint(&self) -> u64 {
+        let text = ron::to_string(self).unwrap_or_default();
+        text.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+    }
+
+    /// ステージ定義の妥当性を確認し、問題の一覧を返す
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.enemy_total == 0 {
+            errors.push("enemy_total must be at least 1".to_string());
+        }
+        self.spawn_interval.validate("spawn_interval", &mut errors);
+        self.enemy_size.validate("enemy_size", &mut errors);
+        let (r, g, b) = self.background;
+        if [r, g, b].iter().any(|c| !(0.0..=1.0).contains(c)) {
+            errors.push("background components must be within 0.0..=1.0".to_string());
+        }
+        if !is_non_negative(self.invulnerability_secs) {
+            errors.push("invulnerability_secs must be a non-negative number".to_string());
+        }
+
+        for (i, wave) in self.waves.iter().enumerate() {
+            if !is_non_negative(wave.time) {
+                errors.push(format!("waves[{i}].time must be a non-negative number"));
+            }
+            if !wave.x.is_finite() {
+                errors.push(format!("waves[{i}].x must be a finite number"));
+            }
+            if let Some((count, spacing)) = wave.formation.count_and_spacing() {
+                if count == 0 || count > FORMATION_COUNT_MAX {
+                    errors.push(format!(
+                        "waves[{i}].formation count must be within 1..={FORMATION_COUNT_MAX}"
+                    ));
+                }
+                if !is_non_negative(spacing) {
+                    errors.push(format!("waves[{i}].formation spacing must not be negative"));
+                }
+            }
+            match &wave.path {
+                Some(MovementPath::Sine {
+                    amplitude,
+                    frequency,
+                }) if !is_non_negative(*amplitude) || !is_positive(*frequency) => {
+                    errors.push(format!(
+                        "waves[{i}].path Sine needs amplitude >= 0 and frequency > 0"
+                    ));
+                }
+                Some(MovementPath::Waypoints(points)) if points.is_empty() => {
+                    errors.push(format!("waves[{i}].path Waypoints must not be empty"));
+                }
+                _ => {}
+            }
+        }
+
+        // ランダムなスポーンがなければ、ウェーブの敵だけで討伐数に届かないとボスが出てこない
+        let scripted_total = self.scripted_enemy_total();
+        if !self.random_spawns && scripted_total < self.enemy_total {
+            errors.push(format!(
+                "waves spawn only {scripted_total} enemies toward enemy_total {} without random_spawns",
+                self.enemy_total
+            ));
+        }
+
+        errors
+    }
+
+    /// ウェーブで出現する敵のうち、討伐数に数える敵の合計
+    fn scripted_enemy_total(&self) -> u32 {
+        self.waves
+            .iter()
+            .filter(|wave| wave.archetype.stats().counts_toward_total)
+            .map(|wave| wave.formation.offsets().len() as u32)
+            .sum()
+    }
+
+    /// 画面の広さに対して、ウェーブの配置の問題の一覧を返す
+    ///
+    /// 画面の広さはウィンドウの大きさで変わるため、読み込み時ではなくステージの開始時に確認する。
+    /// 敵は最も大きいサイズ（enemy_size.initial）で確かめる
+    pub fn layout_errors(&self, half_size: Vec2) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        for (i, wave) in self.waves.iter().enumerate() {
+            let side = self.enemy_size.initial * wave.archetype.stats().size_scale;
+            let (left, right) = wave.horizontal_reach(side);
+            if wave.x + left < -half_size.x || wave.x + right > half_size.x {
+                errors.push(format!(
+                    "waves[{i}] formation at x = {} leaves the playfield (-{}..={})",
+                    wave.x, half_size.x, half_size.x
+                ));
+            }
+            if let Some(MovementPath::Waypoints(points)) = &wave.path
+                && points
+                    .iter()
+                    .any(|&(x, y)| x.abs() > half_size.x || y.abs() > half_size.y)
+            {
+                errors.push(format!(
+                    "waves[{i}].path Waypoints must be within the playfield ({} x {})",
+                    half_size.x * 2.0,
+                    half_size.y * 2.0
+                ));
+            }
+        }
+
+        errors
+    }
+}
+
+impl Wave {
+    /// フォーメーションの中心から見た、敵の左端と右端までの距離（左は負の値）
+    ///
+    /// side は敵1体の大きさ。正弦波で揺れる場合はその振れ幅も含める
+    fn horizontal_reach(&self, side: f32) -> (f32, f32) {
+        let offsets = self.formation.offsets();
+        let min = offsets.iter().map(|o| o.x).fold(0.0, f32::min);
+        let max = offsets.iter().map(|o| o.x).fold(0.0, f32::max);
+        let sway = match self.path {
+            Some(MovementPath::Sine { amplitude, .. }) => amplitude,
+            _ => 0.0,
+        };
+        (min - side / 2.0 - sway, max + side / 2.0 + sway)
+    }
+
+    /// 画面からはみ出さないよう寄せたフォーメーションの中心のX座標
+    ///
+    /// 画面より幅の広いフォーメーションは中央に置く
+    fn clamped_x(&self, side: f32, half_width: f32) -> f32 {
+        let (left, right) = self.horizontal_reach(side);
+        let (min_x, max_x) = (-half_width - left, half_width - right);
+        if min_x > max_x {
+            (min_x + max_x) / 2.0
+        } else {
+            self.x.clamp(min_x, max_x)
+        }
+    }
+}
+
+/// 有限の正の数かどうか（NaN と無限大は false）
+fn is_positive(value: f32) -> bool {
+    value.is_finite() && value > 0.0
+}
+
+/// 有限の0以上の数かどうか（NaN と無限大は false）
+fn is_non_negative(value: f32) -> bool {
+    value.is_finite() && value >= 0.0
+}
+
+/// ステージ定義の読み込みエラー
+#[derive(Debug)]
+pub enum StageLoadError {
+    /// ファイルを読めなかった
+    Io(std::io::Error),
+    /// RON として解釈できなかった
+    Parse(ron::error::SpannedError),
+    /// 内容に問題があった
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for StageLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read stage file: {err}"),
+            Self::Parse(err) => write!(f, "failed to parse stage file: {err}"),
+            Self::Invalid(errors) => {
+                write!(f, "invalid stage definition: {}", errors.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for StageLoadError {}
+
+/// ステージ定義ファイル（*.stage.ron）のローダー
+#[derive(Default, TypePath)]
+pub struct StageLoader;
+
+impl AssetLoader for StageLoader {
+    type Asset = StageDefinition;
+    type Settings = ();
+    type Error = StageLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(StageLoadError::Io)?;
+        let mut stage: StageDefinition =
+            ron::de::from_bytes(&bytes).map_err(StageLoadError::Parse)?;
+
+        let errors = stage.validate();
+        if !errors.is_empty() {
+            return Err(StageLoadError::Invalid(errors));
+        }
+
+        // ウェーブは時刻順に並べておく
+        stage.waves.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(stage)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[STAGE_EXTENSION]
+    }
+}
+
+/// キャンペーンの各ステージ定義のハンドルを保持するリソース（CAMPAIGN_STAGES と同じ順）
+#[derive(Resource)]
+pub struct StageHandles(pub Vec<Handle<StageDefinition>>);
+
+/// 現在のステージ定義を保持するリソース
+///
+/// アセットの読み込みが終わるまで（読み込めなかった場合も）は既定値を使う
+#[derive(Resource, Default)]
+pub struct ActiveStage(pub StageDefinition);
+
+/// スクリプトのウェーブの進み具合を保持するリソース
+#[derive(Resource, Default)]
+pub struct StageProgress {
+    /// 次に出現させるウェーブの番号
+    next_wave: usize,
+}
+
+/// 起動時にキャンペーンの全ステージ定義を読み込むシステム
+pub(super) fn load_stages(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = CAMPAIGN_STAGES
+        .iter()
+        .map(|path| asset_server.load(*path))
+        .collect();
+    commands.insert_resource(StageHandles(handles));
+}
+
+/// キャンペーンの全ステージ定義の読み込みが終わったか（失敗したものも含む）を返す実行条件
+pub fn stages_loaded(handles: Option<Res<StageHandles>>, asset_server: Res<AssetServer>) -> bool {
+    handles.is_some_and(|handles| {
+        handles.0.iter().all(|handle| {
+            asset_server.is_loaded_with_dependencies(handle)
+                || asset_server.load_state(handle).is_failed()
+        })
+    })
+}
+
+/// 現在のステージの定義を ActiveStage に反映するシステム
+///
+/// ステージが切り替わったとき、定義の読み込みが終わったとき、再読み込みされたときに更新する。
+/// ファイルの変更は AssetPlugin が監視し、再読み込みすると AssetEvent::Modified が届く。
+/// 状態遷移より前に反映されるよう PostUpdate で実行する
+pub(super) fn sync_active_stage(
+    mut events: MessageReader<AssetEvent<StageDefinition>>,
+    campaign: Res<Campaign>,
+    handles: Option<Res<StageHandles>>,
+    stages: Res<Assets<StageDefinition>>,
+    game_elapsed_time: Res<GameElapsedTime>,
+    mut active_stage: ResMut<ActiveStage>,
+    mut progress: ResMut<StageProgress>,
+) {
+    let Some(handle) = handles.as_ref().and_then(|h| h.0.get(campaign.current)) else {
+        return;
+    };
+
+    let mut reloaded = false;
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && *id == handle.id()
+        {
+            reloaded = true;
+        }
+    }
+    if !reloaded && !campaign.is_changed() {
+        return;
+    }
+
+    let Some(stage) = stages.get(handle) else {
+        // まだ読み込まれていない（または読み込みに失敗した）ステージは既定値で遊ぶ
+        active_stage.0 = StageDefinition::default();
+        return;
+    };
+
+    if reloaded {
+        info!(
+            "stage \"{}\" loaded ({} waves)",
+            stage.name,
+            stage.waves.len()
+        );
+    }
+    active_stage.0 = stage.clone();
+    // プレイ中に再読み込みした場合は、経過時間より前のウェーブを出し直さない
+    progress.next_wave = stage
+        .waves
+        .iter()
+        .take_while(|wave| wave.time <= game_elapsed_time.0)
+        .count();
+}
+
+/// ゲーム開始時にウェーブの進み具合をリセットするシステム
+pub(super) fn reset_stage_progress(mut progress: ResMut<StageProgress>) {
+    progress.next_wave = 0;
+}
+
+/// ステージの開始時に、画面からはみ出すウェーブがあれば警告するシステム
+///
+/// はみ出すフォーメーションは出現時に画面の内側へ寄せる
+pub(super) fn check_stage_layout(active_stage: Res<ActiveStage>, playfield: Res<Playfield>) {
+    for error in active_stage.0.layout_errors(playfield.half_size) {
+        warn!("stage \"{}\": {}", active_stage.0.name, error);
+    }
+}
+
+/// 時刻になったウェーブの敵をspawnするシステム
+pub(super) fn run_stage_waves(
+    mut commands: Commands,
+    game_elapsed_time: Res<GameElapsedTime>,
+    active_stage: Res<ActiveStage>,
+    mut progress: ResMut<StageProgress>,
+    playfield: Res<Playfield>,
+) {
+    let stage = &active_stage.0;
+    let window_half_height = playfield.half_size.y;
+    let base_side = stage.enemy_size.at(game_elapsed_time.0);
+
+    while let Some(wave) = stage.waves.get(progress.next_wave) {
+        if wave.time > game_elapsed_time.0 {
+            break;
+        }
+        progress.next_wave += 1;
+
+        let size = base_side * wave.archetype.stats().size_scale;
+        let x = wave.clamped_x(size, playfield.half_size.x);
+        let origin = Vec2::new(x, window_half_height + size / 2.0);
+        let motion = wave.path.as_ref().map(MovementPath::to_motion);
+
+        for offset in wave.formation.offsets() {
+            enemy::spawn_enemy(
+                &mut commands,
+                wave.archetype,
+                origin + offset,
+                base_side,
+                1.0,
+                motion.clone(),
+            );
+        }
+    }
+}
@@ -0,0 +1,437 @@
+/// プレイヤーの武器と弾
+use bevy::prelude::*;
+
+use super::animation::{AnimationClip, SpriteAnimation};
+use super::boss::Boss;
+use super::collision::{Collider, CollisionLayers, Swept};
+use super::enemy::Enemy;
+use super::interpolation::Interpolated;
+use super::powerup::{Piercing, PiercingBullet, SpreadShot};
+use super::{BulletStock, Player, Playfield, Score};
+use crate::font::DefaultFont;
+use crate::plugins::audio::{PlaySfx, Sfx};
+use crate::plugins::input::{Action, FixedActionState};
+use crate::plugins::locale::LocalizedText;
+use crate::state::GameState;
+
+/// 通常の弾のサイズ
+pub const BULLET_SIZE: Vec2 = Vec2::new(10.0, 20.0);
+/// 弾の色
+const BULLET_COLOR: Color = Color::srgb(1.0, 1.0, 0.0);
+/// ホーミングミサイルの色
+const MISSILE_COLOR: Color = Color::srgb(1.0, 0.5, 0.2);
+/// レーザーの色
+const LASER_COLOR: Color = Color::srgb(0.4, 1.0, 1.0);
+/// レーザーの太さ
+const LASER_WIDTH: f32 = 6.0;
+
+/// SpreadShot の効果中に追加される弾の数
+const SPREAD_SHOT_EXTRA: u32 = 2;
+/// SpreadShot の効果で Single が変わる扇の開き（ラジアン）
+const SPREAD_SHOT_ANGLE: f32 = 0.4;
+
+/// 武器の撃ち方
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WeaponPattern {
+    /// 真上に1発撃つ
+    Single,
+    /// 真上を中心に扇状に撃つ
+    Spread {
+        /// 弾の数
+        count: u32,
+        /// 扇の開き（ラジアン）
+        angle: f32,
+    },
+    /// 敵を貫通する細長い弾を撃つ
+    Laser {
+        /// レーザーの長さ（ピクセル）
+        length: f32,
+    },
+    /// 近くの敵に向かって曲がるミサイルを撃つ
+    Homing {
+        /// ミサイルの数
+        count: u32,
+        /// 1秒あたりに曲がれる角度（ラジアン）
+        turn_rate: f32,
+    },
+}
+
+/// 武器の性能（データとして定義し、撃つ処理は共通）
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Weapon {
+    /// HUD に表示する名前の文字列表のキー
+    pub label_key: &'static str,
+    /// 撃ち方
+    pub pattern: WeaponPattern,
+    /// 弾の速さ（ピクセル/秒）
+    pub speed: f32,
+    /// 1発あたりのダメージ
+    pub damage: u32,
+    /// 1回撃つのに消費する弾のストック
+    pub cost: u32,
+    /// 次に撃てるまでの時間（秒）
+    pub cooldown: f32,
+}
+
+/// Qキーで切り替えられる武器（最初の武器が初期装備）
+pub const ARSENAL: [Weapon; 4] = [
+    Weapon {
+        label_key: "weapon.blaster",
+        pattern: WeaponPattern::Single,
+        speed: 600.0,
+        damage: 1,
+        cost: 1,
+        cooldown: 0.1,
+    },
+    Weapon {
+        label_key: "weapon.spread",
+        pattern: WeaponPattern::Spread {
+            count: 5,
+            angle: 0.8,
+        },
+        speed: 550.0,
+        damage: 1,
+        cost: 2,
+        cooldown: 0.25,
+    },
+    Weapon {
+        label_key: "weapon.laser",
+        pattern: WeaponPattern::Laser { length: 120.0 },
+        speed: 1400.0,
+        damage: 1,
+        cost: 2,
+        cooldown: 0.3,
+    },
+    Weapon {
+        label_key: "weapon.homing",
+        pattern: WeaponPattern::Homing {
+            count: 2,
+            turn_rate: 4.0,
+        },
+        speed: 420.0,
+        damage: 2,
+        cost: 2,
+        cooldown: 0.4,
+    },
+];
+
+impl Default for Weapon {
+    fn default() -> Self {
+        ARSENAL[0]
+    }
+}
+
+impl Weapon {
+    /// SpreadShot の効果中の撃ち方（弾の数が増える）
+    fn spread_pattern(&self) -> WeaponPattern {
+        match self.pattern {
+            WeaponPattern::Single => WeaponPattern::Spread {
+                count: 1 + SPREAD_SHOT_EXTRA,
+                angle: SPREAD_SHOT_ANGLE,
+            },
+            WeaponPattern::Spread { count, angle } => WeaponPattern::Spread {
+                count: count + SPREAD_SHOT_EXTRA,
+                angle: angle * 1.5,
+            },
+            WeaponPattern::Homing { count, turn_rate } => WeaponPattern::Homing {
+                count: count + SPREAD_SHOT_EXTRA,
+                turn_rate,
+            },
+            pattern @ WeaponPattern::Laser { .. } => pattern,
+        }
+    }
+}
+
+/// 次に撃てるまでの残り時間（秒）を管理するコンポーネント
+#[derive(Component, Default)]
+pub struct WeaponCooldown(f32);
+
+/// プレイヤーの弾のコンポーネント
+#[derive(Component)]
+#[require(Interpolated, Swept)]
+pub struct Bullet {
+    /// 速度（ピクセル/秒）
+    pub velocity: Vec2,
+    /// 当たったときのダメージ
+    pub damage: u32,
+    /// 撃ったプレイヤー（Player の index）
+    pub owner: usize,
+}
+
+/// 近くの敵に向かって曲がる弾のコンポーネント
+#[derive(Component)]
+pub struct Homing {
+    /// 1秒あたりに曲がれる角度（ラジアン）
+    turn_rate: f32,
+}
+
+/// 武器名のUI用コンポーネント（何人目のプレイヤーの表示か）
+#[derive(Component)]
+pub struct WeaponText(usize);
+
+/// 武器名の表示
+fn weapon_label(weapon: &Weapon) -> LocalizedText {
+    LocalizedText::new("hud.weapon").with_localized_arg("name", weapon.label_key)
+}
+
+/// HUD に武器名の表示を作る
+pub(super) fn spawn_weapon_label(
+    parent: &mut ChildSpawnerCommands,
+    asset: &DefaultFont,
+    player: usize,
+) {
+    parent.spawn((
+        weapon_label(&Weapon::default()),
+        TextFont {
+            font: asset.font.clone(),
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+        Node {
+            margin: UiRect::top(Val::Px(6.0)),
+            ..default()
+        },
+        WeaponText(player),
+    ));
+}
+
+/// 速度の向きに合わせた弾の回転（弾は上向きが基準）
+fn bullet_rotation(velocity: Vec2) -> Quat {
+    Quat::from_rotation_z(Vec2::Y.angle_to(velocity))
+}
+
+/// 大きさ size のプレイヤーの弾のコライダー（敵とボスに当たる）
+pub fn bullet_collider(size: Vec2) -> Collider {
+    Collider::aabb(
+        size,
+        CollisionLayers::PLAYER_BULLET,
+        CollisionLayers::ENEMY | CollisionLayers::BOSS | CollisionLayers::WEAK_POINT,
+    )
+}
+
+/// 弾を1発spawnする
+fn spawn_bullet(
+    commands: &mut Commands,
+    position: Vec3,
+    velocity: Vec2,
+    weapon: &Weapon,
+    owner: usize,
+    piercing: bool,
+) {
+    let (color, size) = match weapon.pattern {
+        WeaponPattern::Laser { length } => (LASER_COLOR, Vec2::new(LASER_WIDTH, length)),
+        WeaponPattern::Homing { .. } => (MISSILE_COLOR, BULLET_SIZE),
+        _ => (BULLET_COLOR, BULLET_SIZE),
+    };
+
+    let mut bullet = commands.spawn((
+        Sprite::from_color(color, size),
+        Transform::from_translation(position).with_rotation(bullet_rotation(velocity)),
+        Bullet {
+            velocity,
+            damage: weapon.damage,
+            owner,
+        },
+        bullet_collider(size),
+        DespawnOnExit(GameState::Game),
+    ));
+
+    // レーザーと Piercing の効果中の弾は敵を貫通する
+    if piercing || matches!(weapon.pattern, WeaponPattern::Laser { .. }) {
+        bullet.insert(PiercingBullet::default());
+    }
+    if let WeaponPattern::Homing { turn_rate, .. } = weapon.pattern {
+        bullet.insert(Homing { turn_rate });
+    }
+    // レーザーは細長いので絵にせず、四角形のまま表示する
+    if !matches!(weapon.pattern, WeaponPattern::Laser { .. }) {
+        bullet.insert(SpriteAnimation::new(AnimationClip::Bullet));
+    }
+}
+
+/// 扇状に並ぶ count 発の弾の向き（真上が中心）
+fn fan_directions(count: u32, angle: f32) -> impl Iterator<Item = Vec2> {
+    let count = count.max(1);
+    (0..count).map(move |i| {
+        let t = if count == 1 {
+            0.0
+        } else {
+            i as f32 / (count - 1) as f32 - 0.5
+        };
+        Vec2::from_angle(t * angle).rotate(Vec2::Y)
+    })
+}
+
+/// 武器切り替え操作（1PはQ）で武器を切り替えるシステム
+pub(super) fn cycle_weapon(
+    actions: Res<FixedActionState>,
+    mut query: Query<(&mut Weapon, &Player)>,
+) {
+    for (mut weapon, player) in &mut query {
+        if !actions.just_pressed(player.index, Action::CycleWeapon) {
+            continue;
+        }
+
+        let index = ARSENAL
+            .iter()
+            .position(|w| w.label_key == weapon.label_key)
+            .unwrap_or(0);
+        *weapon = ARSENAL[(index + 1) % ARSENAL.len()];
+    }
+}
+
+/// 発射操作（1PはEnter）で装備中の武器を撃つシステム
+pub(super) fn fire_weapon(
+    mut commands: Commands,
+    time: Res<Time>,
+    actions: Res<FixedActionState>,
+    mut query: Query<(
+        Entity,
+        &Player,
+        &Transform,
+        &Weapon,
+        &mut WeaponCooldown,
+        &mut BulletStock,
+    )>,
+    effect_query: Query<(Has<SpreadShot>, Has<Piercing>)>,
+    mut score: ResMut<Score>,
+    mut sfx: MessageWriter<PlaySfx>,
+) {
+    for (player_entity, player, transform, weapon, mut cooldown, mut stock) in &mut query {
+        // 次に撃てるまでの時間を進める
+        cooldown.0 = (cooldown.0 - time.delta_secs()).max(0.0);
+
+        // 発射操作が行われた時だけ発射する
+        if !actions.just_pressed(player.index, Action::Fire) {
+            continue;
+        }
+        // 撃てるようになっていない、または残弾が足りなければ発射しない
+        if cooldown.0 > 0.0 || stock.current < weapon.cost {
+            continue;
+        }
+
+        // 残弾を消費して弾をspawnする
+        stock.current -= weapon.cost;
+        cooldown.0 = weapon.cooldown;
+        sfx.write(PlaySfx(Sfx::Fire));
+
+        // 効果中のパワーアップ
+        let (spread, piercing) = effect_query.get(player_entity).unwrap_or_default();
+        let pattern = if spread {
+            weapon.spread_pattern()
+        } else {
+            weapon.pattern
+        };
+
+        // 命中率のため、撃った弾の数を数える（扇状やミサイルは1回で何発も撃つ）
+        score.shots += fire_pattern(
+            &mut commands,
+            transform.translation,
+            weapon,
+            pattern,
+            player.index,
+            piercing,
+        );
+    }
+}
+
+/// 撃ち方に従って弾をspawnし、spawnした弾の数を返す
+fn fire_pattern(
+    commands: &mut Commands,
+    origin: Vec3,
+    weapon: &Weapon,
+    pattern: WeaponPattern,
+    owner: usize,
+    piercing: bool,
+) -> u32 {
+    // 撃つ位置と向き
+    let shots: Vec<(Vec3, Vec2)> = match pattern {
+        WeaponPattern::Single => vec![(origin, Vec2::Y)],
+        WeaponPattern::Laser { length } => {
+            // レーザーは先端がプレイヤーの前に出るように置く
+            vec![(origin + Vec3::Y * length / 2.0, Vec2::Y)]
+        }
+        WeaponPattern::Spread { count, angle } => fan_directions(count, angle)
+            .map(|direction| (origin, direction))
+            .collect(),
+        WeaponPattern::Homing { count, .. } => {
+            // ミサイルは左右に開いて撃ち出し、そこから敵へ曲がる
+            fan_directions(count, std::f32::consts::FRAC_PI_2)
+                .map(|direction| (origin, direction))
+                .collect()
+        }
+    };
+
+    for &(position, direction) in &shots {
+        let velocity = direction * weapon.speed;
+        spawn_bullet(commands, position, velocity, weapon, owner, piercing);
+    }
+    shots.len() as u32
+}
+
+/// ホーミングミサイルを一番近い敵（ボスを含む）に向けて曲げるシステム
+pub(super) fn steer_homing(
+    time: Res<Time>,
+    mut missile_query: Query<(&mut Transform, &mut Bullet, &Homing)>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<Bullet>)>,
+    boss_query: Query<&Transform, (With<Boss>, Without<Bullet>)>,
+) {
+    for (mut transform, mut bullet, homing) in &mut missile_query {
+        let position = transform.translation.truncate();
+        let Some(target) = enemy_query
+            .iter()
+            .chain(&boss_query)
+            .map(|t| t.translation.truncate())
+            .min_by(|a, b| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            })
+        else {
+            continue;
+        };
+
+        // 1フレームに曲がれる角度の範囲で目標の方向へ向ける
+        let desired = bullet.velocity.angle_to(target - position);
+        let max_turn = homing.turn_rate * time.delta_secs();
+        let turn = desired.clamp(-max_turn, max_turn);
+        bullet.velocity = Vec2::from_angle(turn).rotate(bullet.velocity);
+        transform.rotation = bullet_rotation(bullet.velocity);
+    }
+}
+
+/// 弾を速度に従って移動させ、画面外に出たら削除するシステム
+pub(super) fn bullet_movement(
+    mut commands: Commands,
+    time: Res<Time>,
+    playfield: Res<Playfield>,
+    mut query: Query<(Entity, &mut Transform, &Bullet, &Sprite)>,
+) {
+    // Bevyの2D座標はY=0が画面中央のため、画面の広さの半分が画面端の座標になる
+    let window_half = playfield.half_size;
+
+    for (entity, mut transform, bullet, sprite) in &mut query {
+        transform.translation += (bullet.velocity * time.delta_secs()).extend(0.0);
+
+        // 画面外に完全に出たら削除する
+        let bounds = window_half + sprite.custom_size.unwrap_or(BULLET_SIZE);
+        let pos = transform.translation.truncate();
+        if pos.x.abs() > bounds.x || pos.y.abs() > bounds.y {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// 武器名の表示を更新するシステム
+pub(super) fn update_weapon_ui(
+    player_query: Query<(&Player, &Weapon), Changed<Weapon>>,
+    mut query: Query<(&mut LocalizedText, &WeaponText)>,
+) {
+    for (player, weapon) in &player_query {
+        for (mut text, label) in &mut query {
+            if label.0 == player.index {
+                *text = weapon_label(weapon);
+            }
+        }
+    }
+}
@@ -1,6 +1,12 @@
 use bevy::prelude::*;
 
 use crate::font::DefaultFont;
+use crate::plugins::campaign::Campaign;
+use crate::plugins::game::{Score, result_breakdown};
+use crate::plugins::highscore::{NameEntry, NameEntrySystems};
+use crate::plugins::input::{Action, ActionState};
+use crate::plugins::locale::{LocalizedText, Strings};
+use crate::plugins::replay::LastReplay;
 use crate::state::GameState;
 
 /// ゲームクリア画面のプラグイン
@@ -11,20 +17,25 @@ impl Plugin for GameClearPlugin {
         app.add_systems(OnEnter(GameState::GameClear), (setup_camera, setup_ui));
         app.add_systems(
             Update,
-            gameclear_update.run_if(in_state(GameState::GameClear)),
+            gameclear_update
+                .before(NameEntrySystems)
+                .run_if(not(resource_exists::<NameEntry>))
+                .run_if(in_state(GameState::GameClear)),
         );
     }
 }
 
-/// ゲームクリア画面の更新処理（Rキーでリトライ、Enterでタイトル）
+/// ゲームクリア画面の更新処理（リトライ操作で最初のステージからリトライ、決定でタイトル）
 fn gameclear_update(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
+    mut campaign: ResMut<Campaign>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Enter) {
+    if actions.any_just_pressed(Action::Confirm) {
         next_state.set(GameState::Title);
-    } else if keyboard_input.just_pressed(KeyCode::KeyR) {
-        next_state.set(GameState::Game);
+    } else if actions.any_just_pressed(Action::Retry) {
+        campaign.current = 0;
+        next_state.set(GameState::StageIntro);
     }
 }
 
@@ -34,7 +45,13 @@ fn setup_camera(mut commands: Commands) {
 }
 
 /// UIのセットアップ
-fn setup_ui(mut commands: Commands, asset: Res<DefaultFont>) {
+fn setup_ui(
+    mut commands: Commands,
+    asset: Res<DefaultFont>,
+    score: Res<Score>,
+    strings: Res<Strings>,
+    last_replay: Option<Res<LastReplay>>,
+) {
     commands
         .spawn((
             Node {
@@ -50,7 +67,7 @@ fn setup_ui(mut commands: Commands, asset: Res<DefaultFont>) {
         .with_children(|parent| {
             // ゲームクリアテキスト
             parent.spawn((
-                Text::new("GAME CLEAR!"),
+                LocalizedText::new("gameclear.title"),
                 TextFont {
                     font: asset.font.clone(),
                     font_size: 80.0,
@@ -63,9 +80,25 @@ fn setup_ui(mut commands: Commands, asset: Res<DefaultFont>) {
                 },
             ));
 
+            // スコア内訳
+            parent.spawn((
+                Text::new(result_breakdown(&score, &strings)),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                TextLayout::new_with_justify(Justify::Center),
+                Node {
+                    margin: UiRect::bottom(Val::Px(40.0)),
+                    ..default()
+                },
+            ));
+
             // 説明テキスト
             parent.spawn((
-                Text::new("Press R to Retry\nPress Enter to Title"),
+                LocalizedText::new("result.retry_or_title"),
                 TextFont {
                     font: asset.font.clone(),
                     font_size: 40.0,
@@ -73,5 +106,22 @@ fn setup_ui(mut commands: Commands, asset: Res<DefaultFont>) {
                 },
                 TextColor(Color::WHITE),
             ));
+
+            // リプレイの案内（記録があるときだけ）
+            if last_replay.is_some() {
+                parent.spawn((
+                    LocalizedText::new("result.watch_replay"),
+                    TextFont {
+                        font: asset.font.clone(),
+                        font_size: 28.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                    Node {
+                        margin: UiRect::top(Val::Px(20.0)),
+                        ..default()
+                    },
+                ));
+            }
         });
 }
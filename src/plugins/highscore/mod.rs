@@ -0,0 +1,566 @@
+/// ハイスコア
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::font::DefaultFont;
+use crate::plugins::game::Score;
+use crate::plugins::input::{Action, ActionState};
+use crate::plugins::locale::{LocalizedText, Strings};
+use crate::plugins::replay::ReplayPlayback;
+use crate::state::GameState;
+use crate::storage;
+
+/// ハイスコア表に残す件数
+pub const HIGH_SCORE_LIMIT: usize = 10;
+/// ハイスコアファイルの形式バージョン
+const HIGH_SCORE_FILE_VERSION: u32 = 1;
+/// ハイスコアファイル名
+const HIGH_SCORE_FILE_NAME: &str = "highscores.ron";
+/// イニシャルの文字数
+const INITIALS_LEN: usize = 3;
+
+/// ハイスコアのプラグイン
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        // タイトル画面の OnEnter は Startup より先に走るため、ビルド時に読み込んでおく
+        app.insert_resource(HighScores::load());
+        // リプレイの再生ではハイスコアを記録しない
+        app.add_systems(
+            OnEnter(GameState::GameOver),
+            begin_name_entry.run_if(not(resource_exists::<ReplayPlayback>)),
+        );
+        app.add_systems(
+            OnEnter(GameState::GameClear),
+            begin_name_entry.run_if(not(resource_exists::<ReplayPlayback>)),
+        );
+        app.add_systems(OnExit(GameState::GameOver), cancel_name_entry);
+        app.add_systems(OnExit(GameState::GameClear), cancel_name_entry);
+        app.add_systems(
+            Update,
+            (name_entry_input, update_name_entry_ui)
+                .chain()
+                .in_set(NameEntrySystems)
+                .run_if(resource_exists::<NameEntry>),
+        );
+    }
+}
+
+/// イニシャル入力のシステムセット
+///
+/// 結果画面の操作はこのセットより前に実行し、確定に使った Enter を拾わないようにする
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NameEntrySystems;
+
+/// ハイスコア1件分の記録
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HighScoreEntry {
+    /// プレイヤーのイニシャル
+    #[serde(default)]
+    pub name: String,
+    /// 獲得点数
+    #[serde(default)]
+    pub score: u32,
+    /// 撃破数
+    #[serde(default)]
+    pub kills: u32,
+    /// プレイ時間（秒）
+    #[serde(default)]
+    pub time: f32,
+    /// 記録した日付（YYYY-MM-DD）
+    #[serde(default)]
+    pub date: String,
+}
+
+/// ハイスコアファイルの中身
+///
+/// 読み込むときは記録ごとに解釈できるよう、記録を ron::Value のまま受け取る
+#[derive(Serialize, Deserialize)]
+struct HighScoreFile<E = HighScoreEntry> {
+    /// 形式バージョン
+    version: u32,
+    /// 記録の一覧（E に Default を求めないよう Vec::new を既定値にする）
+    #[serde(default = "Vec::new")]
+    entries: Vec<E>,
+}
+
+/// ハイスコア表を保持するリソース（点数の高い順）
+#[derive(Resource, Default)]
+pub struct HighScores {
+    entries: Vec<HighScoreEntry>,
+    /// 保存先（保存できない環境では None）
+    path: Option<PathBuf>,
+    /// 読み込んだファイルの形式バージョンが新しすぎた場合、そのバージョン（上書きしないよう保存しない）
+    newer_version: Option<u32>,
+}
+
+impl HighScores {
+    /// 記録の一覧（点数の高い順）
+    pub fn entries(&self) -> &[HighScoreEntry] {
+        &self.entries
+    }
+
+    /// この点数が表に入る場合、その順位（0始まり）を返す
+    pub fn qualifies(&self, score: u32) -> Option<usize> {
+        if score == 0 {
+            return None;
+        }
+        let rank = self
+            .entries
+            .iter()
+            .position(|e| score > e.score)
+            .unwrap_or(self.entries.len());
+        (rank < HIGH_SCORE_LIMIT).then_some(rank)
+    }
+
+    /// 記録を追加して上限件数に切り詰める
+    pub fn insert(&mut self, entry: HighScoreEntry) {
+        let rank = self.qualifies(entry.score).unwrap_or(self.entries.len());
+        self.entries.insert(rank, entry);
+        self.entries.truncate(HIGH_SCORE_LIMIT);
+    }
+
+    /// ハイスコアファイルを読み込む
+    fn load() -> Self {
+        Self::load_from(storage::data_dir().map(|dir| dir.join(HIGH_SCORE_FILE_NAME)))
+    }
+
+    /// path のハイスコアファイルを読み込み、そこを保存先にする
+    ///
+    /// 読み込めないファイルは `.bak` に退避して空の表から始める
+    fn load_from(path: Option<PathBuf>) -> Self {
+        let mut entries = Vec::new();
+        let mut newer_version = None;
+
+        if let Some(path) = &path {
+            match storage::read_file(path) {
+                Ok(Some(contents)) => match Self::parse(&contents) {
+                    Ok((version, parsed)) => {
+                        entries = parsed;
+                        if version > HIGH_SCORE_FILE_VERSION {
+                            warn!(
+                                "high score file version {} is newer than supported version {}, new records will not be saved",
+                                version, HIGH_SCORE_FILE_VERSION
+                            );
+                            newer_version = Some(version);
+                        }
+                    }
+                    Err(err) => {
+                        warn!("high score file {} is corrupted: {}", path.display(), err);
+                        if let Err(err) = storage::backup_file(path) {
+                            warn!("failed to back up corrupted high score file: {}", err);
+                        }
+                    }
+                },
+                Ok(None) => {}
+                Err(err) => warn!(
+                    "failed to read high scores from {}: {}",
+                    path.display(),
+                    err
+                ),
+            }
+        }
+
+        Self {
+            entries,
+            path,
+            newer_version,
+        }
+    }
+
+    /// 文字列からハイスコア表を読み込み、ファイルの形式バージョンと記録の一覧を返す
+    ///
+    /// 記録ごとの不正な値は補正し、読み込めない記録はその記録だけ捨てる
+    fn parse(contents: &str) -> Result<(u32, Vec<HighScoreEntry>), ron::error::SpannedError> {
+        let file: HighScoreFile<ron::Value> = ron::from_str(contents)?;
+
+        let mut entries: Vec<HighScoreEntry> = file
+            .entries
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, value)| match value.into_rust::<HighScoreEntry>() {
+                Ok(entry) => Some(entry),
+                Err(err) => {
+                    warn!("skipping invalid high score entry {}: {}", index, err);
+                    None
+                }
+            })
+            .map(|mut entry| {
+                entry.name = sanitize_initials(&entry.name);
+                if !entry.time.is_finite() || entry.time < 0.0 {
+                    entry.time = 0.0;
+                }
+                entry
+            })
+            .filter(|entry| entry.score > 0)
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        entries.truncate(HIGH_SCORE_LIMIT);
+        Ok((file.version, entries))
+    }
+
+    /// ハイスコア表をファイルに保存する
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Some(version) = self.newer_version {
+            warn!(
+                "not saving high scores over newer file version {} at {}",
+                version,
+                path.display()
+            );
+            return;
+        }
+        let file = HighScoreFile {
+            version: HIGH_SCORE_FILE_VERSION,
+            entries: self.entries.clone(),
+        };
+        let result = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                storage::write_file_atomic(path, &contents).map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            warn!("failed to save high scores to {}: {}", path.display(), err);
+        }
+    }
+}
+
+/// イニシャルを大文字英字3文字に揃える（足りない分は'A'で埋める）
+fn sanitize_initials(name: &str) -> String {
+    let mut initials: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_uppercase())
+        .take(INITIALS_LEN)
+        .collect();
+    while initials.len() < INITIALS_LEN {
+        initials.push('A');
+    }
+    initials
+}
+
+/// イニシャル入力中の状態を保持するリソース
+///
+/// このリソースがある間、結果画面のリトライ・タイトル遷移は無効になる
+#[derive(Resource)]
+pub struct NameEntry {
+    /// 入力中の文字
+    letters: [u8; INITIALS_LEN],
+    /// 編集中の文字位置
+    cursor: usize,
+    /// 登録する記録（名前以外）
+    entry: HighScoreEntry,
+}
+
+/// イニシャル入力UIのマーカーコンポーネント
+#[derive(Component)]
+struct NameEntryScreen;
+
+/// イニシャル表示テキストのマーカーコンポーネント
+#[derive(Component)]
+struct NameEntryText;
+
+/// 結果画面に入ったとき、ハイスコアに入るならイニシャル入力を始めるシステム
+fn begin_name_entry(
+    mut commands: Commands,
+    asset: Res<DefaultFont>,
+    state: Res<State<GameState>>,
+    high_scores: Res<HighScores>,
+    score: Res<Score>,
+) {
+    let Some(rank) = high_scores.qualifies(score.points) else {
+        return;
+    };
+
+    let name_entry = NameEntry {
+        letters: [b'A'; INITIALS_LEN],
+        cursor: 0,
+        entry: HighScoreEntry {
+            name: String::new(),
+            score: score.points,
+            kills: score.kills,
+            time: score.time,
+            date: storage::today(),
+        },
+    };
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(40.0),
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+            NameEntryScreen,
+            DespawnOnExit(*state.get()),
+        ))
+        .with_children(|parent| {
+            // ランクイン表示
+            parent.spawn((
+                LocalizedText::new("highscore.new_record").with_arg("rank", rank + 1),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 32.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.85, 0.0)),
+            ));
+
+            // 入力中のイニシャル
+            parent.spawn((
+                Text::new(initials_label(&name_entry)),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 48.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                NameEntryText,
+            ));
+
+            // 操作説明
+            parent.spawn((
+                LocalizedText::new("highscore.entry_help"),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 22.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            ));
+        });
+
+    commands.insert_resource(name_entry);
+}
+
+/// 入力中のイニシャルの表示文字列（編集中の文字を[]で囲む）
+fn initials_label(name_entry: &NameEntry) -> String {
+    name_entry
+        .letters
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| {
+            if i == name_entry.cursor {
+                format!("[{}]", c as char)
+            } else {
+                format!(" {} ", c as char)
+            }
+        })
+        .collect()
+}
+
+/// 英字キーと文字の対応表
+const LETTER_KEYS: [(KeyCode, u8); 26] = [
+    (KeyCode::KeyA, b'A'),
+    (KeyCode::KeyB, b'B'),
+    (KeyCode::KeyC, b'C'),
+    (KeyCode::KeyD, b'D'),
+    (KeyCode::KeyE, b'E'),
+    (KeyCode::KeyF, b'F'),
+    (KeyCode::KeyG, b'G'),
+    (KeyCode::KeyH, b'H'),
+    (KeyCode::KeyI, b'I'),
+    (KeyCode::KeyJ, b'J'),
+    (KeyCode::KeyK, b'K'),
+    (KeyCode::KeyL, b'L'),
+    (KeyCode::KeyM, b'M'),
+    (KeyCode::KeyN, b'N'),
+    (KeyCode::KeyO, b'O'),
+    (KeyCode::KeyP, b'P'),
+    (KeyCode::KeyQ, b'Q'),
+    (KeyCode::KeyR, b'R'),
+    (KeyCode::KeyS, b'S'),
+    (KeyCode::KeyT, b'T'),
+    (KeyCode::KeyU, b'U'),
+    (KeyCode::KeyV, b'V'),
+    (KeyCode::KeyW, b'W'),
+    (KeyCode::KeyX, b'X'),
+    (KeyCode::KeyY, b'Y'),
+    (KeyCode::KeyZ, b'Z'),
+];
+
+/// イニシャル入力の操作処理
+fn name_entry_input(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
+    mut name_entry: ResMut<NameEntry>,
+    mut high_scores: ResMut<HighScores>,
+    screen_query: Query<Entity, With<NameEntryScreen>>,
+) {
+    let cursor = name_entry.cursor;
+
+    // 英字キーで直接入力して次の文字へ進む
+    if let Some(&(_, letter)) = LETTER_KEYS
+        .iter()
+        .find(|(key, _)| keyboard_input.just_pressed(*key))
+    {
+        name_entry.letters[cursor] = letter;
+        name_entry.cursor = (cursor + 1).min(INITIALS_LEN - 1);
+    }
+
+    // 上下（矢印キー・十字キー）で文字を切り替える
+    if actions.any_just_pressed(Action::MenuUp) {
+        name_entry.letters[cursor] = if name_entry.letters[cursor] == b'Z' {
+            b'A'
+        } else {
+            name_entry.letters[cursor] + 1
+        };
+    }
+    if actions.any_just_pressed(Action::MenuDown) {
+        name_entry.letters[cursor] = if name_entry.letters[cursor] == b'A' {
+            b'Z'
+        } else {
+            name_entry.letters[cursor] - 1
+        };
+    }
+
+    // 左右（BackSpace）で編集位置を移動する
+    if actions.any_just_pressed(Action::MenuLeft) || keyboard_input.just_pressed(KeyCode::Backspace)
+    {
+        name_entry.cursor = name_entry.cursor.saturating_sub(1);
+    }
+    if actions.any_just_pressed(Action::MenuRight) {
+        name_entry.cursor = (name_entry.cursor + 1).min(INITIALS_LEN - 1);
+    }
+
+    // 決定で確定して保存する
+    if actions.any_just_pressed(Action::Confirm) {
+        let mut entry = name_entry.entry.clone();
+        entry.name = String::from_utf8_lossy(&name_entry.letters).into_owned();
+        high_scores.insert(entry);
+        high_scores.save();
+
+        for entity in &screen_query {
+            commands.entity(entity).despawn();
+        }
+        commands.remove_resource::<NameEntry>();
+    }
+}
+
+/// 入力中のイニシャル表示を更新するシステム
+fn update_name_entry_ui(
+    name_entry: Res<NameEntry>,
+    mut query: Query<&mut Text, With<NameEntryText>>,
+) {
+    if name_entry.is_changed()
+        && let Ok(mut text) = query.single_mut()
+    {
+        **text = initials_label(&name_entry);
+    }
+}
+
+/// 結果画面を離れたら入力途中のイニシャル入力を破棄するシステム
+fn cancel_name_entry(mut commands: Commands) {
+    commands.remove_resource::<NameEntry>();
+}
+
+/// タイトル画面用のハイスコア表の表示文字列
+pub fn high_score_table(high_scores: &HighScores, strings: &Strings) -> String {
+    if high_scores.entries().is_empty() {
+        return strings.get("highscore.empty");
+    }
+
+    high_scores
+        .entries()
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let secs = entry.time as u32;
+            strings.format(
+                "highscore.row",
+                &[
+                    ("rank", format!("{:>2}", i + 1)),
+                    ("name", entry.name.clone()),
+                    ("score", format!("{:>7}", entry.score)),
+                    ("kills", format!("{:>3}", entry.kills)),
+                    ("time", format!("{:02}:{:02}", secs / 60, secs % 60)),
+                    ("date", entry.date.clone()),
+                ],
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テストごとに別の一時ファイルのパス
+    fn temp_file(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("space-battle-highscore-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    /// 1件分の記録
+    fn entry(name: &str, score: u32) -> HighScoreEntry {
+        HighScoreEntry {
+            name: name.to_string(),
+            score,
+            kills: 0,
+            time: 0.0,
+            date: String::new(),
+        }
+    }
+
+    #[test]
+    fn invalid_entry_is_skipped_alone() {
+        let contents = r#"(version: 1, entries: [
+            (name: "AAA", score: 300),
+            (name: "BBB", score: "lots"),
+            (name: "CCC", score: 100),
+        ])"#;
+        let (version, entries) = HighScores::parse(contents).unwrap();
+        assert_eq!(version, 1);
+        let names: Vec<_> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["AAA", "CCC"]);
+    }
+
+    #[test]
+    fn newer_file_version_is_read_but_not_overwritten() {
+        let path = temp_file("newer.ron");
+        let newer_version = HIGH_SCORE_FILE_VERSION + 1;
+        let contents =
+            format!(r#"(version: {newer_version}, entries: [(name: "AAA", score: 300)])"#);
+        std::fs::write(&path, &contents).unwrap();
+
+        let mut high_scores = HighScores::load_from(Some(path.clone()));
+        assert_eq!(high_scores.newer_version, Some(newer_version));
+        assert_eq!(high_scores.entries().len(), 1);
+
+        high_scores.insert(entry("BBB", 500));
+        high_scores.save();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), contents);
+    }
+
+    #[test]
+    fn current_file_version_is_saved() {
+        let path = temp_file("current.ron");
+        std::fs::write(
+            &path,
+            r#"(version: 1, entries: [(name: "AAA", score: 300)])"#,
+        )
+        .unwrap();
+
+        let mut high_scores = HighScores::load_from(Some(path.clone()));
+        assert_eq!(high_scores.newer_version, None);
+        high_scores.insert(entry("BBB", 500));
+        high_scores.save();
+
+        let (_, saved) = HighScores::parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let names: Vec<_> = saved.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["BBB", "AAA"]);
+    }
+}
@@ -0,0 +1,843 @@
+/// 入力アクション（キーボード・ゲームパッドの入力をゲームの操作に変換する）
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+use bevy::input::InputSystems;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::plugins::game::MAX_PLAYERS;
+use crate::storage;
+
+/// アナログスティックの入力を無視する範囲（0.0 ~ 1.0）
+const STICK_DEAD_ZONE: f32 = 0.2;
+/// 操作設定ファイルの形式バージョン
+const CONTROLS_FILE_VERSION: u32 = 1;
+/// 操作設定ファイル名
+const CONTROLS_FILE_NAME: &str = "controls.ron";
+
+/// 入力アクションのプラグイン
+pub struct InputActionPlugin;
+
+impl Plugin for InputActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputBindings>();
+        app.init_resource::<ActionState>();
+        app.init_resource::<FixedActionState>();
+        app.add_systems(PreUpdate, update_action_state.after(InputSystems));
+        app.add_systems(
+            RunFixedMainLoop,
+            latch_fixed_actions.in_set(RunFixedMainLoopSystems::BeforeFixedMainLoop),
+        );
+        app.add_systems(
+            FixedPreUpdate,
+            advance_fixed_actions.in_set(FixedInputSystems),
+        );
+    }
+}
+
+/// ゲームの操作
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    /// 上へ移動
+    MoveUp,
+    /// 下へ移動
+    MoveDown,
+    /// 左へ移動
+    MoveLeft,
+    /// 右へ移動
+    MoveRight,
+    /// 弾の発射
+    Fire,
+    /// 武器の切り替え
+    CycleWeapon,
+    /// ポーズ・ポーズの解除
+    Pause,
+    /// 決定
+    Confirm,
+    /// リトライ
+    Retry,
+    /// メニューの上
+    MenuUp,
+    /// メニューの下
+    MenuDown,
+    /// メニューの左
+    MenuLeft,
+    /// メニューの右
+    MenuRight,
+    /// 遊ぶ人数の切り替え
+    SwitchPlayers,
+    /// 操作設定画面を開く
+    Controls,
+    /// 設定画面を開く
+    Settings,
+    /// リプレイを見る
+    WatchReplay,
+}
+
+impl Action {
+    /// すべての操作（操作設定画面の表示順）
+    pub const ALL: [Action; 17] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::CycleWeapon,
+        Action::Pause,
+        Action::Confirm,
+        Action::Retry,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuLeft,
+        Action::MenuRight,
+        Action::SwitchPlayers,
+        Action::Controls,
+        Action::Settings,
+        Action::WatchReplay,
+    ];
+
+    /// 操作設定画面に表示する名前の文字列表のキー
+    pub const fn label_key(self) -> &'static str {
+        match self {
+            Self::MoveUp => "action.move_up",
+            Self::MoveDown => "action.move_down",
+            Self::MoveLeft => "action.move_left",
+            Self::MoveRight => "action.move_right",
+            Self::Fire => "action.fire",
+            Self::CycleWeapon => "action.cycle_weapon",
+            Self::Pause => "action.pause",
+            Self::Confirm => "action.confirm",
+            Self::Retry => "action.retry",
+            Self::MenuUp => "action.menu_up",
+            Self::MenuDown => "action.menu_down",
+            Self::MenuLeft => "action.menu_left",
+            Self::MenuRight => "action.menu_right",
+            Self::SwitchPlayers => "action.switch_players",
+            Self::Controls => "action.controls",
+            Self::Settings => "action.settings",
+            Self::WatchReplay => "action.watch_replay",
+        }
+    }
+
+    /// ゲーム中（ポーズしていない間）に使う操作かどうか
+    const fn in_game(self) -> bool {
+        matches!(
+            self,
+            Self::MoveUp
+                | Self::MoveDown
+                | Self::MoveLeft
+                | Self::MoveRight
+                | Self::Fire
+                | Self::CycleWeapon
+                | Self::Pause
+        )
+    }
+
+    /// メニュー画面（タイトル・ポーズ・結果画面など）で使う操作かどうか
+    const fn in_menu(self) -> bool {
+        !self.in_game() || matches!(self, Self::Pause)
+    }
+
+    /// 同じ場面で使われる操作かどうか（同じ入力を割り当てると衝突する）
+    const fn shares_context(self, other: Action) -> bool {
+        (self.in_game() && other.in_game()) || (self.in_menu() && other.in_menu())
+    }
+}
+
+/// 操作に割り当てる入力
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundInput {
+    /// キーボードのキー
+    Key(KeyCode),
+    /// ゲームパッドのボタン
+    Button(GamepadButton),
+}
+
+/// 1つの操作に割り当てたキーとゲームパッドのボタン
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Binding {
+    /// キーボードのキー
+    pub key: Option<KeyCode>,
+    /// ゲームパッドのボタン
+    pub button: Option<GamepadButton>,
+}
+
+impl Binding {
+    const fn new(key: Option<KeyCode>, button: Option<GamepadButton>) -> Self {
+        Self { key, button }
+    }
+
+    /// input と同じ種類（キーかボタンか）の割り当て
+    fn get(&self, input: BoundInput) -> Option<BoundInput> {
+        match input {
+            BoundInput::Key(_) => self.key.map(BoundInput::Key),
+            BoundInput::Button(_) => self.button.map(BoundInput::Button),
+        }
+    }
+
+    /// input の種類の割り当てを置き換える（None なら割り当てを外す）
+    fn set(&mut self, kind: BoundInput, input: Option<BoundInput>) {
+        match (kind, input) {
+            (BoundInput::Key(_), Some(BoundInput::Key(key))) => self.key = Some(key),
+            (BoundInput::Key(_), _) => self.key = None,
+            (BoundInput::Button(_), Some(BoundInput::Button(button))) => self.button = Some(button),
+            (BoundInput::Button(_), _) => self.button = None,
+        }
+    }
+}
+
+/// プレイヤーごとの操作の割り当てを保持するリソース
+///
+/// ゲームパッドは接続順に 1P、2P に割り当てる
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct InputBindings {
+    pub players: [HashMap<Action, Binding>; MAX_PLAYERS],
+    /// 保存先（保存できない環境では None）
+    path: Option<PathBuf>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            players: [default_bindings(0), default_bindings(1)],
+            path: None,
+        }
+    }
+}
+
+impl InputBindings {
+    /// player 番目のプレイヤーの action の割り当て
+    pub fn binding(&self, player: usize, action: Action) -> Binding {
+        self.players
+            .get(player)
+            .and_then(|bindings| bindings.get(&action))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// player 番目のプレイヤーの action の既定の割り当て
+    pub fn default_binding(player: usize, action: Action) -> Binding {
+        default_bindings(player)
+            .get(&action)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// player 番目のプレイヤーの action に input を割り当てたとき、衝突する操作をすべて返す
+    ///
+    /// キーボードは全員で共有するので全プレイヤーの割り当てと、
+    /// ゲームパッドは各自のものなので同じプレイヤーの割り当てとだけ比べる。
+    /// 別の場面で使う操作（発射と決定など）は同じ入力でも衝突しない。
+    /// ポーズのように両方の場面で使う操作は複数の操作と衝突しうるので、プレイヤー順・Action::ALL の順に並べて返す
+    pub fn conflicts(
+        &self,
+        player: usize,
+        action: Action,
+        input: BoundInput,
+    ) -> Vec<(usize, Action)> {
+        (0..MAX_PLAYERS)
+            .filter(|&other_player| matches!(input, BoundInput::Key(_)) || other_player == player)
+            .flat_map(|other_player| Action::ALL.map(|other| (other_player, other)))
+            .filter(|&(other_player, other)| (other_player, other) != (player, action))
+            .filter(|&(_, other)| action.shares_context(other))
+            .filter(|&(other_player, other)| {
+                self.binding(other_player, other).get(input) == Some(input)
+            })
+            .collect()
+    }
+
+    /// 現在の割り当てが他の操作と衝突しているかどうか（設定ファイルを手で編集した場合など）
+    pub fn has_conflict(&self, player: usize, action: Action, input: Option<BoundInput>) -> bool {
+        input.is_some_and(|input| !self.conflicts(player, action, input).is_empty())
+    }
+
+    /// player 番目のプレイヤーの action に input を割り当てる
+    ///
+    /// 衝突する操作があれば、そのすべてに action の元の割り当てを渡して入れ替え、入れ替えた操作を返す
+    pub fn assign(
+        &mut self,
+        player: usize,
+        action: Action,
+        input: BoundInput,
+    ) -> Vec<(usize, Action)> {
+        let conflicts = self.conflicts(player, action, input);
+        let previous = self.binding(player, action).get(input);
+        for &(other_player, other) in &conflicts {
+            self.players[other_player]
+                .entry(other)
+                .or_default()
+                .set(input, previous);
+        }
+        self.players[player]
+            .entry(action)
+            .or_default()
+            .set(input, Some(input));
+        conflicts
+    }
+
+    /// player 番目のプレイヤーの割り当てを既定に戻す
+    pub fn reset(&mut self, player: usize) {
+        self.players[player] = default_bindings(player);
+    }
+
+    /// 操作設定ファイルを読み込む（起動時に main から呼ぶ）
+    ///
+    /// 読み込めないファイルは `.bak` に退避して既定の割り当てから始める
+    pub fn load() -> Self {
+        let path = storage::config_dir().map(|dir| dir.join(CONTROLS_FILE_NAME));
+        let mut bindings = Self::default();
+
+        if let Some(path) = &path {
+            match storage::read_file(path) {
+                Ok(Some(contents)) => match Self::parse(&contents) {
+                    Ok(players) => bindings.players = players,
+                    Err(err) => {
+                        warn!("controls file {} is corrupted: {}", path.display(), err);
+                        if let Err(err) = storage::backup_file(path) {
+                            warn!("failed to back up corrupted controls file: {}", err);
+                        }
+                    }
+                },
+                Ok(None) => {}
+                Err(err) => warn!("failed to read controls from {}: {}", path.display(), err),
+            }
+        }
+
+        bindings.path = path;
+        bindings
+    }
+
+    /// 文字列から操作の割り当てを読み込む
+    ///
+    /// ファイルにない操作と、知らない名前のキー・ボタンは既定の割り当てのままにする
+    fn parse(
+        contents: &str,
+    ) -> Result<[HashMap<Action, Binding>; MAX_PLAYERS], ron::error::SpannedError> {
+        let file: ControlsFile = ron::from_str(contents)?;
+        if file.version > CONTROLS_FILE_VERSION {
+            warn!(
+                "controls file version {} is newer than supported version {}",
+                file.version, CONTROLS_FILE_VERSION
+            );
+        }
+
+        let mut players = [default_bindings(0), default_bindings(1)];
+        for (bindings, saved) in players.iter_mut().zip(file.players) {
+            for (action, saved) in saved {
+                let binding = bindings.entry(action).or_default();
+                match saved.key.as_deref().map(|name| (name, key_from_name(name))) {
+                    None => binding.key = None,
+                    Some((_, Some(key))) => binding.key = Some(key),
+                    Some((name, None)) => warn!("unknown key {:?} for {:?}", name, action),
+                }
+                match saved
+                    .button
+                    .as_deref()
+                    .map(|name| (name, button_from_name(name)))
+                {
+                    None => binding.button = None,
+                    Some((_, Some(button))) => binding.button = Some(button),
+                    Some((name, None)) => warn!("unknown button {:?} for {:?}", name, action),
+                }
+            }
+        }
+        Ok(players)
+    }
+
+    /// 操作の割り当てをファイルに保存する
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let file = ControlsFile {
+            version: CONTROLS_FILE_VERSION,
+            players: self
+                .players
+                .iter()
+                .map(|bindings| {
+                    bindings
+                        .iter()
+                        .map(|(&action, binding)| {
+                            let saved = SavedBinding {
+                                key: binding.key.and_then(key_name).map(str::to_string),
+                                button: binding.button.and_then(button_name).map(str::to_string),
+                            };
+                            (action, saved)
+                        })
+                        .collect()
+                })
+                .collect(),
+        };
+        let result = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                storage::write_file_atomic(path, &contents).map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            warn!("failed to save controls to {}: {}", path.display(), err);
+        }
+    }
+}
+
+/// 操作設定ファイルの中身
+#[derive(Serialize, Deserialize)]
+struct ControlsFile {
+    /// 形式バージョン
+    version: u32,
+    /// プレイヤーごとの割り当て（1P, 2P の順）
+    #[serde(default)]
+    players: Vec<BTreeMap<Action, SavedBinding>>,
+}
+
+/// 操作設定ファイルに保存する1つの操作の割り当て（キー・ボタンは名前で保存する）
+#[derive(Serialize, Deserialize)]
+struct SavedBinding {
+    /// キーの名前（割り当てなしは None）
+    #[serde(default)]
+    key: Option<String>,
+    /// ボタンの名前（割り当てなしは None）
+    #[serde(default)]
+    button: Option<String>,
+}
+
+/// 割り当てられるキーと名前の対応表（操作設定画面の表示と設定ファイルで使う）
+const KEY_NAMES: &[(KeyCode, &str)] = &[
+    (KeyCode::KeyA, "A"),
+    (KeyCode::KeyB, "B"),
+    (KeyCode::KeyC, "C"),
+    (KeyCode::KeyD, "D"),
+    (KeyCode::KeyE, "E"),
+    (KeyCode::KeyF, "F"),
+    (KeyCode::KeyG, "G"),
+    (KeyCode::KeyH, "H"),
+    (KeyCode::KeyI, "I"),
+    (KeyCode::KeyJ, "J"),
+    (KeyCode::KeyK, "K"),
+    (KeyCode::KeyL, "L"),
+    (KeyCode::KeyM, "M"),
+    (KeyCode::KeyN, "N"),
+    (KeyCode::KeyO, "O"),
+    (KeyCode::KeyP, "P"),
+    (KeyCode::KeyQ, "Q"),
+    (KeyCode::KeyR, "R"),
+    (KeyCode::KeyS, "S"),
+    (KeyCode::KeyT, "T"),
+    (KeyCode::KeyU, "U"),
+    (KeyCode::KeyV, "V"),
+    (KeyCode::KeyW, "W"),
+    (KeyCode::KeyX, "X"),
+    (KeyCode::KeyY, "Y"),
+    (KeyCode::KeyZ, "Z"),
+    (KeyCode::Digit0, "0"),
+    (KeyCode::Digit1, "1"),
+    (KeyCode::Digit2, "2"),
+    (KeyCode::Digit3, "3"),
+    (KeyCode::Digit4, "4"),
+    (KeyCode::Digit5, "5"),
+    (KeyCode::Digit6, "6"),
+    (KeyCode::Digit7, "7"),
+    (KeyCode::Digit8, "8"),
+    (KeyCode::Digit9, "9"),
+    (KeyCode::ArrowUp, "Up"),
+    (KeyCode::ArrowDown, "Down"),
+    (KeyCode::ArrowLeft, "Left"),
+    (KeyCode::ArrowRight, "Right"),
+    (KeyCode::Enter, "Enter"),
+    (KeyCode::Space, "Space"),
+    (KeyCode::Escape, "Escape"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::Backspace, "Backspace"),
+    (KeyCode::ShiftLeft, "LShift"),
+    (KeyCode::ShiftRight, "RShift"),
+    (KeyCode::ControlLeft, "LCtrl"),
+    (KeyCode::ControlRight, "RCtrl"),
+    (KeyCode::AltLeft, "LAlt"),
+    (KeyCode::AltRight, "RAlt"),
+    (KeyCode::Comma, ","),
+    (KeyCode::Period, "."),
+    (KeyCode::Slash, "/"),
+    (KeyCode::Semicolon, ";"),
+    (KeyCode::Quote, "'"),
+    (KeyCode::BracketLeft, "["),
+    (KeyCode::BracketRight, "]"),
+    (KeyCode::Minus, "-"),
+    (KeyCode::Equal, "="),
+];
+
+/// 割り当てられるゲームパッドのボタンと名前の対応表
+const BUTTON_NAMES: &[(GamepadButton, &str)] = &[
+    (GamepadButton::South, "South"),
+    (GamepadButton::East, "East"),
+    (GamepadButton::West, "West"),
+    (GamepadButton::North, "North"),
+    (GamepadButton::LeftTrigger, "LB"),
+    (GamepadButton::RightTrigger, "RB"),
+    (GamepadButton::LeftTrigger2, "LT"),
+    (GamepadButton::RightTrigger2, "RT"),
+    (GamepadButton::Select, "Select"),
+    (GamepadButton::Start, "Start"),
+    (GamepadButton::LeftThumb, "LStick"),
+    (GamepadButton::RightThumb, "RStick"),
+    (GamepadButton::DPadUp, "DPadUp"),
+    (GamepadButton::DPadDown, "DPadDown"),
+    (GamepadButton::DPadLeft, "DPadLeft"),
+    (GamepadButton::DPadRight, "DPadRight"),
+];
+
+/// キーの名前（割り当てられないキーは None）
+pub fn key_name(key: KeyCode) -> Option<&'static str> {
+    KEY_NAMES
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, name)| *name)
+}
+
+/// 名前からキーを探す
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    KEY_NAMES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(key, _)| *key)
+}
+
+/// ボタンの名前（割り当てられないボタンは None）
+pub fn button_name(button: GamepadButton) -> Option<&'static str> {
+    BUTTON_NAMES
+        .iter()
+        .find(|(b, _)| *b == button)
+        .map(|(_, name)| *name)
+}
+
+/// 名前からボタンを探す
+fn button_from_name(name: &str) -> Option<GamepadButton> {
+    BUTTON_NAMES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(button, _)| *button)
+}
+
+/// player 番目のプレイヤーの既定の割り当て
+///
+/// 1P はキーボードの WASD・Enter・Q と矢印キーのメニュー操作、2P は矢印キー・右Shift・右Ctrl。
+/// 発射と決定のように別の場面で使う操作は同じ入力を共有する。
+/// ゲームパッドの割り当ては共通
+fn default_bindings(player: usize) -> HashMap<Action, Binding> {
+    use Action::*;
+    use GamepadButton as B;
+
+    let gamepad = |action| match action {
+        MoveUp | MenuUp => Some(B::DPadUp),
+        MoveDown | MenuDown => Some(B::DPadDown),
+        MoveLeft | MenuLeft => Some(B::DPadLeft),
+        MoveRight | MenuRight => Some(B::DPadRight),
+        Fire | Confirm => Some(B::South),
+        CycleWeapon => Some(B::West),
+        Pause => Some(B::Start),
+        Retry => Some(B::North),
+        SwitchPlayers => Some(B::Select),
+        Controls => Some(B::West),
+        Settings => Some(B::East),
+        WatchReplay => Some(B::RightTrigger),
+    };
+    let keyboard = |action| match (player, action) {
+        (0, MoveUp) => Some(KeyCode::KeyW),
+        (0, MoveDown) => Some(KeyCode::KeyS),
+        (0, MoveLeft) => Some(KeyCode::KeyA),
+        (0, MoveRight) => Some(KeyCode::KeyD),
+        (0, Fire) => Some(KeyCode::Enter),
+        (0, CycleWeapon) => Some(KeyCode::KeyQ),
+        (0, Pause) => Some(KeyCode::Escape),
+        (0, Confirm) => Some(KeyCode::Enter),
+        (0, Retry) => Some(KeyCode::KeyR),
+        (0, MenuUp) => Some(KeyCode::ArrowUp),
+        (0, MenuDown) => Some(KeyCode::ArrowDown),
+        (0, MenuLeft) => Some(KeyCode::ArrowLeft),
+        (0, MenuRight) => Some(KeyCode::ArrowRight),
+        (0, SwitchPlayers) => Some(KeyCode::Tab),
+        (0, Controls) => Some(KeyCode::KeyC),
+        (0, Settings) => Some(KeyCode::KeyO),
+        (0, WatchReplay) => Some(KeyCode::KeyV),
+        (_, MoveUp) => Some(KeyCode::ArrowUp),
+        (_, MoveDown) => Some(KeyCode::ArrowDown),
+        (_, MoveLeft) => Some(KeyCode::ArrowLeft),
+        (_, MoveRight) => Some(KeyCode::ArrowRight),
+        (_, Fire) => Some(KeyCode::ShiftRight),
+        (_, CycleWeapon) => Some(KeyCode::ControlRight),
+        _ => None,
+    };
+
+    Action::ALL
+        .into_iter()
+        .map(|action| (action, Binding::new(keyboard(action), gamepad(action))))
+        .collect()
+}
+
+/// 1人分の操作の状態
+#[derive(Default)]
+struct PlayerActions {
+    /// 押されている操作
+    pressed: HashSet<Action>,
+    /// このフレームで押された操作
+    just_pressed: HashSet<Action>,
+    /// アナログスティックの入力（不感帯を除いて 0.0 ~ 1.0 に補正済み）
+    stick: Vec2,
+}
+
+/// 現在のフレームの操作の状態を保持するリソース
+#[derive(Resource, Default)]
+pub struct ActionState {
+    players: [PlayerActions; MAX_PLAYERS],
+}
+
+impl ActionState {
+    /// player 番目のプレイヤーが action を押しているかどうか
+    pub fn pressed(&self, player: usize, action: Action) -> bool {
+        self.players
+            .get(player)
+            .is_some_and(|p| p.pressed.contains(&action))
+    }
+
+    /// player 番目のプレイヤーがこのフレームで action を押したかどうか
+    pub fn just_pressed(&self, player: usize, action: Action) -> bool {
+        self.players
+            .get(player)
+            .is_some_and(|p| p.just_pressed.contains(&action))
+    }
+
+    /// 誰かがこのフレームで action を押したかどうか（メニュー操作用）
+    pub fn any_just_pressed(&self, action: Action) -> bool {
+        self.players
+            .iter()
+            .any(|p| p.just_pressed.contains(&action))
+    }
+
+    /// player 番目のプレイヤーの移動方向（長さは 1.0 以下）
+    ///
+    /// 移動キー・十字キーとアナログスティックの入力を合わせたもの
+    pub fn movement(&self, player: usize) -> Vec2 {
+        let mut direction = Vec2::ZERO;
+        if self.pressed(player, Action::MoveUp) {
+            direction.y += 1.0;
+        }
+        if self.pressed(player, Action::MoveDown) {
+            direction.y -= 1.0;
+        }
+        if self.pressed(player, Action::MoveLeft) {
+            direction.x -= 1.0;
+        }
+        if self.pressed(player, Action::MoveRight) {
+            direction.x += 1.0;
+        }
+        if let Some(actions) = self.players.get(player) {
+            direction += actions.stick;
+        }
+
+        // 斜め移動やスティックとの併用で、縦横移動より速くならないようにする
+        direction.clamp_length_max(1.0)
+    }
+}
+
+/// 不感帯を除いて、スティックの入力を 0.0 ~ 1.0 の長さに補正する
+fn apply_dead_zone(stick: Vec2) -> Vec2 {
+    let length = stick.length();
+    if length <= STICK_DEAD_ZONE {
+        return Vec2::ZERO;
+    }
+    let scaled = ((length - STICK_DEAD_ZONE) / (1.0 - STICK_DEAD_ZONE)).min(1.0);
+    stick / length * scaled
+}
+
+/// ティックの操作の状態を決めるシステムセット
+///
+/// リプレイの再生はこのセットの後で、記録した操作に置き換える
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FixedInputSystems;
+
+/// 1人分・1ティック分の操作（リプレイの記録用）
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TickInput {
+    /// 押されている操作（Action::ALL の添字のビット）
+    pressed: u32,
+    /// このティックで押された操作（Action::ALL の添字のビット）
+    just_pressed: u32,
+    /// アナログスティックの入力（-127 ~ 127）
+    stick: (i8, i8),
+}
+
+impl TickInput {
+    /// 押されている操作・このティックで押された操作・スティックの入力から作る（ボットの操作用）
+    pub fn new(pressed: &[Action], just_pressed: &[Action], stick: Vec2) -> Self {
+        Self {
+            pressed: action_bits(&pressed.iter().copied().collect()),
+            just_pressed: action_bits(&just_pressed.iter().copied().collect()),
+            stick: quantize_stick(stick),
+        }
+    }
+}
+
+/// 操作の集合をビットに変換する
+fn action_bits(actions: &HashSet<Action>) -> u32 {
+    Action::ALL
+        .into_iter()
+        .enumerate()
+        .filter(|(_, action)| actions.contains(action))
+        .fold(0, |bits, (index, _)| bits | 1 << index)
+}
+
+/// ビットを操作の集合に変換する
+fn actions_from_bits(bits: u32) -> HashSet<Action> {
+    Action::ALL
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| bits & 1 << index != 0)
+        .map(|(_, action)| action)
+        .collect()
+}
+
+/// スティックの入力を記録できる細かさに丸める
+///
+/// 記録しない細かい値でゲームが進むと再生したときに展開がずれるため、プレイ中も丸めた値を使う
+fn quantize_stick(stick: Vec2) -> (i8, i8) {
+    let quantize = |value: f32| (value.clamp(-1.0, 1.0) * i8::MAX as f32).round() as i8;
+    (quantize(stick.x), quantize(stick.y))
+}
+
+/// 丸めたスティックの入力を元の範囲に戻す
+fn dequantize_stick((x, y): (i8, i8)) -> Vec2 {
+    Vec2::new(x as f32, y as f32) / i8::MAX as f32
+}
+
+/// 固定タイムステップの1ティック分の操作の状態を保持するリソース
+///
+/// フレームとティックの回数は一致しないため、フレームで押された操作は次のティックまでためておき、
+/// 取りこぼしや2重の入力が起きないようにする。ゲームの処理（FixedUpdate）はこちらを使う
+#[derive(Resource, Default, Deref)]
+pub struct FixedActionState {
+    /// 現在のティックの操作の状態
+    #[deref]
+    current: ActionState,
+    /// まだティックに渡していない、押された操作
+    pending: [HashSet<Action>; MAX_PLAYERS],
+}
+
+impl FixedActionState {
+    /// player 番目のプレイヤーの現在のティックの操作（リプレイの記録用）
+    pub fn record(&self, player: usize) -> TickInput {
+        self.current
+            .players
+            .get(player)
+            .map(|actions| TickInput {
+                pressed: action_bits(&actions.pressed),
+                just_pressed: action_bits(&actions.just_pressed),
+                stick: quantize_stick(actions.stick),
+            })
+            .unwrap_or_default()
+    }
+
+    /// player 番目のプレイヤーの現在のティックの操作を、記録した操作に置き換える（リプレイの再生用）
+    pub fn replay(&mut self, player: usize, input: TickInput) {
+        if let Some(actions) = self.current.players.get_mut(player) {
+            actions.pressed = actions_from_bits(input.pressed);
+            actions.just_pressed = actions_from_bits(input.just_pressed);
+            actions.stick = dequantize_stick(input.stick);
+        }
+    }
+}
+
+/// 接続順（エンティティ順）に並べたゲームパッド（n 番目のゲームパッドを n 番目のプレイヤーに割り当てる）
+pub fn gamepads_in_order<'a>(gamepad_query: &'a Query<(Entity, &Gamepad)>) -> Vec<&'a Gamepad> {
+    let mut gamepads: Vec<(Entity, &Gamepad)> = gamepad_query.iter().collect();
+    gamepads.sort_by_key(|(entity, _)| *entity);
+    gamepads.into_iter().map(|(_, gamepad)| gamepad).collect()
+}
+
+/// キーボードとゲームパッドの入力から、各プレイヤーの操作の状態を更新するシステム
+fn update_action_state(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepad_query: Query<(Entity, &Gamepad)>,
+    bindings: Res<InputBindings>,
+    mut state: ResMut<ActionState>,
+) {
+    let gamepads = gamepads_in_order(&gamepad_query);
+
+    for (player, actions) in state.players.iter_mut().enumerate() {
+        let gamepad = gamepads.get(player).copied();
+        actions.pressed.clear();
+        actions.just_pressed.clear();
+        actions.stick = gamepad
+            .map(|gamepad| apply_dead_zone(gamepad.left_stick()))
+            .unwrap_or(Vec2::ZERO);
+
+        for (&action, binding) in &bindings.players[player] {
+            let key = binding.key;
+            let button = binding.button.zip(gamepad);
+
+            if key.is_some_and(|key| keyboard_input.pressed(key))
+                || button.is_some_and(|(button, gamepad)| gamepad.pressed(button))
+            {
+                actions.pressed.insert(action);
+            }
+            if key.is_some_and(|key| keyboard_input.just_pressed(key))
+                || button.is_some_and(|(button, gamepad)| gamepad.just_pressed(button))
+            {
+                actions.just_pressed.insert(action);
+            }
+        }
+    }
+}
+
+/// フレームで押された操作を、ティックに渡すまでためておくシステム
+fn latch_fixed_actions(actions: Res<ActionState>, mut fixed: ResMut<FixedActionState>) {
+    for (pending, frame) in fixed.pending.iter_mut().zip(&actions.players) {
+        pending.extend(frame.just_pressed.iter().copied());
+    }
+}
+
+/// ティックの始めに、そのティックの操作の状態を決めるシステム
+///
+/// 1ティックより短く押して離した操作も、押されていたものとして扱う
+fn advance_fixed_actions(actions: Res<ActionState>, mut fixed: ResMut<FixedActionState>) {
+    let FixedActionState { current, pending } = &mut *fixed;
+    for ((tick, pending), frame) in current
+        .players
+        .iter_mut()
+        .zip(pending.iter_mut())
+        .zip(&actions.players)
+    {
+        tick.just_pressed = std::mem::take(pending);
+        tick.pressed = frame.pressed.union(&tick.just_pressed).copied().collect();
+        tick.stick = dequantize_stick(quantize_stick(frame.stick));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assigning_pause_resolves_every_conflict() {
+        let mut bindings = InputBindings::default();
+        let enter = BoundInput::Key(KeyCode::Enter);
+
+        // ポーズはゲーム中とメニューの両方で使うので、発射と決定の両方と衝突する
+        let swapped = bindings.assign(0, Action::Pause, enter);
+        assert_eq!(swapped, vec![(0, Action::Fire), (0, Action::Confirm)]);
+
+        assert_eq!(bindings.binding(0, Action::Pause).key, Some(KeyCode::Enter));
+        assert_eq!(bindings.binding(0, Action::Fire).key, Some(KeyCode::Escape));
+        assert_eq!(
+            bindings.binding(0, Action::Confirm).key,
+            Some(KeyCode::Escape)
+        );
+        for player in 0..MAX_PLAYERS {
+            for action in Action::ALL {
+                let input = bindings.binding(player, action).get(enter);
+                assert!(
+                    !bindings.has_conflict(player, action, input),
+                    "{player}P {action:?} still conflicts"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+/// 多言語対応（言語ごとの文字列表と、キーから表示文字列を引く仕組み）
+///
+/// 文字列表は `assets/locale/<言語>.strings.ron` に「キー: 文字列」の形で書く。
+/// 文字列中の `{name}` は表示するときに引数の値に置き換える
+use std::collections::HashMap;
+use std::fmt;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use bevy::ui::UiSystems;
+
+use crate::plugins::settings::{Language, Settings};
+
+/// 文字列表ファイルの拡張子
+const STRINGS_EXTENSION: &str = "strings.ron";
+/// 選んだ言語の文字列表にキーがないときに使う言語
+const FALLBACK_LANGUAGE: Language = Language::English;
+
+/// 多言語対応のプラグイン
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<StringTable>();
+        app.init_asset_loader::<StringTableLoader>();
+        // タイトル画面の OnEnter は Startup より先に走るため、ビルド時に用意しておく
+        app.init_resource::<Strings>();
+        app.add_systems(Startup, load_string_tables);
+        app.add_systems(Update, rebuild_strings);
+        app.add_systems(PostUpdate, render_localized_text.before(UiSystems::Prepare));
+    }
+}
+
+impl Language {
+    /// すべての言語
+    const ALL: [Language; 2] = [Language::Japanese, Language::English];
+
+    /// 文字列表ファイルのパス（assets/ からの相対パス）
+    const fn strings_path(self) -> &'static str {
+        match self {
+            Self::Japanese => "locale/ja.strings.ron",
+            Self::English => "locale/en.strings.ron",
+        }
+    }
+}
+
+/// 1つの言語の文字列表（キー → 文字列）
+#[derive(Asset, TypePath, Clone, Debug, Default)]
+pub struct StringTable(HashMap<String, String>);
+
+/// 文字列表の読み込みエラー
+#[derive(Debug)]
+pub enum StringTableLoadError {
+    /// ファイルを読めなかった
+    Io(std::io::Error),
+    /// RON として解釈できなかった
+    Parse(ron::error::SpannedError),
+}
+
+impl fmt::Display for StringTableLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read string table: {err}"),
+            Self::Parse(err) => write!(f, "failed to parse string table: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for StringTableLoadError {}
+
+/// 文字列表ファイル（*.strings.ron）のローダー
+#[derive(Default, TypePath)]
+pub struct StringTableLoader;
+
+impl AssetLoader for StringTableLoader {
+    type Asset = StringTable;
+    type Settings = ();
+    type Error = StringTableLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(StringTableLoadError::Io)?;
+        let table = ron::de::from_bytes(&bytes).map_err(StringTableLoadError::Parse)?;
+        Ok(StringTable(table))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[STRINGS_EXTENSION]
+    }
+}
+
+/// 各言語の文字列表のハンドルを保持するリソース
+#[derive(Resource)]
+struct StringTableHandles(HashMap<Language, Handle<StringTable>>);
+
+/// 現在の言語で表示文字列を引くためのリソース
+///
+/// 文字列表の読み込みが終わるまでは空の文字列を返す
+#[derive(Resource, Default)]
+pub struct Strings {
+    /// 選んだ言語の文字列表
+    current: StringTable,
+    /// キーがないときに使う文字列表
+    fallback: StringTable,
+    /// どちらかの文字列表を読み込めたかどうか
+    loaded: bool,
+}
+
+impl Strings {
+    /// キーに対応する文字列
+    ///
+    /// 選んだ言語になければ FALLBACK_LANGUAGE の文字列を、それにもなければキーそのものを返す
+    pub fn get(&self, key: &str) -> String {
+        if !self.loaded {
+            return String::new();
+        }
+        self.current
+            .0
+            .get(key)
+            .or_else(|| self.fallback.0.get(key))
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// キーに対応する文字列の `{name}` を引数の値に置き換えたもの
+    pub fn format(&self, key: &str, args: &[(&str, String)]) -> String {
+        args.iter().fold(self.get(key), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+    }
+}
+
+/// キーから表示文字列を引くテキストのコンポーネント
+///
+/// 言語を切り替えたときや引数を変えたときに、同じエンティティの Text を書き換える
+#[derive(Component, Clone, Debug)]
+#[require(Text)]
+pub struct LocalizedText {
+    /// 文字列表のキー
+    key: &'static str,
+    /// `{name}` に入れる値
+    args: Vec<(&'static str, String)>,
+    /// `{name}` に入れる文字列の文字列表のキー（表示する言語で引いてから入れる）
+    localized_args: Vec<(&'static str, &'static str)>,
+}
+
+impl LocalizedText {
+    /// 引数のないテキスト
+    pub fn new(key: &'static str) -> Self {
+        Self {
+            key,
+            args: Vec::new(),
+            localized_args: Vec::new(),
+        }
+    }
+
+    /// `{name}` に入れる値を加える
+    pub fn with_arg(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.set_arg(name, value);
+        self
+    }
+
+    /// `{name}` に、文字列表の key の文字列を入れる
+    pub fn with_localized_arg(mut self, name: &'static str, key: &'static str) -> Self {
+        self.localized_args.push((name, key));
+        self
+    }
+
+    /// 文字列表のキーを変える
+    pub fn set_key(&mut self, key: &'static str) {
+        self.key = key;
+    }
+
+    /// `{name}` に入れる値を変える
+    pub fn set_arg(&mut self, name: &'static str, value: impl ToString) {
+        let value = value.to_string();
+        match self.args.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => self.args.push((name, value)),
+        }
+    }
+}
+
+/// 起動時に全言語の文字列表を読み込むシステム
+fn load_string_tables(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = Language::ALL
+        .into_iter()
+        .map(|language| (language, asset_server.load(language.strings_path())))
+        .collect();
+    commands.insert_resource(StringTableHandles(handles));
+}
+
+/// 文字列表の読み込み・再読み込みや言語の切り替えに合わせて、表示に使う文字列表を選び直すシステム
+fn rebuild_strings(
+    mut events: MessageReader<AssetEvent<StringTable>>,
+    settings: Res<Settings>,
+    handles: Option<Res<StringTableHandles>>,
+    tables: Res<Assets<StringTable>>,
+    mut strings: ResMut<Strings>,
+) {
+    let Some(handles) = handles else {
+        return;
+    };
+
+    let mut reloaded = false;
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { .. } | AssetEvent::Modified { .. } = event {
+            reloaded = true;
+        }
+    }
+    if !reloaded && !settings.is_changed() {
+        return;
+    }
+
+    let table = |language: Language| {
+        handles
+            .0
+            .get(&language)
+            .and_then(|handle| tables.get(handle))
+    };
+    let current = table(settings.language);
+    let fallback = table(FALLBACK_LANGUAGE);
+    // 言語を切り替えただけで中身が変わらない場合も、表示の更新のために変更扱いにする
+    *strings = Strings {
+        loaded: current.is_some() || fallback.is_some(),
+        current: current.cloned().unwrap_or_default(),
+        fallback: fallback.cloned().unwrap_or_default(),
+    };
+}
+
+/// LocalizedText の内容を Text に書き込むシステム
+fn render_localized_text(strings: Res<Strings>, mut query: Query<(Ref<LocalizedText>, &mut Text)>) {
+    for (localized, mut text) in &mut query {
+        if !strings.is_changed() && !localized.is_changed() {
+            continue;
+        }
+        let args: Vec<(&str, String)> = localized
+            .args
+            .iter()
+            .cloned()
+            .chain(
+                localized
+                    .localized_args
+                    .iter()
+                    .map(|&(name, key)| (name, strings.get(key))),
+            )
+            .collect();
+        text.0 = strings.format(localized.key, &args);
+    }
+}
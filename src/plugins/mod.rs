@@ -1,4 +1,13 @@
+pub mod audio;
+pub mod campaign;
+pub mod controls;
 pub mod game;
 pub mod gameclear;
 pub mod gameover;
+pub mod highscore;
+pub mod input;
+pub mod locale;
+pub mod replay;
+pub mod settings;
+pub mod simulation;
 pub mod title;
@@ -0,0 +1,636 @@
+/// リプレイ（操作の記録と再生）
+///
+/// プレイごとにシードと人数を、ステージごとに画面の広さ・ステージ定義の指紋・ティックごとの操作を記録し、
+/// 結果画面に入るときにファイルへ保存する。再生するときは同じシードでステージを始め、
+/// 記録した操作をティックごとに流し込むことで同じ展開を再現する。
+/// ステージ定義が記録したときと違えば同じ展開にならないため、再生しない
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::font::DefaultFont;
+use crate::plugins::campaign::{CAMPAIGN_STAGES, Campaign};
+use crate::plugins::game::{
+    ActiveStage, FIXED_TIMESTEP_HZ, GameRng, MAX_PLAYERS, PauseState, PlayerCount, Playfield,
+    Score, StageStartSystems,
+};
+use crate::plugins::highscore::{NameEntry, NameEntrySystems};
+use crate::plugins::input::{Action, ActionState, FixedActionState, FixedInputSystems, TickInput};
+use crate::plugins::locale::LocalizedText;
+use crate::state::GameState;
+use crate::storage;
+
+/// リプレイファイルの形式バージョン
+const REPLAY_FILE_VERSION: u32 = 2;
+/// リプレイファイルを保存するディレクトリ名（データディレクトリからの相対パス）
+const REPLAY_DIR_NAME: &str = "replays";
+/// リプレイファイルの拡張子
+const REPLAY_EXTENSION: &str = "replay.ron";
+/// 1回のシークで進める・戻す秒数
+const SEEK_SECS: f64 = 5.0;
+/// シーク中の再生速度
+const SEEK_SPEED: f32 = 32.0;
+/// 選べる再生速度
+const PLAYBACK_SPEEDS: [f32; 4] = [1.0, 2.0, 4.0, 8.0];
+
+/// リプレイのプラグイン
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayRecorder>();
+
+        // 記録（プレイの開始時に記録を始め、結果画面に入るときに保存する）
+        app.add_systems(OnExit(GameState::Title), start_recording);
+        app.add_systems(OnExit(GameState::GameOver), start_recording);
+        app.add_systems(OnExit(GameState::GameClear), start_recording);
+        app.add_systems(
+            OnEnter(GameState::Game),
+            begin_stage_recording
+                .after(StageStartSystems)
+                .run_if(not(resource_exists::<ReplayPlayback>)),
+        );
+        app.add_systems(
+            FixedPreUpdate,
+            record_tick
+                .after(FixedInputSystems)
+                .run_if(in_state(PauseState::Running).and(not(resource_exists::<ReplayPlayback>))),
+        );
+        for entered in [GameState::GameOver, GameState::GameClear] {
+            app.add_systems(
+                OnTransition {
+                    exited: GameState::Game,
+                    entered,
+                },
+                finish_recording.run_if(not(resource_exists::<ReplayPlayback>)),
+            );
+        }
+
+        // 再生
+        app.add_systems(
+            Startup,
+            start_loaded_replay.run_if(resource_exists::<ReplayPlayback>),
+        );
+        app.add_systems(
+            Update,
+            watch_replay
+                .before(NameEntrySystems)
+                .run_if(resource_exists::<LastReplay>)
+                .run_if(not(resource_exists::<NameEntry>))
+                .run_if(in_state(GameState::GameOver).or(in_state(GameState::GameClear))),
+        );
+        // ステージ開始前の画面の OnEnter より先にステージ番号を合わせるため、遷移のスケジュールで行う
+        for exited in [
+            GameState::Title,
+            GameState::StageResult,
+            GameState::GameOver,
+            GameState::GameClear,
+        ] {
+            app.add_systems(
+                OnTransition {
+                    exited,
+                    entered: GameState::StageIntro,
+                },
+                prepare_replay_stage.run_if(resource_exists::<ReplayPlayback>),
+            );
+        }
+        app.add_systems(
+            OnEnter(GameState::Game),
+            (begin_stage_playback, setup_viewer_ui)
+                .after(StageStartSystems)
+                .run_if(resource_exists::<ReplayPlayback>),
+        );
+        app.add_systems(
+            FixedPreUpdate,
+            feed_replay_input
+                .after(FixedInputSystems)
+                .run_if(in_state(PauseState::Running).and(resource_exists::<ReplayPlayback>)),
+        );
+        app.add_systems(
+            FixedLast,
+            stop_at_seek_target.run_if(resource_exists::<ReplayPlayback>),
+        );
+        app.add_systems(
+            Update,
+            (viewer_update, update_viewer_ui)
+                .chain()
+                .run_if(in_state(PauseState::Running).and(resource_exists::<ReplayPlayback>)),
+        );
+        app.add_systems(
+            OnExit(GameState::Game),
+            end_stage_playback.run_if(resource_exists::<ReplayPlayback>),
+        );
+        app.add_systems(
+            OnExit(GameState::GameOver),
+            stop_finished_replay.run_if(resource_exists::<ReplayPlayback>),
+        );
+        app.add_systems(
+            OnExit(GameState::GameClear),
+            stop_finished_replay.run_if(resource_exists::<ReplayPlayback>),
+        );
+        app.add_systems(
+            OnTransition {
+                exited: GameState::Game,
+                entered: GameState::Title,
+            },
+            stop_replay.run_if(resource_exists::<ReplayPlayback>),
+        );
+    }
+}
+
+/// リプレイファイルの中身（1回のプレイ分）
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Replay {
+    /// 形式バージョン
+    version: u32,
+    /// プレイのシード
+    seed: u64,
+    /// プレイヤーの人数
+    players: usize,
+    /// 遊んだステージ（遊んだ順）
+    stages: Vec<StageReplay>,
+}
+
+/// 1ステージ分の記録
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct StageReplay {
+    /// ステージ番号（CAMPAIGN_STAGES の添字）
+    stage: usize,
+    /// 画面の広さ（中心から画面端までの距離）
+    playfield: (f32, f32),
+    /// 記録したときのステージ定義の指紋（StageDefinition::fingerprint）
+    stage_hash: u64,
+    /// ティックごとの各プレイヤーの操作（同じ操作が続くティックは回数とまとめる）
+    inputs: Vec<(u32, Vec<TickInput>)>,
+}
+
+impl StageReplay {
+    /// 1ティック分の操作を加える
+    fn push(&mut self, tick: Vec<TickInput>) {
+        match self.inputs.last_mut() {
+            Some((count, last)) if *last == tick => *count += 1,
+            _ => self.inputs.push((1, tick)),
+        }
+    }
+
+    /// ティックごとの操作の列に展開する
+    fn expand(&self) -> Vec<Vec<TickInput>> {
+        self.inputs
+            .iter()
+            .flat_map(|(count, tick)| std::iter::repeat_n(tick.clone(), *count as usize))
+            .collect()
+    }
+}
+
+impl Replay {
+    /// 空の記録を作る
+    fn new(players: usize) -> Self {
+        Self {
+            version: REPLAY_FILE_VERSION,
+            seed: 0,
+            players,
+            stages: Vec::new(),
+        }
+    }
+
+    /// リプレイファイルを読み込む
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = storage::read_file(path)
+            .map_err(|err| err.to_string())?
+            .ok_or_else(|| "file not found".to_string())?;
+        Self::parse(&contents)
+    }
+
+    /// 文字列からリプレイを読み込む
+    ///
+    /// 新しい形式のファイルは再生しても同じ展開にならないため、読み込まない
+    fn parse(contents: &str) -> Result<Self, String> {
+        let replay: Self = ron::from_str(contents).map_err(|err| err.to_string())?;
+        if replay.version != REPLAY_FILE_VERSION {
+            return Err(format!(
+                "replay file version {} is not supported (expected {})",
+                replay.version, REPLAY_FILE_VERSION
+            ));
+        }
+        if replay.stages.is_empty() {
+            return Err("replay has no stages".to_string());
+        }
+        Ok(replay)
+    }
+
+    /// リプレイをファイルに保存する（保存先はデータディレクトリの replays/）
+    fn save(&self) {
+        let Some(dir) = storage::data_dir() else {
+            return;
+        };
+        let path = dir.join(REPLAY_DIR_NAME).join(format!(
+            "{}-{:016x}.{}",
+            storage::today(),
+            self.seed,
+            REPLAY_EXTENSION
+        ));
+        // ティックごとの操作が並ぶため、整形せずに保存する
+        let result = ron::to_string(self)
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                storage::write_file_atomic(&path, &contents).map_err(|err| err.to_string())
+            });
+        match result {
+            Ok(()) => info!("replay saved to {}", path.display()),
+            Err(err) => warn!("failed to save replay to {}: {}", path.display(), err),
+        }
+    }
+}
+
+/// 起動時に指定されたリプレイファイルを読み込み、すぐに再生する
+pub fn watch_file(app: &mut App, path: PathBuf) {
+    match Replay::load(&path) {
+        Ok(replay) => {
+            app.insert_resource(ReplayPlayback::new(replay.clone()));
+            app.insert_resource(LastReplay(replay));
+        }
+        Err(err) => warn!("failed to load replay from {}: {}", path.display(), err),
+    }
+}
+
+/// 記録中のプレイを保持するリソース
+#[derive(Resource, Default)]
+struct ReplayRecorder(Option<Replay>);
+
+/// 最後に遊んだ（または読み込んだ）プレイのリプレイを保持するリソース
+///
+/// このリソースがある間、結果画面からリプレイを見られる
+#[derive(Resource)]
+pub struct LastReplay(Replay);
+
+/// リプレイの再生中の状態を保持するリソース
+///
+/// このリソースがある間、ゲームの操作は記録した操作に置き換わる
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    /// 再生しているリプレイ
+    replay: Replay,
+    /// 再生しているステージ（replay.stages の添字）
+    stage: usize,
+    /// 再生しているステージのティックごとの操作
+    inputs: Vec<Vec<TickInput>>,
+    /// ステージの開始から進めたティック数
+    tick: usize,
+    /// ステージの開始時のスコア（シークで巻き戻すときに戻す）
+    score: Score,
+    /// 再生速度（PLAYBACK_SPEEDS の添字）
+    speed: usize,
+    /// 一時停止中かどうか
+    paused: bool,
+    /// シークで止めるティック（シーク中でなければ None）
+    seek_target: Option<usize>,
+    /// シークで巻き戻すため、同じステージを最初からやり直しているかどうか
+    restarting: bool,
+}
+
+impl ReplayPlayback {
+    /// 最初のステージから再生する
+    fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            stage: 0,
+            inputs: Vec::new(),
+            tick: 0,
+            score: Score::default(),
+            speed: 0,
+            paused: false,
+            seek_target: None,
+            restarting: false,
+        }
+    }
+
+    /// 最後のステージまで再生し終えたかどうか
+    fn finished(&self) -> bool {
+        self.stage >= self.replay.stages.len()
+    }
+
+    /// 再生の状態に合わせて、ゲームの時間の進み方を変える
+    fn apply_speed(&self, time: &mut Time<Virtual>) {
+        if self.seek_target.is_some() {
+            time.set_relative_speed(SEEK_SPEED);
+            time.unpause();
+        } else {
+            time.set_relative_speed(PLAYBACK_SPEEDS[self.speed]);
+            if self.paused {
+                time.pause();
+            } else {
+                time.unpause();
+            }
+        }
+    }
+}
+
+/// プレイの開始時に記録を始めるシステム
+fn start_recording(mut recorder: ResMut<ReplayRecorder>, player_count: Res<PlayerCount>) {
+    recorder.0 = Some(Replay::new(player_count.0));
+}
+
+/// ステージの開始時に、ステージの記録を始めるシステム
+fn begin_stage_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    game_rng: Res<GameRng>,
+    campaign: Res<Campaign>,
+    playfield: Res<Playfield>,
+    active_stage: Res<ActiveStage>,
+) {
+    let Some(replay) = recorder.0.as_mut() else {
+        return;
+    };
+    replay.seed = game_rng.seed();
+    replay.stages.push(StageReplay {
+        stage: campaign.current,
+        playfield: playfield.half_size.into(),
+        stage_hash: active_stage.0.fingerprint(),
+        inputs: Vec::new(),
+    });
+}
+
+/// ティックごとに各プレイヤーの操作を記録するシステム
+fn record_tick(mut recorder: ResMut<ReplayRecorder>, actions: Res<FixedActionState>) {
+    let Some(replay) = recorder.0.as_mut() else {
+        return;
+    };
+    let players = replay.players;
+    if let Some(stage) = replay.stages.last_mut() {
+        stage.push((0..players).map(|player| actions.record(player)).collect());
+    }
+}
+
+/// 結果画面に入るときに、記録したプレイを保存するシステム
+fn finish_recording(mut commands: Commands, mut recorder: ResMut<ReplayRecorder>) {
+    let Some(replay) = recorder.0.take() else {
+        return;
+    };
+    replay.save();
+    commands.insert_resource(LastReplay(replay));
+}
+
+/// 起動時にリプレイファイルが指定されていれば、再生を始めるシステム
+fn start_loaded_replay(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::StageIntro);
+}
+
+/// 結果画面でリプレイを見る操作をしたら、再生を始めるシステム
+fn watch_replay(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    last_replay: Res<LastReplay>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if actions.any_just_pressed(Action::WatchReplay) {
+        commands.insert_resource(ReplayPlayback::new(last_replay.0.clone()));
+        next_state.set(GameState::StageIntro);
+    }
+}
+
+/// ステージ開始前の画面に入るときに、再生するステージ・人数・シードを合わせるシステム
+fn prepare_replay_stage(
+    mut commands: Commands,
+    playback: Res<ReplayPlayback>,
+    mut campaign: ResMut<Campaign>,
+    mut player_count: ResMut<PlayerCount>,
+    mut game_rng: ResMut<GameRng>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(stage) = playback.replay.stages.get(playback.stage) else {
+        // 記録より先のステージへ進んだ（展開がずれた）ら再生をやめる
+        warn!("replay has no more recorded stages");
+        commands.remove_resource::<ReplayPlayback>();
+        next_state.set(GameState::Title);
+        return;
+    };
+    campaign.current = stage.stage.min(CAMPAIGN_STAGES.len() - 1);
+    player_count.0 = playback.replay.players.clamp(1, MAX_PLAYERS);
+    game_rng.set_run_seed(playback.replay.seed);
+}
+
+/// ステージの開始時に、記録した画面の広さと操作で再生を始めるシステム
+///
+/// ステージ定義が記録したときと違う場合は、再生をやめてタイトルへ戻る
+fn begin_stage_playback(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    active_stage: Res<ActiveStage>,
+    mut playfield: ResMut<Playfield>,
+    mut score: ResMut<Score>,
+    mut time: ResMut<Time<Virtual>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let playback = &mut *playback;
+    let Some(stage) = playback.replay.stages.get(playback.stage) else {
+        return;
+    };
+    if stage.stage_hash != active_stage.0.fingerprint() {
+        warn!(
+            "stage {} definition differs from the one the replay was recorded with, stopping playback",
+            stage.stage + 1
+        );
+        commands.remove_resource::<ReplayPlayback>();
+        next_state.set(GameState::Title);
+        return;
+    }
+    playfield.half_size = stage.playfield.into();
+    playback.inputs = stage.expand();
+    playback.tick = 0;
+    if playback.restarting {
+        // シークで巻き戻すときは、ステージの開始時のスコアに戻す
+        playback.restarting = false;
+        *score = playback.score.clone();
+    } else {
+        playback.score = score.clone();
+    }
+    if playback.seek_target == Some(0) {
+        playback.seek_target = None;
+    }
+    playback.apply_speed(&mut time);
+}
+
+/// ティックごとに記録した操作を流し込むシステム
+fn feed_replay_input(
+    mut playback: ResMut<ReplayPlayback>,
+    mut actions: ResMut<FixedActionState>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    match playback.inputs.get(playback.tick) {
+        Some(inputs) => {
+            for (player, input) in inputs.iter().enumerate() {
+                actions.replay(player, *input);
+            }
+        }
+        None => {
+            for player in 0..MAX_PLAYERS {
+                actions.replay(player, TickInput::default());
+            }
+            // 記録が尽きてもステージが終わらないのは展開がずれたときなので、一時停止して知らせる
+            if !playback.paused {
+                warn!(
+                    "replay ran out of recorded input at tick {} of stage {}",
+                    playback.tick,
+                    playback.stage + 1
+                );
+                playback.paused = true;
+                playback.seek_target = None;
+                playback.apply_speed(&mut time);
+            }
+        }
+    }
+    playback.tick += 1;
+}
+
+/// シークで目標のティックまで進めたら、それ以上進めずに止めるシステム
+fn stop_at_seek_target(
+    mut playback: ResMut<ReplayPlayback>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    let Some(target) = playback.seek_target else {
+        return;
+    };
+    if playback.tick < target {
+        return;
+    }
+    playback.seek_target = None;
+    // このフレームで残りのティックを進めないよう、たまった時間を捨てる
+    let overstep = fixed_time.overstep();
+    fixed_time.discard_overstep(overstep);
+    playback.apply_speed(&mut virtual_time);
+}
+
+/// 再生中の操作（決定で一時停止、上下で再生速度、左右でシーク）を行うシステム
+fn viewer_update(
+    actions: Res<ActionState>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut time: ResMut<Time<Virtual>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // シーク中は操作を受け付けない
+    if playback.seek_target.is_some() {
+        return;
+    }
+
+    let seek_ticks = (SEEK_SECS * FIXED_TIMESTEP_HZ) as usize;
+    if actions.any_just_pressed(Action::Confirm) {
+        playback.paused = !playback.paused;
+    } else if actions.any_just_pressed(Action::MenuUp) {
+        playback.speed = (playback.speed + 1).min(PLAYBACK_SPEEDS.len() - 1);
+    } else if actions.any_just_pressed(Action::MenuDown) {
+        playback.speed = playback.speed.saturating_sub(1);
+    } else if actions.any_just_pressed(Action::MenuRight) {
+        let target = (playback.tick + seek_ticks).min(playback.inputs.len());
+        if target > playback.tick {
+            playback.seek_target = Some(target);
+        }
+    } else if actions.any_just_pressed(Action::MenuLeft) {
+        // 巻き戻しはステージを最初からやり直し、目標のティックまで早送りする
+        playback.seek_target = Some(playback.tick.saturating_sub(seek_ticks));
+        playback.restarting = true;
+        next_state.set(GameState::Game);
+    } else {
+        return;
+    }
+    playback.apply_speed(&mut time);
+}
+
+/// ステージの終了時に、次のステージへ進めて時間の進み方を戻すシステム
+fn end_stage_playback(mut playback: ResMut<ReplayPlayback>, mut time: ResMut<Time<Virtual>>) {
+    time.set_relative_speed(1.0);
+    time.unpause();
+    if playback.restarting {
+        return;
+    }
+    playback.stage += 1;
+    playback.seek_target = None;
+    playback.paused = false;
+}
+
+/// 最後のステージまで再生し終えた結果画面から出るときに、再生を終えるシステム
+fn stop_finished_replay(mut commands: Commands, playback: Res<ReplayPlayback>) {
+    if playback.finished() {
+        commands.remove_resource::<ReplayPlayback>();
+    }
+}
+
+/// 再生中にタイトルへ戻ったときに、再生を終えるシステム
+fn stop_replay(mut commands: Commands) {
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+/// 再生の状態を表示するテキストのマーカー
+#[derive(Component)]
+struct ReplayStatusText;
+
+/// 再生中の状態と操作の説明を画面下に表示する
+fn setup_viewer_ui(mut commands: Commands, asset: Res<DefaultFont>) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(20.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::FlexEnd,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            DespawnOnExit(GameState::Game),
+        ))
+        .with_children(|parent| {
+            // 再生の状態
+            parent.spawn((
+                ReplayStatusText,
+                LocalizedText::new("replay.playing"),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.8, 0.2)),
+            ));
+
+            // 操作の説明
+            parent.spawn((
+                LocalizedText::new("replay.help"),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            ));
+        });
+}
+
+/// 再生の状態の表示を更新するシステム
+fn update_viewer_ui(
+    playback: Res<ReplayPlayback>,
+    mut query: Query<&mut LocalizedText, With<ReplayStatusText>>,
+) {
+    if !playback.is_changed() {
+        return;
+    }
+    let key = if playback.seek_target.is_some() {
+        "replay.seeking"
+    } else if playback.paused {
+        "replay.paused"
+    } else {
+        "replay.playing"
+    };
+    for mut text in &mut query {
+        text.set_key(key);
+        text.set_arg("speed", PLAYBACK_SPEEDS[playback.speed]);
+        text.set_arg("time", format_ticks(playback.tick));
+        text.set_arg("total", format_ticks(playback.inputs.len()));
+    }
+}
+
+/// ティック数を "分:秒" 形式にする
+fn format_ticks(ticks: usize) -> String {
+    let secs = (ticks as f64 / FIXED_TIMESTEP_HZ) as usize;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
@@ -0,0 +1,531 @@
+/// 設定（画面・音量・言語など）と設定画面
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::window::{MonitorSelection, PresentMode, PrimaryWindow, WindowMode, WindowResolution};
+use serde::{Deserialize, Serialize};
+
+use crate::font::DefaultFont;
+use crate::plugins::audio::AudioVolumes;
+use crate::plugins::input::{Action, ActionState};
+use crate::plugins::locale::{LocalizedText, Strings};
+use crate::storage;
+
+/// 設定ファイルの形式バージョン
+const SETTINGS_FILE_VERSION: u32 = 1;
+/// 設定ファイル名
+const SETTINGS_FILE_NAME: &str = "settings.ron";
+/// 選べるウィンドウサイズ（幅, 高さ）
+const RESOLUTIONS: [(u32, u32); 4] = [(800, 800), (1000, 1000), (1024, 768), (1280, 720)];
+/// ウィンドウサイズとして受け付ける範囲（設定ファイルを手で編集した場合の補正用）
+const RESOLUTION_RANGE: std::ops::RangeInclusive<u32> = 320..=7680;
+/// 音量を1回の操作で変える量
+const VOLUME_STEP: f32 = 0.1;
+
+/// 選択中の行の背景色
+const SELECTED_ROW_COLOR: Color = Color::srgba(0.4, 0.8, 1.0, 0.3);
+
+/// 設定画面のプラグイン
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        // 設定は main でウィンドウを作る前に読み込んで挿入する。ない場合は既定の設定を使う
+        app.init_resource::<Settings>();
+        app.init_state::<SettingsMenuState>();
+        app.add_systems(
+            OnEnter(SettingsMenuState::Open),
+            (reset_settings_menu, setup_ui).chain(),
+        );
+        app.add_systems(OnExit(SettingsMenuState::Open), save_settings);
+        app.add_systems(
+            Update,
+            (settings_update, update_settings_ui)
+                .chain()
+                .run_if(in_state(SettingsMenuState::Open)),
+        );
+        app.add_systems(Update, apply_settings.run_if(resource_changed::<Settings>));
+    }
+}
+
+/// 設定画面の開閉状態
+///
+/// ゲーム画面の上にも重ねて開けるよう、GameState とは独立した状態にする
+#[derive(States, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+pub enum SettingsMenuState {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// 表示する言語
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+pub enum Language {
+    #[default]
+    Japanese,
+    English,
+}
+
+impl Language {
+    /// 設定画面に表示する名前（どの言語で表示しているときも、その言語自身での名前にする）
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Japanese => "日本語",
+            Self::English => "English",
+        }
+    }
+}
+
+/// 設定を保持するリソース
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    /// ウィンドウサイズ（幅, 高さ）
+    pub resolution: (u32, u32),
+    /// フルスクリーンで表示するかどうか
+    pub fullscreen: bool,
+    /// 垂直同期を行うかどうか
+    pub vsync: bool,
+    /// 全体の音量（0.0 ~ 1.0）
+    pub master_volume: f32,
+    /// BGM の音量（0.0 ~ 1.0）
+    pub music_volume: f32,
+    /// 効果音の音量（0.0 ~ 1.0）
+    pub sfx_volume: f32,
+    /// 表示する言語
+    pub language: Language,
+    /// 画面の揺れを有効にするかどうか
+    pub screen_shake: bool,
+    /// 保存先（保存できない環境では None）
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let volumes = AudioVolumes::default();
+        Self {
+            resolution: RESOLUTIONS[0],
+            fullscreen: false,
+            vsync: true,
+            master_volume: volumes.master,
+            music_volume: volumes.music,
+            sfx_volume: volumes.sfx,
+            language: Language::default(),
+            screen_shake: true,
+            path: None,
+        }
+    }
+}
+
+/// 設定ファイルの中身
+#[derive(Serialize, Deserialize)]
+struct SettingsFile {
+    /// 形式バージョン
+    version: u32,
+    /// 設定
+    #[serde(default)]
+    settings: Settings,
+}
+
+impl Settings {
+    /// 設定ファイルを読み込む（main でウィンドウを作る前に呼ぶ）
+    ///
+    /// 読み込めないファイルは `.bak` に退避して既定の設定から始める。
+    /// ログの出力はまだ始まっていないため、警告は出さずに返し、呼び出し側がアプリを組んだ後に出力する
+    pub fn load() -> (Self, Vec<String>) {
+        let path = storage::config_dir().map(|dir| dir.join(SETTINGS_FILE_NAME));
+        let mut settings = Self::default();
+        let mut warnings = Vec::new();
+
+        if let Some(path) = &path {
+            match storage::read_file(path) {
+                Ok(Some(contents)) => match Self::parse(&contents, &mut warnings) {
+                    Ok(parsed) => settings = parsed,
+                    Err(err) => {
+                        warnings.push(format!(
+                            "settings file {} is corrupted: {}",
+                            path.display(),
+                            err
+                        ));
+                        if let Err(err) = storage::backup_file(path) {
+                            warnings
+                                .push(format!("failed to back up corrupted settings file: {err}"));
+                        }
+                    }
+                },
+                Ok(None) => {}
+                Err(err) => warnings.push(format!(
+                    "failed to read settings from {}: {}",
+                    path.display(),
+                    err
+                )),
+            }
+        }
+
+        settings.path = path;
+        (settings, warnings)
+    }
+
+    /// 文字列から設定を読み込む
+    ///
+    /// 項目ごとの不正な値は既定の値や範囲内の値に補正し、補正した内容を warnings に追加する
+    fn parse(contents: &str, warnings: &mut Vec<String>) -> Result<Self, ron::error::SpannedError> {
+        let file: SettingsFile = ron::from_str(contents)?;
+        if file.version > SETTINGS_FILE_VERSION {
+            warnings.push(format!(
+                "settings file version {} is newer than supported version {}",
+                file.version, SETTINGS_FILE_VERSION
+            ));
+        }
+
+        let mut settings = file.settings;
+        let (width, height) = settings.resolution;
+        if !RESOLUTION_RANGE.contains(&width) || !RESOLUTION_RANGE.contains(&height) {
+            warnings.push(format!("invalid resolution {width}x{height} in settings"));
+            settings.resolution = RESOLUTIONS[0];
+        }
+        for volume in [
+            &mut settings.master_volume,
+            &mut settings.music_volume,
+            &mut settings.sfx_volume,
+        ] {
+            *volume = if volume.is_finite() {
+                volume.clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+        }
+        Ok(settings)
+    }
+
+    /// 設定をファイルに保存する
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let file = SettingsFile {
+            version: SETTINGS_FILE_VERSION,
+            settings: self.clone(),
+        };
+        let result = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                storage::write_file_atomic(path, &contents).map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            warn!("failed to save settings to {}: {}", path.display(), err);
+        }
+    }
+
+    /// 設定に合わせたウィンドウ（main でウィンドウを作るときに使う）
+    pub fn window(&self) -> Window {
+        let (width, height) = self.resolution;
+        Window {
+            resolution: WindowResolution::new(width, height),
+            mode: self.window_mode(),
+            present_mode: self.present_mode(),
+            ..default()
+        }
+    }
+
+    /// フルスクリーンの設定に合わせた表示モード
+    fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    /// 垂直同期の設定に合わせた表示の更新方法
+    fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+}
+
+/// 設定画面の項目（表示順）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SettingItem {
+    Resolution,
+    Fullscreen,
+    VSync,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Language,
+    ScreenShake,
+}
+
+impl SettingItem {
+    /// すべての項目
+    const ALL: [SettingItem; 8] = [
+        SettingItem::Resolution,
+        SettingItem::Fullscreen,
+        SettingItem::VSync,
+        SettingItem::MasterVolume,
+        SettingItem::MusicVolume,
+        SettingItem::SfxVolume,
+        SettingItem::Language,
+        SettingItem::ScreenShake,
+    ];
+
+    /// 項目の名前の文字列表のキー
+    const fn label_key(self) -> &'static str {
+        match self {
+            Self::Resolution => "settings.resolution",
+            Self::Fullscreen => "settings.fullscreen",
+            Self::VSync => "settings.vsync",
+            Self::MasterVolume => "settings.master_volume",
+            Self::MusicVolume => "settings.music_volume",
+            Self::SfxVolume => "settings.sfx_volume",
+            Self::Language => "settings.language",
+            Self::ScreenShake => "settings.screen_shake",
+        }
+    }
+
+    /// 現在の値の表示文字列
+    fn value_label(self, settings: &Settings, strings: &Strings) -> String {
+        let on_off = |value: bool| strings.get(if value { "settings.on" } else { "settings.off" });
+        let percent = |value: f32| format!("{:.0}%", value * 100.0);
+        match self {
+            Self::Resolution => format!("{}x{}", settings.resolution.0, settings.resolution.1),
+            Self::Fullscreen => on_off(settings.fullscreen),
+            Self::VSync => on_off(settings.vsync),
+            Self::MasterVolume => percent(settings.master_volume),
+            Self::MusicVolume => percent(settings.music_volume),
+            Self::SfxVolume => percent(settings.sfx_volume),
+            Self::Language => settings.language.label().to_string(),
+            Self::ScreenShake => on_off(settings.screen_shake),
+        }
+    }
+
+    /// 値を1段階変える（step は +1 か -1。ON/OFF の項目はどちらでも切り替わる）
+    fn adjust(self, settings: &mut Settings, step: i32) {
+        let volume = |value: f32| {
+            let steps = (value / VOLUME_STEP).round() + step as f32;
+            (steps * VOLUME_STEP).clamp(0.0, 1.0)
+        };
+        match self {
+            Self::Resolution => {
+                // 一覧にないサイズ（手で編集した場合）からは先頭に戻す
+                let next = RESOLUTIONS
+                    .iter()
+                    .position(|&r| r == settings.resolution)
+                    .map_or(0, |i| {
+                        (i as i32 + step).rem_euclid(RESOLUTIONS.len() as i32) as usize
+                    });
+                settings.resolution = RESOLUTIONS[next];
+            }
+            Self::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            Self::VSync => settings.vsync = !settings.vsync,
+            Self::MasterVolume => settings.master_volume = volume(settings.master_volume),
+            Self::MusicVolume => settings.music_volume = volume(settings.music_volume),
+            Self::SfxVolume => settings.sfx_volume = volume(settings.sfx_volume),
+            Self::Language => {
+                settings.language = match settings.language {
+                    Language::Japanese => Language::English,
+                    Language::English => Language::Japanese,
+                }
+            }
+            Self::ScreenShake => settings.screen_shake = !settings.screen_shake,
+        }
+    }
+}
+
+/// 設定画面の状態を保持するリソース
+#[derive(Resource, Default)]
+struct SettingsMenu {
+    /// 選択中の項目（SettingItem::ALL の添字）
+    cursor: usize,
+}
+
+/// 項目の行のコンポーネント（SettingItem::ALL の添字）
+#[derive(Component)]
+struct SettingsRow(usize);
+
+/// 項目の値の表示のコンポーネント（SettingItem::ALL の添字）
+#[derive(Component)]
+struct SettingsValueText(usize);
+
+/// 画面を開くたびに選択位置を先頭に戻す
+fn reset_settings_menu(mut commands: Commands) {
+    commands.insert_resource(SettingsMenu::default());
+}
+
+/// 設定画面を閉じるときに設定を保存する
+fn save_settings(settings: Res<Settings>) {
+    settings.save();
+}
+
+/// 設定をウィンドウと音量に反映するシステム
+fn apply_settings(
+    settings: Res<Settings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut volumes: ResMut<AudioVolumes>,
+) {
+    volumes.set_if_neq(AudioVolumes {
+        master: settings.master_volume,
+        music: settings.music_volume,
+        sfx: settings.sfx_volume,
+    });
+
+    let Ok(mut window) = window_query.single_mut() else {
+        return;
+    };
+    // フルスクリーン中は画面に合わせた大きさになるので、ウィンドウサイズは変えない
+    let (width, height) = settings.resolution;
+    if !settings.fullscreen
+        && (window.resolution.physical_width() != width
+            || window.resolution.physical_height() != height)
+    {
+        window.resolution.set_physical_resolution(width, height);
+    }
+    if window.mode != settings.window_mode() {
+        window.mode = settings.window_mode();
+    }
+    if window.present_mode != settings.present_mode() {
+        window.present_mode = settings.present_mode();
+    }
+}
+
+/// 設定画面の操作処理（上下で項目を選び、左右・決定で値を変える）
+fn settings_update(
+    actions: Res<ActionState>,
+    mut menu: ResMut<SettingsMenu>,
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<SettingsMenuState>>,
+) {
+    if actions.any_just_pressed(Action::Pause) {
+        next_state.set(SettingsMenuState::Closed);
+        return;
+    }
+
+    let count = SettingItem::ALL.len();
+    if actions.any_just_pressed(Action::MenuUp) {
+        menu.cursor = (menu.cursor + count - 1) % count;
+    }
+    if actions.any_just_pressed(Action::MenuDown) {
+        menu.cursor = (menu.cursor + 1) % count;
+    }
+
+    let item = SettingItem::ALL[menu.cursor];
+    if actions.any_just_pressed(Action::MenuLeft) {
+        item.adjust(&mut settings, -1);
+    } else if actions.any_just_pressed(Action::MenuRight)
+        || actions.any_just_pressed(Action::Confirm)
+    {
+        item.adjust(&mut settings, 1);
+    }
+}
+
+/// 設定画面の表示を更新するシステム
+fn update_settings_ui(
+    menu: Res<SettingsMenu>,
+    settings: Res<Settings>,
+    strings: Res<Strings>,
+    mut row_query: Query<(&SettingsRow, &mut BackgroundColor)>,
+    mut value_query: Query<(&SettingsValueText, &mut Text)>,
+) {
+    if !menu.is_changed() && !settings.is_changed() && !strings.is_changed() {
+        return;
+    }
+
+    for (row, mut background) in &mut row_query {
+        background.0 = if row.0 == menu.cursor {
+            SELECTED_ROW_COLOR
+        } else {
+            Color::NONE
+        };
+    }
+    for (value, mut text) in &mut value_query {
+        text.0 = SettingItem::ALL[value.0].value_label(&settings, &strings);
+    }
+}
+
+/// UIのセットアップ（タイトル画面やポーズ画面の上に重ねて表示する）
+fn setup_ui(mut commands: Commands, asset: Res<DefaultFont>) {
+    let text_font = |size: f32| TextFont {
+        font: asset.font.clone(),
+        font_size: size,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.9)),
+            ZIndex(200),
+            DespawnOnExit(SettingsMenuState::Open),
+        ))
+        .with_children(|parent| {
+            // 見出し
+            parent.spawn((
+                LocalizedText::new("settings.title"),
+                text_font(48.0),
+                TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    ..default()
+                },
+            ));
+
+            // 項目ごとの名前と値
+            for (index, item) in SettingItem::ALL.into_iter().enumerate() {
+                parent
+                    .spawn((
+                        Node {
+                            flex_direction: FlexDirection::Row,
+                            padding: UiRect::axes(Val::Px(12.0), Val::Px(4.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::NONE),
+                        SettingsRow(index),
+                    ))
+                    .with_children(|row| {
+                        row.spawn((
+                            LocalizedText::new(item.label_key()),
+                            text_font(26.0),
+                            TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                            Node {
+                                width: Val::Px(260.0),
+                                ..default()
+                            },
+                        ));
+                        row.spawn((
+                            Text::new(""),
+                            text_font(26.0),
+                            TextColor(Color::WHITE),
+                            Node {
+                                width: Val::Px(180.0),
+                                ..default()
+                            },
+                            SettingsValueText(index),
+                        ));
+                    });
+            }
+
+            // 操作説明
+            parent.spawn((
+                LocalizedText::new("settings.help"),
+                text_font(20.0),
+                TextColor(Color::srgb(1.0, 0.85, 0.0)),
+                Node {
+                    margin: UiRect::top(Val::Px(20.0)),
+                    ..default()
+                },
+            ));
+        });
+}
@@ -0,0 +1,627 @@
+/// ヘッドレスのシミュレーション（バランス調整用）
+///
+/// ウィンドウ・描画・音声なしでゲームの処理だけを動かし、ボットに指定回数プレイさせて
+/// 生存時間・撃破数・ダメージの原因・クリア率などの統計を CSV か JSON で出力する。
+/// 1回の更新で1ティック進めるため、実時間を待たずに最速で回る
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bevy::input::InputPlugin;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use rand::rngs::Xoshiro256PlusPlus;
+use rand::{RngExt, SeedableRng};
+use serde::Serialize;
+
+use crate::font::DefaultFont;
+use crate::plugins::audio::PlaySfx;
+use crate::plugins::campaign::Campaign;
+use crate::plugins::controls::ControlsMenuState;
+use crate::plugins::game::{
+    Boss, DamageSource, Enemy, EnemyBullet, FIXED_TIMESTEP_HZ, GameRng, MAX_PLAYERS, PauseState,
+    Player, PlayerCount, PlayerDamaged, Pooled, Score, stages_loaded,
+};
+use crate::plugins::input::{
+    Action, FixedActionState, FixedInputSystems, InputBindings, TickInput,
+};
+use crate::plugins::settings::{Settings, SettingsMenuState};
+use crate::state::GameState;
+
+/// 既定のプレイ回数
+const DEFAULT_RUNS: usize = 100;
+/// 既定の1プレイの制限時間（秒。これを超えたら打ち切る）
+const DEFAULT_MAX_SECS: f32 = 600.0;
+/// ボットが狙いを合わせたとみなす横方向の距離（ピクセル）
+const BOT_AIM_TOLERANCE: f32 = 12.0;
+/// ボットが狙う方向へスティックを倒しきる横方向の距離（ピクセル）
+const BOT_STEER_DISTANCE: f32 = 60.0;
+/// ボットが敵の弾を避け始める縦方向の距離（ピクセル）
+const BOT_DODGE_RANGE: f32 = 160.0;
+/// ボットが敵の弾を避ける横方向の幅（ピクセル）
+const BOT_DODGE_WIDTH: f32 = 45.0;
+/// ランダムなボットが移動の向きを変えるまでのティック数
+const RANDOM_BOT_HOLD_TICKS: u32 = 20;
+/// ランダムなボットが1ティックに撃つ確率
+const RANDOM_BOT_FIRE_CHANCE: f64 = 0.05;
+
+/// ボットの種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BotKind {
+    /// 一番下の敵の真下へ動いて撃ち、近づく敵の弾を避ける
+    Scripted,
+    /// ランダムに動いて撃つ
+    Random,
+}
+
+/// 統計の出力形式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatsFormat {
+    /// 1プレイ1行の CSV
+    Csv,
+    /// 集計と各プレイの記録をまとめた JSON
+    Json,
+}
+
+/// シミュレーションの設定（コマンドライン引数から作る）
+#[derive(Resource, Clone, Debug)]
+pub struct SimulationOptions {
+    /// プレイ回数
+    runs: usize,
+    /// ボットの種類
+    bot: BotKind,
+    /// プレイヤーの人数
+    players: usize,
+    /// 最初のプレイのシード（以降のプレイは1ずつ増やす）
+    seed: u64,
+    /// 1プレイの制限時間（秒）
+    max_secs: f32,
+    /// 統計の出力先（None なら標準出力）
+    out: Option<PathBuf>,
+    /// 統計の出力形式
+    format: StatsFormat,
+}
+
+impl SimulationOptions {
+    /// コマンドライン引数（`--runs` `--bot` `--players` `--seed` `--max-secs` `--out` `--format`）から作る
+    ///
+    /// 出力形式を省略したときは、出力先の拡張子が .json なら JSON、それ以外は CSV にする
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let value = |name: &str| {
+            args.iter()
+                .skip_while(|arg| *arg != name)
+                .nth(1)
+                .map(String::as_str)
+        };
+        let parse = |name: &str| -> Result<Option<u64>, String> {
+            value(name)
+                .map(|v| {
+                    v.parse()
+                        .map_err(|err| format!("invalid {name} value {v}: {err}"))
+                })
+                .transpose()
+        };
+
+        let bot = match value("--bot") {
+            None | Some("scripted") => BotKind::Scripted,
+            Some("random") => BotKind::Random,
+            Some(other) => return Err(format!("unknown --bot value {other}")),
+        };
+        let out = value("--out").map(PathBuf::from);
+        let format = match value("--format") {
+            Some("csv") => StatsFormat::Csv,
+            Some("json") => StatsFormat::Json,
+            Some(other) => return Err(format!("unknown --format value {other}")),
+            None if out
+                .as_ref()
+                .is_some_and(|path| path.extension().is_some_and(|ext| ext == "json")) =>
+            {
+                StatsFormat::Json
+            }
+            None => StatsFormat::Csv,
+        };
+        let max_secs = match value("--max-secs") {
+            Some(v) => v
+                .parse::<f32>()
+                .ok()
+                .filter(|secs| secs.is_finite() && *secs > 0.0)
+                .ok_or_else(|| format!("invalid --max-secs value {v}"))?,
+            None => DEFAULT_MAX_SECS,
+        };
+        // 1回も遊ばずに統計を出すことはできないため、0回は受け付けない
+        let runs = match parse("--runs")? {
+            Some(0) => return Err("invalid --runs value 0: must be at least 1".to_string()),
+            Some(runs) => runs as usize,
+            None => DEFAULT_RUNS,
+        };
+
+        Ok(Self {
+            runs,
+            bot,
+            players: parse("--players")?
+                .map_or(1, |players| players as usize)
+                .clamp(1, MAX_PLAYERS),
+            seed: parse("--seed")?.unwrap_or_else(|| rand::rng().random()),
+            max_secs,
+            out,
+            format,
+        })
+    }
+}
+
+/// ウィンドウなしでシミュレーションを行い、統計を出力する
+pub fn run(options: SimulationOptions) -> AppExit {
+    let mut app = headless_app();
+    app.add_plugins(LogPlugin::default())
+        .add_plugins(SimulationPlugin)
+        .insert_resource(options);
+    app.run()
+}
+
+/// ウィンドウ・描画・音声なしでゲームの処理だけを動かすアプリを作る（シミュレーションとテスト用）
+///
+/// 1回の更新で1ティック分の時間が進む。ログの出力は呼び出し側で組み込む
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        // 実行中にファイルが書き換わっても結果が変わらないよう、アセットの変更は監視しない
+        .add_plugins((
+            AssetPlugin {
+                watch_for_changes_override: Some(false),
+                ..default()
+            },
+            StatesPlugin,
+            InputPlugin,
+        ))
+        // 実時間ではなく、1回の更新で1ティック分の時間を進める
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / FIXED_TIMESTEP_HZ,
+        )))
+        .init_state::<GameState>()
+        // 操作設定・設定画面は開かないが、ポーズ画面の操作が閉じていることを前提にするため状態だけ用意する
+        .init_state::<ControlsMenuState>()
+        .init_state::<SettingsMenuState>()
+        // 画面・音声のプラグインは組み込まないため、ゲームの処理が使うリソースとメッセージだけ用意する
+        .insert_resource(Settings::default())
+        .insert_resource(DefaultFont {
+            font: Handle::default(),
+        })
+        .add_message::<PlaySfx>()
+        // 進行状況やキー割り当ては保存しない
+        .init_resource::<Campaign>()
+        .insert_resource(InputBindings::default())
+        .add_plugins(crate::plugins::game::GamePlugin)
+        .add_plugins(crate::plugins::input::InputActionPlugin);
+    app
+}
+
+/// ボットの操作と統計の集計を行うプラグイン
+struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimulationStats>();
+        app.init_resource::<Bot>();
+        app.add_systems(
+            Update,
+            start_run
+                .run_if(in_state(GameState::Title))
+                .run_if(stages_loaded),
+        );
+        app.add_systems(
+            OnTransition {
+                exited: GameState::Title,
+                entered: GameState::StageIntro,
+            },
+            seed_run,
+        );
+        // ステージ間の画面は待たずに次へ進む
+        app.add_systems(OnEnter(GameState::StageIntro), skip_stage_intro);
+        app.add_systems(OnEnter(GameState::StageResult), skip_stage_result);
+        app.add_systems(
+            OnEnter(GameState::GameOver),
+            ((|| RunOutcome::GameOver).pipe(record_run), return_to_title).chain(),
+        );
+        app.add_systems(
+            OnEnter(GameState::GameClear),
+            ((|| RunOutcome::Cleared).pipe(record_run), return_to_title).chain(),
+        );
+        // 制限時間で打ち切ったときはゲームから直接タイトルへ戻る
+        app.add_systems(
+            OnTransition {
+                exited: GameState::Game,
+                entered: GameState::Title,
+            },
+            (|| RunOutcome::Timeout).pipe(record_run),
+        );
+        app.add_systems(
+            FixedPreUpdate,
+            drive_bot
+                .after(FixedInputSystems)
+                .run_if(in_state(PauseState::Running)),
+        );
+        app.add_systems(
+            FixedPostUpdate,
+            (count_damage, check_time_limit).run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+/// プレイの結果
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum RunOutcome {
+    /// 最後のステージのボスを倒した
+    Cleared,
+    /// プレイヤーが全員倒された
+    GameOver,
+    /// 制限時間を超えたので打ち切った
+    Timeout,
+}
+
+impl RunOutcome {
+    /// CSV に書く名前
+    fn name(self) -> &'static str {
+        match self {
+            Self::Cleared => "cleared",
+            Self::GameOver => "game_over",
+            Self::Timeout => "timeout",
+        }
+    }
+}
+
+/// 1プレイ分の統計
+#[derive(Serialize, Clone, Debug)]
+struct RunStats {
+    /// 何回目のプレイか（0から）
+    run: usize,
+    /// プレイのシード
+    seed: u64,
+    /// 結果
+    outcome: RunOutcome,
+    /// 最後に遊んだステージ（1から）
+    stage: usize,
+    /// 生存時間（秒）
+    survival_secs: f32,
+    /// 撃破数
+    kills: u32,
+    /// 得点
+    points: u32,
+    /// 命中率（0.0 ~ 1.0）
+    accuracy: f32,
+    /// 敵との接触で受けたダメージ
+    damage_collision: u32,
+    /// 敵の弾で受けたダメージ
+    damage_enemy_bullet: u32,
+    /// 敵の取り逃がしで受けたダメージ
+    damage_escape: u32,
+}
+
+/// CSV の見出し行（RunStats のフィールドと同じ順）
+const CSV_HEADER: &str = "run,seed,outcome,stage,survival_secs,kills,points,accuracy,damage_collision,damage_enemy_bullet,damage_escape";
+
+impl RunStats {
+    /// CSV の1行にする
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{:.3},{},{},{:.4},{},{},{}",
+            self.run,
+            self.seed,
+            self.outcome.name(),
+            self.stage,
+            self.survival_secs,
+            self.kills,
+            self.points,
+            self.accuracy,
+            self.damage_collision,
+            self.damage_enemy_bullet,
+            self.damage_escape
+        )
+    }
+}
+
+/// 全プレイの集計
+#[derive(Serialize, Debug)]
+struct StatsSummary {
+    /// プレイ回数
+    runs: usize,
+    /// クリアした回数
+    clears: usize,
+    /// クリア率（0.0 ~ 1.0）
+    clear_rate: f32,
+    /// 平均生存時間（秒）
+    mean_survival_secs: f32,
+    /// 平均撃破数
+    mean_kills: f32,
+    /// 敵との接触で受けたダメージの合計
+    damage_collision: u32,
+    /// 敵の弾で受けたダメージの合計
+    damage_enemy_bullet: u32,
+    /// 敵の取り逃がしで受けたダメージの合計
+    damage_escape: u32,
+}
+
+impl StatsSummary {
+    /// 各プレイの統計から集計する
+    fn new(runs: &[RunStats]) -> Self {
+        let count = runs.len().max(1) as f32;
+        let clears = runs
+            .iter()
+            .filter(|run| run.outcome == RunOutcome::Cleared)
+            .count();
+        Self {
+            runs: runs.len(),
+            clears,
+            clear_rate: clears as f32 / count,
+            mean_survival_secs: runs.iter().map(|run| run.survival_secs).sum::<f32>() / count,
+            mean_kills: runs.iter().map(|run| run.kills as f32).sum::<f32>() / count,
+            damage_collision: runs.iter().map(|run| run.damage_collision).sum(),
+            damage_enemy_bullet: runs.iter().map(|run| run.damage_enemy_bullet).sum(),
+            damage_escape: runs.iter().map(|run| run.damage_escape).sum(),
+        }
+    }
+}
+
+/// JSON に書く内容
+#[derive(Serialize)]
+struct StatsFile<'a> {
+    /// 全プレイの集計
+    summary: StatsSummary,
+    /// 各プレイの統計
+    runs: &'a [RunStats],
+}
+
+/// 統計を集計するリソース
+#[derive(Resource, Default)]
+struct SimulationStats {
+    /// 終わったプレイの統計
+    runs: Vec<RunStats>,
+    /// 今のプレイで原因ごとに受けたダメージ
+    damage: HashMap<DamageSource, u32>,
+}
+
+/// ボットの状態を保持するリソース
+#[derive(Resource)]
+struct Bot {
+    /// ランダムなボットの乱数（プレイのシードから作る）
+    rng: Xoshiro256PlusPlus,
+    /// 直前のティックで撃つ操作を押していたか（プレイヤーごと）
+    firing: [bool; MAX_PLAYERS],
+    /// ランダムなボットのスティックの向き（プレイヤーごと）
+    stick: [Vec2; MAX_PLAYERS],
+    /// ランダムなボットが向きを変えるまでのティック数（プレイヤーごと）
+    hold: [u32; MAX_PLAYERS],
+}
+
+impl Default for Bot {
+    fn default() -> Self {
+        Self {
+            rng: Xoshiro256PlusPlus::seed_from_u64(0),
+            firing: [false; MAX_PLAYERS],
+            stick: [Vec2::ZERO; MAX_PLAYERS],
+            hold: [0; MAX_PLAYERS],
+        }
+    }
+}
+
+/// 次のプレイを始めるシステム（ステージ定義の読み込みを待ってから）
+fn start_run(
+    options: Res<SimulationOptions>,
+    mut campaign: ResMut<Campaign>,
+    mut player_count: ResMut<PlayerCount>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    campaign.current = 0;
+    player_count.0 = options.players;
+    next_state.set(GameState::StageIntro);
+}
+
+/// プレイの開始時に、プレイごとのシードとボットの状態を決めるシステム
+fn seed_run(
+    options: Res<SimulationOptions>,
+    stats: Res<SimulationStats>,
+    mut game_rng: ResMut<GameRng>,
+    mut bot: ResMut<Bot>,
+) {
+    let seed = options.seed.wrapping_add(stats.runs.len() as u64);
+    game_rng.set_run_seed(seed);
+    *bot = Bot {
+        rng: Xoshiro256PlusPlus::seed_from_u64(seed),
+        ..default()
+    };
+}
+
+/// ステージ開始前の画面をすぐに抜けるシステム
+fn skip_stage_intro(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Game);
+}
+
+/// ステージクリア後の結果画面をすぐに抜けて、次のステージへ進むシステム
+fn skip_stage_result(mut campaign: ResMut<Campaign>, mut next_state: ResMut<NextState<GameState>>) {
+    campaign.current += 1;
+    next_state.set(GameState::StageIntro);
+}
+
+/// 受けたダメージを原因ごとに数えるシステム
+fn count_damage(mut messages: MessageReader<PlayerDamaged>, mut stats: ResMut<SimulationStats>) {
+    for message in messages.read() {
+        *stats.damage.entry(message.source).or_default() += 1;
+    }
+}
+
+/// 制限時間を超えたプレイを打ち切るシステム
+fn check_time_limit(
+    options: Res<SimulationOptions>,
+    score: Res<Score>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if score.time >= options.max_secs {
+        next_state.set(GameState::Title);
+    }
+}
+
+/// 結果画面からタイトルへ戻り、次のプレイを始めるシステム
+fn return_to_title(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Title);
+}
+
+/// プレイの統計を記録し、全プレイが終わったら統計を出力して終了するシステム
+fn record_run(
+    In(outcome): In<RunOutcome>,
+    options: Res<SimulationOptions>,
+    mut stats: ResMut<SimulationStats>,
+    score: Res<Score>,
+    campaign: Res<Campaign>,
+    game_rng: Res<GameRng>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    let damage = std::mem::take(&mut stats.damage);
+    let run = RunStats {
+        run: stats.runs.len(),
+        seed: game_rng.seed(),
+        outcome,
+        stage: campaign.current + 1,
+        survival_secs: score.time,
+        kills: score.kills,
+        points: score.points,
+        accuracy: score.accuracy(),
+        damage_collision: damage.get(&DamageSource::Collision).copied().unwrap_or(0),
+        damage_enemy_bullet: damage.get(&DamageSource::EnemyBullet).copied().unwrap_or(0),
+        damage_escape: damage.get(&DamageSource::Escape).copied().unwrap_or(0),
+    };
+    info!(
+        "run {} finished: {} at stage {} after {:.1}s",
+        run.run,
+        run.outcome.name(),
+        run.stage,
+        run.survival_secs
+    );
+    stats.runs.push(run);
+
+    if stats.runs.len() < options.runs {
+        return;
+    }
+
+    let summary = StatsSummary::new(&stats.runs);
+    info!(
+        "{} runs: clear rate {:.1}%, mean survival {:.1}s, mean kills {:.1}",
+        summary.runs,
+        summary.clear_rate * 100.0,
+        summary.mean_survival_secs,
+        summary.mean_kills
+    );
+    let contents = match options.format {
+        StatsFormat::Csv => std::iter::once(CSV_HEADER.to_string())
+            .chain(stats.runs.iter().map(RunStats::csv_row))
+            .map(|line| line + "\n")
+            .collect(),
+        StatsFormat::Json => {
+            let file = StatsFile {
+                summary,
+                runs: &stats.runs,
+            };
+            match serde_json::to_string_pretty(&file) {
+                Ok(json) => json + "\n",
+                Err(err) => {
+                    error!("failed to serialize stats: {}", err);
+                    exit.write(AppExit::error());
+                    return;
+                }
+            }
+        }
+    };
+    match &options.out {
+        Some(path) => {
+            if let Err(err) = std::fs::write(path, contents) {
+                error!("failed to write stats to {}: {}", path.display(), err);
+                exit.write(AppExit::error());
+                return;
+            }
+            info!("stats written to {}", path.display());
+        }
+        None => print!("{contents}"),
+    }
+    exit.write(AppExit::Success);
+}
+
+/// ボットの操作をティックの操作として流し込むシステム
+fn drive_bot(
+    options: Res<SimulationOptions>,
+    mut bot: ResMut<Bot>,
+    mut actions: ResMut<FixedActionState>,
+    player_query: Query<(&Transform, &Player)>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+    boss_query: Query<&Transform, With<Boss>>,
+    bullet_query: Query<&Transform, (With<EnemyBullet>, Without<Pooled>)>,
+) {
+    let bot = &mut *bot;
+    for (transform, player) in &player_query {
+        let index = player.index;
+        let position = transform.translation.truncate();
+        let (stick, fire) = match options.bot {
+            BotKind::Scripted => scripted_input(position, &enemy_query, &boss_query, &bullet_query),
+            BotKind::Random => {
+                // 一定のティックごとに向きを変える
+                if bot.hold[index] == 0 {
+                    bot.hold[index] = RANDOM_BOT_HOLD_TICKS;
+                    bot.stick[index] = Vec2::new(
+                        bot.rng.random_range(-1.0..=1.0),
+                        bot.rng.random_range(-1.0..=1.0),
+                    );
+                }
+                bot.hold[index] -= 1;
+                (
+                    bot.stick[index],
+                    bot.rng.random_bool(RANDOM_BOT_FIRE_CHANCE),
+                )
+            }
+        };
+
+        // 撃つ操作は押した瞬間だけ効くため、押し続けずに1ティックおきに押し直す
+        let fire = fire && !bot.firing[index];
+        bot.firing[index] = fire;
+        let pressed: &[Action] = if fire { &[Action::Fire] } else { &[] };
+        actions.replay(index, TickInput::new(pressed, pressed, stick));
+    }
+}
+
+/// 一番下の敵（いなければボス）の真下へ動いて撃ち、真上から近づく敵の弾を避ける操作を決める
+fn scripted_input(
+    position: Vec2,
+    enemy_query: &Query<&Transform, With<Enemy>>,
+    boss_query: &Query<&Transform, With<Boss>>,
+    bullet_query: &Query<&Transform, (With<EnemyBullet>, Without<Pooled>)>,
+) -> (Vec2, bool) {
+    // 近づいてくる敵の弾があれば、弾から離れる向きへ逃げる
+    let threat = bullet_query
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .filter(|bullet| {
+            let offset = *bullet - position;
+            offset.y > 0.0 && offset.y < BOT_DODGE_RANGE && offset.x.abs() < BOT_DODGE_WIDTH
+        })
+        .min_by(|a, b| a.y.total_cmp(&b.y));
+    if let Some(bullet) = threat {
+        let away = if bullet.x > position.x { -1.0 } else { 1.0 };
+        return (Vec2::new(away, 0.0), false);
+    }
+
+    let target = enemy_query
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .min_by(|a, b| a.y.total_cmp(&b.y))
+        .or_else(|| {
+            boss_query
+                .iter()
+                .map(|transform| transform.translation.truncate())
+                .next()
+        });
+    let Some(target) = target else {
+        return (Vec2::ZERO, false);
+    };
+    let dx = target.x - position.x;
+    let stick = Vec2::new((dx / BOT_STEER_DISTANCE).clamp(-1.0, 1.0), 0.0);
+    (stick, dx.abs() < BOT_AIM_TOLERANCE)
+}
@@ -1,8 +1,14 @@
-use bevy::prelude::*;
 use crate::font::DefaultFont;
 use crate::font::setup_font;
+use crate::plugins::campaign::Campaign;
+use crate::plugins::controls::ControlsMenuState;
+use crate::plugins::game::{MAX_PLAYERS, PlayerCount};
+use crate::plugins::highscore::{HighScores, high_score_table};
+use crate::plugins::input::{Action, ActionState};
+use crate::plugins::locale::{LocalizedText, Strings};
+use crate::plugins::settings::SettingsMenuState;
 use crate::state::GameState;
-use crate::DespawnOnExit;
+use bevy::prelude::*;
 
 /// タイトル画面のプラグイン
 pub struct TitlePlugin;
@@ -13,17 +19,135 @@ impl Plugin for TitlePlugin {
             OnEnter(GameState::Title),
             (setup_camera, (setup_font, setup_ui).chain()),
         );
-        app.add_systems(Update, title_update.run_if(in_state(GameState::Title)));
+        app.add_systems(
+            Update,
+            (
+                title_update.run_if(
+                    in_state(ControlsMenuState::Closed).and(in_state(SettingsMenuState::Closed)),
+                ),
+                (
+                    update_stage_select_ui,
+                    update_player_count_ui,
+                    update_high_score_table_ui,
+                ),
+            )
+                .chain()
+                .run_if(in_state(GameState::Title)),
+        );
     }
 }
 
-/// タイトル画面の更新処理（Enterキーで遷移）
+/// ステージ選択のUI用マーカーコンポーネント
+#[derive(Component)]
+struct StageSelectText;
+
+/// 人数表示のUI用マーカーコンポーネント
+#[derive(Component)]
+struct PlayerCountText;
+
+/// ハイスコア表のUI用マーカーコンポーネント
+#[derive(Component)]
+struct HighScoreTableText;
+
+/// タイトル画面の更新処理（左右でステージ選択、Tab・Selectで人数切り替え、C・Oで操作設定・設定、決定で遷移）
 fn title_update(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
+    mut campaign: ResMut<Campaign>,
+    mut player_count: ResMut<PlayerCount>,
     mut next_state: ResMut<NextState<GameState>>,
+    mut next_controls: ResMut<NextState<ControlsMenuState>>,
+    mut next_settings: ResMut<NextState<SettingsMenuState>>,
+) {
+    if actions.any_just_pressed(Action::Controls) {
+        next_controls.set(ControlsMenuState::Open);
+        return;
+    }
+    if actions.any_just_pressed(Action::Settings) {
+        next_settings.set(SettingsMenuState::Open);
+        return;
+    }
+
+    // 1人プレイと2人プレイを切り替える
+    if actions.any_just_pressed(Action::SwitchPlayers) {
+        player_count.0 = player_count.0 % MAX_PLAYERS + 1;
+    }
+
+    // 解放済みのステージの中から選ぶ
+    let last = campaign.unlocked() - 1;
+    if actions.any_just_pressed(Action::MenuLeft) && campaign.current > 0 {
+        campaign.current -= 1;
+    } else if actions.any_just_pressed(Action::MenuRight) && campaign.current < last {
+        campaign.current += 1;
+    }
+    if campaign.current > last {
+        campaign.current = last;
+    }
+
+    if actions.any_just_pressed(Action::Confirm) {
+        next_state.set(GameState::StageIntro);
+    }
+}
+
+/// ステージ選択の表示
+fn stage_select_label(campaign: &Campaign) -> LocalizedText {
+    let left = if campaign.current > 0 { "<" } else { " " };
+    let right = if campaign.current + 1 < campaign.unlocked() {
+        ">"
+    } else {
+        " "
+    };
+    LocalizedText::new("title.stage_select")
+        .with_arg("left", left)
+        .with_arg("stage", campaign.current + 1)
+        .with_arg("right", right)
+}
+
+/// 人数の表示
+fn player_count_label(player_count: &PlayerCount) -> LocalizedText {
+    if player_count.0 > 1 {
+        LocalizedText::new("title.players_many").with_arg("count", player_count.0)
+    } else {
+        LocalizedText::new("title.players_one")
+    }
+}
+
+/// 人数の表示を更新するシステム
+fn update_player_count_ui(
+    player_count: Res<PlayerCount>,
+    mut query: Query<&mut LocalizedText, With<PlayerCountText>>,
+) {
+    if !player_count.is_changed() {
+        return;
+    }
+    for mut text in &mut query {
+        *text = player_count_label(&player_count);
+    }
+}
+
+/// ステージ選択の表示を更新するシステム
+fn update_stage_select_ui(
+    campaign: Res<Campaign>,
+    mut query: Query<&mut LocalizedText, With<StageSelectText>>,
+) {
+    if !campaign.is_changed() {
+        return;
+    }
+    for mut text in &mut query {
+        *text = stage_select_label(&campaign);
+    }
+}
+
+/// 言語の切り替えや文字列表の読み込みに合わせて、ハイスコア表の表示を更新するシステム
+fn update_high_score_table_ui(
+    strings: Res<Strings>,
+    high_scores: Res<HighScores>,
+    mut query: Query<&mut Text, With<HighScoreTableText>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Enter) {
-        next_state.set(GameState::Game);
+    if !strings.is_changed() {
+        return;
+    }
+    for mut text in &mut query {
+        text.0 = high_score_table(&high_scores, &strings);
     }
 }
 
@@ -33,7 +157,14 @@ fn setup_camera(mut commands: Commands) {
 }
 
 /// UIのセットアップ
-fn setup_ui(mut commands: Commands, asset: Res<DefaultFont>) {
+fn setup_ui(
+    mut commands: Commands,
+    asset: Res<DefaultFont>,
+    high_scores: Res<HighScores>,
+    campaign: Res<Campaign>,
+    player_count: Res<PlayerCount>,
+    strings: Res<Strings>,
+) {
     commands
         .spawn((
             Node {
@@ -49,7 +180,7 @@ fn setup_ui(mut commands: Commands, asset: Res<DefaultFont>) {
         .with_children(|parent| {
             // タイトルテキスト
             parent.spawn((
-                Text::new("SPACE BATTLE"),
+                LocalizedText::new("title.name"),
                 TextFont {
                     font: asset.font.clone(),
                     font_size: 80.0,
@@ -62,15 +193,91 @@ fn setup_ui(mut commands: Commands, asset: Res<DefaultFont>) {
                 },
             ));
 
+            // ステージ選択
+            parent.spawn((
+                stage_select_label(&campaign),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 40.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.4, 0.8, 1.0)),
+                Node {
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    ..default()
+                },
+                StageSelectText,
+            ));
+
+            // 人数選択
+            parent.spawn((
+                player_count_label(&player_count),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                Node {
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    ..default()
+                },
+                PlayerCountText,
+            ));
+
             // 説明テキスト
             parent.spawn((
-                Text::new("Press Enter to Start"),
+                LocalizedText::new("title.start"),
                 TextFont {
                     font: asset.font.clone(),
                     font_size: 40.0,
                     ..default()
                 },
                 TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(8.0)),
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                LocalizedText::new("title.menus"),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                Node {
+                    margin: UiRect::bottom(Val::Px(40.0)),
+                    ..default()
+                },
+            ));
+
+            // ハイスコア表
+            parent.spawn((
+                LocalizedText::new("title.high_scores"),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 28.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.85, 0.0)),
+                Node {
+                    margin: UiRect::bottom(Val::Px(8.0)),
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                Text::new(high_score_table(&high_scores, &strings)),
+                TextFont {
+                    font: asset.font.clone(),
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                HighScoreTableText,
             ));
         });
 }
@@ -6,8 +6,12 @@ pub enum GameState {
     #[default]
     /// タイトル画面
     Title,
+    /// ステージ開始前の画面
+    StageIntro,
     /// ゲーム画面
     Game,
+    /// ステージクリア後の結果画面
+    StageResult,
     /// ゲームオーバー画面
     GameOver,
     /// ゲームクリア画面
@@ -0,0 +1,106 @@
+/// ユーザーデータの保存先と読み書き
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 保存先ディレクトリ名
+const APP_DIR_NAME: &str = "space-battle";
+/// 保存先を上書きする環境変数（テストや持ち運び用）
+const DATA_DIR_ENV: &str = "SPACE_BATTLE_DATA_DIR";
+/// 設定ファイルの保存先を上書きする環境変数（テストや持ち運び用）
+const CONFIG_DIR_ENV: &str = "SPACE_BATTLE_CONFIG_DIR";
+
+/// ユーザーデータの保存ディレクトリを返す（OSごとの慣習に従う）
+///
+/// ホームディレクトリが分からない環境では None を返し、保存は行わない
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV) {
+        return Some(PathBuf::from(dir));
+    }
+
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })
+    };
+
+    base.map(|dir| dir.join(APP_DIR_NAME))
+}
+
+/// 設定ファイルの保存ディレクトリを返す（OSごとの慣習に従う）
+///
+/// ホームディレクトリが分からない環境では None を返し、保存は行わない
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(CONFIG_DIR_ENV) {
+        return Some(PathBuf::from(dir));
+    }
+
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Preferences"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+
+    base.map(|dir| dir.join(APP_DIR_NAME))
+}
+
+/// ファイルを読み込む。存在しない場合は Ok(None) を返す
+pub fn read_file(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// 一時ファイルに書いてからリネームすることで、書き込み途中で壊れないように保存する
+pub fn write_file_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, contents)?;
+    fs::rename(&tmp_path, path)
+}
+
+/// 読み込めなかったファイルを `.bak` として退避する（上書きで失わないように）
+pub fn backup_file(path: &Path) -> io::Result<PathBuf> {
+    let backup_path = path.with_extension("bak");
+    fs::rename(path, &backup_path)?;
+    Ok(backup_path)
+}
+
+/// 現在の日付（UTC）を "YYYY-MM-DD" 形式で返す
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// 1970-01-01 からの日数を年月日に変換する（グレゴリオ暦）
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
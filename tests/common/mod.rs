@@ -0,0 +1,322 @@
+// テストのバイナリごとに使う関数が違うため、使われない関数の警告は出さない
+#![allow(dead_code)]
+
+/// 結合テスト用のハーネス
+///
+/// ウィンドウなしのゲームのアプリを作り、キー入力の注入・ティック単位の時間送り・
+/// 状態の読み出しを行う。1回の更新で1ティック（1/60秒）進む
+use bevy::input::ButtonState;
+use bevy::input::keyboard::{Key, KeyboardInput, NativeKey};
+use bevy::prelude::*;
+use space_battle::plugins::campaign::Campaign;
+use space_battle::plugins::game::{
+    ActiveStage, BULLET_SIZE, Bullet, BulletStock, Enemy, EnemyArchetype, EnemyCount,
+    FIXED_TIMESTEP_HZ, GameElapsedTime, GameRng, HP, PauseState, Player, PowerUpKind, Score,
+    StageDefinition, bullet_collider, spawn_enemy, spawn_power_up, stages_loaded,
+};
+use space_battle::plugins::replay::ReplayPlugin;
+use space_battle::plugins::simulation::headless_app;
+use space_battle::state::GameState;
+
+/// ステージ定義の読み込みを待つ最大の更新回数
+const MAX_LOAD_UPDATES: usize = 5_000;
+/// 敵を出すときの基本サイズ
+const ENEMY_SIDE: f32 = 50.0;
+/// start_stage で使う乱数のシード（倒した敵が落とすアイテムを毎回同じにする）
+const STAGE_SEED: u64 = 1;
+
+/// テスト用のゲームのアプリ
+pub struct TestApp {
+    app: App,
+}
+
+impl TestApp {
+    /// ステージ定義の読み込みを待ち、タイトル画面の状態で作る
+    pub fn new() -> Self {
+        Self::load(headless_app())
+    }
+
+    /// リプレイの記録と再生を組み込んで作る
+    pub fn with_replays() -> Self {
+        let mut app = headless_app();
+        app.add_plugins(ReplayPlugin);
+        Self::load(app)
+    }
+
+    /// ステージ定義の読み込みを待ち、タイトル画面の状態にする
+    fn load(mut app: App) -> Self {
+        app.finish();
+        app.cleanup();
+
+        for _ in 0..MAX_LOAD_UPDATES {
+            app.update();
+            if app.world_mut().run_system_cached(stages_loaded).unwrap() {
+                // 読み込み完了の通知を反映させる
+                app.update();
+                return Self { app };
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        panic!("stage definitions did not load");
+    }
+
+    /// stage 番目のステージを始める
+    ///
+    /// 決まった状況だけを試せるよう、敵のランダムな出現と編隊は止め、乱数のシードも固定する
+    pub fn start_stage(&mut self, stage: usize) {
+        self.app.insert_resource(GameRng::new(Some(STAGE_SEED)));
+        self.select_stage(stage);
+
+        let mut active_stage = self.app.world_mut().resource_mut::<ActiveStage>();
+        active_stage.0.random_spawns = false;
+        active_stage.0.waves.clear();
+
+        self.enter_game();
+    }
+
+    /// stage 番目のステージを、定義どおり（敵のランダムな出現と編隊あり）にシード seed で始める
+    pub fn start_seeded_stage(&mut self, stage: usize, seed: u64) {
+        self.app.insert_resource(GameRng::new(Some(seed)));
+        self.select_stage(stage);
+        self.enter_game();
+    }
+
+    /// stage 番目のステージを選び、その定義を反映させる
+    fn select_stage(&mut self, stage: usize) {
+        self.app.world_mut().resource_mut::<Campaign>().current = stage;
+        self.app.update();
+    }
+
+    /// ゲーム画面に移る
+    pub fn enter_game(&mut self) {
+        self.set_state(GameState::Game);
+        assert_eq!(self.state(), GameState::Game);
+    }
+
+    /// ゲームの状態を切り替えて1回更新する
+    pub fn set_state(&mut self, state: GameState) {
+        self.app
+            .world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(state);
+        self.app.update();
+    }
+
+    /// 現在のステージの定義
+    pub fn stage(&self) -> StageDefinition {
+        self.app.world().resource::<ActiveStage>().0.clone()
+    }
+
+    /// 現在のステージの定義を差し替える（ステージを始めた直後に使う）
+    pub fn set_stage(&mut self, stage: StageDefinition) {
+        self.app.world_mut().resource_mut::<ActiveStage>().0 = stage;
+    }
+
+    /// ticks 回だけティックを進める
+    pub fn step(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.app.update();
+        }
+    }
+
+    /// secs 秒分のティックを進める
+    pub fn step_secs(&mut self, secs: f32) {
+        self.step((secs * FIXED_TIMESTEP_HZ as f32).ceil() as usize);
+    }
+
+    /// 条件を満たすまでティックを進める（max_ticks 回進めても満たさなければ失敗）
+    pub fn step_until(&mut self, max_ticks: usize, mut condition: impl FnMut(&mut Self) -> bool) {
+        for _ in 0..max_ticks {
+            if condition(self) {
+                return;
+            }
+            self.app.update();
+        }
+        assert!(
+            condition(self),
+            "condition not met within {max_ticks} ticks"
+        );
+    }
+
+    /// キーを押す（離すまで押したまま）
+    pub fn press(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Pressed);
+    }
+
+    /// キーを離す
+    pub fn release(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Released);
+    }
+
+    /// キーを1ティックだけ押して離す
+    pub fn tap(&mut self, key: KeyCode) {
+        self.press(key);
+        self.step(1);
+        self.release(key);
+        self.step(1);
+    }
+
+    /// キーボードの入力を送る（ButtonInput<KeyCode> に反映される）
+    fn send_key(&mut self, key_code: KeyCode, state: ButtonState) {
+        self.app.world_mut().write_message(KeyboardInput {
+            key_code,
+            logical_key: Key::Unidentified(NativeKey::Unidentified),
+            state,
+            text: None,
+            repeat: false,
+            window: Entity::PLACEHOLDER,
+        });
+    }
+
+    /// 現在のゲームの状態
+    pub fn state(&self) -> GameState {
+        *self.app.world().resource::<State<GameState>>().get()
+    }
+
+    /// 現在のポーズ状態（ゲーム画面でなければ None）
+    pub fn pause_state(&self) -> Option<PauseState> {
+        self.app
+            .world()
+            .get_resource::<State<PauseState>>()
+            .map(|state| *state.get())
+    }
+
+    /// ゲーム内の経過時間（秒）
+    pub fn elapsed_secs(&self) -> f32 {
+        self.app.world().resource::<GameElapsedTime>().0
+    }
+
+    /// 被弾した後の無敵時間（秒）
+    pub fn invulnerability_secs(&self) -> f32 {
+        self.app
+            .world()
+            .resource::<ActiveStage>()
+            .0
+            .invulnerability_secs
+    }
+
+    /// 現在のスコア
+    pub fn score(&self) -> Score {
+        self.app.world().resource::<Score>().clone()
+    }
+
+    /// 残り討伐数
+    pub fn enemy_count(&self) -> u32 {
+        self.app.world().resource::<EnemyCount>().0
+    }
+
+    /// 残り討伐数を変える
+    pub fn set_enemy_count(&mut self, count: u32) {
+        self.app.world_mut().resource_mut::<EnemyCount>().0 = count;
+    }
+
+    /// player 番目のプレイヤーの HP（倒されていれば None）
+    pub fn player_hp(&mut self, player: usize) -> Option<u32> {
+        self.find_player(player, |hp: &HP| hp.0)
+    }
+
+    /// player 番目のプレイヤーの残弾数（倒されていれば None）
+    pub fn bullet_stock(&mut self, player: usize) -> Option<u32> {
+        self.find_player(player, |stock: &BulletStock| stock.current)
+    }
+
+    /// player 番目のプレイヤーの位置
+    pub fn player_position(&mut self, player: usize) -> Vec2 {
+        self.find_player(player, |transform: &Transform| {
+            transform.translation.truncate()
+        })
+        .expect("player not found")
+    }
+
+    /// player 番目のプレイヤーのコンポーネント T から値を取り出す
+    fn find_player<T: Component, R>(&mut self, player: usize, f: impl Fn(&T) -> R) -> Option<R> {
+        let mut query = self.app.world_mut().query::<(&Player, &T)>();
+        query
+            .iter(self.app.world())
+            .find(|(p, _)| p.index == player)
+            .map(|(_, component)| f(component))
+    }
+
+    /// 画面上の敵の数
+    pub fn enemies(&mut self) -> usize {
+        self.count_of::<Enemy>()
+    }
+
+    /// コンポーネント T を持つエンティティの数
+    pub fn count_of<T: Component>(&mut self) -> usize {
+        let mut query = self.app.world_mut().query_filtered::<(), With<T>>();
+        query.iter(self.app.world()).count()
+    }
+
+    /// 位置を指定して基本の敵（Grunt）を出す
+    pub fn spawn_enemy(&mut self, position: Vec2) {
+        let world = self.app.world_mut();
+        spawn_enemy(
+            &mut world.commands(),
+            EnemyArchetype::Grunt,
+            position,
+            ENEMY_SIDE,
+            1.0,
+            None,
+        );
+        world.flush();
+    }
+
+    /// 位置を指定してアイテムを出す
+    pub fn spawn_power_up(&mut self, kind: PowerUpKind, position: Vec2) {
+        let world = self.app.world_mut();
+        spawn_power_up(&mut world.commands(), kind, position);
+        world.flush();
+    }
+
+    /// player 番目のプレイヤーが効果 T を持っているかどうか
+    pub fn has_effect<T: Component>(&mut self, player: usize) -> bool {
+        self.find_player(player, |_: &T| ()).is_some()
+    }
+
+    /// 位置を指定して、止まったままの1Pの弾を出す
+    pub fn spawn_bullet(&mut self, position: Vec2, damage: u32) {
+        self.spawn_moving_bullet(position, Vec2::ZERO, damage);
+    }
+
+    /// 位置と速度（ピクセル/秒）を指定して1Pの弾を出す
+    pub fn spawn_moving_bullet(&mut self, position: Vec2, velocity: Vec2, damage: u32) {
+        self.app.world_mut().spawn((
+            Sprite::from_color(Color::WHITE, BULLET_SIZE),
+            Transform::from_translation(position.extend(0.0)),
+            Bullet {
+                velocity,
+                damage,
+                owner: 0,
+            },
+            bullet_collider(BULLET_SIZE),
+            DespawnOnExit(GameState::Game),
+        ));
+    }
+
+    /// コンポーネント T を持つすべてのエンティティの位置（スポーン順）
+    pub fn positions_of<T: Component>(&mut self) -> Vec<Vec2> {
+        let mut query = self
+            .app
+            .world_mut()
+            .query_filtered::<(Entity, &Transform), With<T>>();
+        let mut positions: Vec<(Entity, Vec2)> = query
+            .iter(self.app.world())
+            .map(|(entity, transform)| (entity, transform.translation.truncate()))
+            .collect();
+        positions.sort_by_key(|(entity, _)| *entity);
+        positions
+            .into_iter()
+            .map(|(_, position)| position)
+            .collect()
+    }
+
+    /// コンポーネント T を持つ最初のエンティティの位置
+    pub fn position_of<T: Component>(&mut self) -> Option<Vec2> {
+        let mut query = self.app.world_mut().query_filtered::<&Transform, With<T>>();
+        query
+            .iter(self.app.world())
+            .next()
+            .map(|transform| transform.translation.truncate())
+    }
+}
@@ -0,0 +1,419 @@
+/// ゲームプレイの結合テスト
+mod common;
+
+use bevy::ecs::schedule::{LogLevel, ScheduleBuildSettings};
+use bevy::prelude::*;
+use common::TestApp;
+use space_battle::plugins::campaign::CAMPAIGN_STAGES;
+use space_battle::plugins::game::{
+    Boss, Enemy, EnemyBullet, FIXED_TIMESTEP_HZ, PauseState, Piercing, Player, PowerUpKind, Shield,
+};
+use space_battle::plugins::simulation::headless_app;
+use space_battle::state::GameState;
+
+/// 弾を撃つキー（1Pの既定の割り当て）
+const FIRE_KEY: KeyCode = KeyCode::Enter;
+/// 武器を切り替えるキー（1Pの既定の割り当て）
+const CYCLE_WEAPON_KEY: KeyCode = KeyCode::KeyQ;
+/// ポーズのキー（1Pの既定の割り当て）
+const PAUSE_KEY: KeyCode = KeyCode::Escape;
+/// プレイヤーの初期HP
+const PLAYER_HP: u32 = 3;
+/// 弾の最大ストック数
+const MAX_BULLET_STOCK: u32 = 3;
+/// 1ティックで敵を飛び越えるほど速い弾の速度（ピクセル/秒）
+const FAST_BULLET_SPEED: f32 = 12_000.0;
+/// 画面の中心から左右の端までの距離（ウィンドウなしのときの既定値）
+const PLAYFIELD_HALF_WIDTH: f32 = 400.0;
+/// 基本の敵（Grunt）の大きさの半分（ステージの既定の敵のサイズ）
+const ENEMY_HALF_SIDE: f32 = 25.0;
+/// プレイヤーから離れた、画面の下端より下の位置（置いた敵はすぐに取り逃がしになる）
+const ESCAPE_POSITION: Vec2 = Vec2::new(300.0, -600.0);
+
+/// 展開を比べるプレイの乱数のシード
+const SCRIPTED_RUN_SEED: u64 = 42;
+/// 展開を比べるプレイのティック数（10秒）
+const SCRIPTED_RUN_TICKS: usize = 600;
+/// 展開を比べるプレイで左右の移動を切り替える間隔（ティック）
+const TURN_TICKS: usize = 90;
+/// 展開を比べるプレイで弾を撃つ間隔（ティック）
+const FIRE_TICKS: usize = 12;
+/// 左右に移動するキー（1Pの既定の割り当て）
+const MOVE_KEYS: [KeyCode; 2] = [KeyCode::KeyA, KeyCode::KeyD];
+
+/// 最初のステージを始めた状態のアプリ
+fn start_game() -> TestApp {
+    let mut app = TestApp::new();
+    app.start_stage(0);
+    app
+}
+
+#[test]
+fn firing_uses_stock_and_recharges_over_time() {
+    let mut app = start_game();
+    assert_eq!(app.bullet_stock(0), Some(MAX_BULLET_STOCK));
+
+    app.tap(FIRE_KEY);
+    assert_eq!(app.bullet_stock(0), Some(MAX_BULLET_STOCK - 1));
+
+    // 1発分のチャージ時間（1秒）で最大まで戻る
+    app.step_secs(1.1);
+    assert_eq!(app.bullet_stock(0), Some(MAX_BULLET_STOCK));
+}
+
+#[test]
+fn stock_does_not_charge_past_maximum() {
+    let mut app = start_game();
+    app.step_secs(3.0);
+    assert_eq!(app.bullet_stock(0), Some(MAX_BULLET_STOCK));
+}
+
+#[test]
+fn enemy_touching_player_deals_damage_and_is_removed() {
+    let mut app = start_game();
+    let position = app.player_position(0);
+    app.spawn_enemy(position);
+
+    app.step(2);
+    assert_eq!(app.player_hp(0), Some(PLAYER_HP - 1));
+    assert_eq!(app.enemies(), 0);
+}
+
+#[test]
+fn enemy_shot_down_while_touching_player_deals_no_damage() {
+    let mut app = start_game();
+    let before = app.enemy_count();
+    let position = app.player_position(0);
+    app.spawn_enemy(position);
+    app.spawn_bullet(position, 1);
+
+    app.step(2);
+    assert_eq!(app.enemies(), 0);
+    assert_eq!(app.enemy_count(), before - 1);
+    assert_eq!(app.player_hp(0), Some(PLAYER_HP));
+}
+
+#[test]
+fn bullet_destroys_enemy_and_counts_down() {
+    let mut app = start_game();
+    let before = app.enemy_count();
+    let position = app.player_position(0) + Vec2::new(0.0, 300.0);
+    app.spawn_enemy(position);
+
+    app.tap(FIRE_KEY);
+    app.step_until(60, |app| app.enemies() == 0);
+    assert_eq!(app.enemy_count(), before - 1);
+    assert_eq!(app.player_hp(0), Some(PLAYER_HP));
+}
+
+#[test]
+fn shots_count_every_projectile() {
+    let mut app = start_game();
+    // SPREAD は1回で5発撃つ
+    app.tap(CYCLE_WEAPON_KEY);
+    app.tap(FIRE_KEY);
+    assert_eq!(app.score().shots, 5);
+}
+
+#[test]
+fn piercing_hit_counts_once_for_accuracy() {
+    let mut app = start_game();
+    // LASER は敵を貫通する
+    app.tap(CYCLE_WEAPON_KEY);
+    app.tap(CYCLE_WEAPON_KEY);
+    let player = app.player_position(0);
+    app.spawn_enemy(player + Vec2::new(0.0, 150.0));
+    app.spawn_enemy(player + Vec2::new(0.0, 250.0));
+
+    app.tap(FIRE_KEY);
+    app.step_until(60, |app| app.enemies() == 0);
+    let score = app.score();
+    assert_eq!((score.shots, score.hits), (1, 1));
+    assert_eq!(score.accuracy(), 1.0);
+}
+
+#[test]
+fn colliders_only_hit_layers_in_their_mask() {
+    let mut app = start_game();
+    // 敵どうし、プレイヤーの弾とプレイヤーは当たらない
+    let player = app.player_position(0);
+    app.spawn_enemy(player + Vec2::new(0.0, 200.0));
+    app.spawn_enemy(player + Vec2::new(0.0, 200.0));
+    app.spawn_bullet(player, 1);
+
+    app.step(2);
+    assert_eq!(app.enemies(), 2);
+    assert_eq!(app.player_hp(0), Some(PLAYER_HP));
+}
+
+#[test]
+fn fast_bullet_does_not_pass_through_enemy() {
+    let mut app = start_game();
+    let before = app.enemy_count();
+    let position = app.player_position(0) + Vec2::new(0.0, 300.0);
+    app.spawn_enemy(position);
+
+    // 1ティックで敵の大きさより長く進む弾でも、通り道にいる敵に当たる
+    let velocity = Vec2::new(0.0, FAST_BULLET_SPEED);
+    let step = velocity / FIXED_TIMESTEP_HZ as f32;
+    app.spawn_moving_bullet(position - step / 2.0, velocity, 1);
+
+    app.step(2);
+    assert_eq!(app.enemies(), 0);
+    assert_eq!(app.enemy_count(), before - 1);
+}
+
+#[test]
+fn escaping_enemy_damages_player() {
+    let mut app = start_game();
+    app.spawn_enemy(ESCAPE_POSITION);
+
+    app.step(2);
+    assert_eq!(app.enemies(), 0);
+    assert_eq!(app.player_hp(0), Some(PLAYER_HP - 1));
+    assert_eq!(app.state(), GameState::Game);
+}
+
+#[test]
+fn wave_outside_playfield_spawns_on_screen() {
+    let mut app = start_game();
+    app.set_stage(
+        ron::from_str(
+            "(random_spawns: false, enemy_total: 1, waves: [(time: 0.0, archetype: Grunt, x: 1000.0)])",
+        )
+        .unwrap(),
+    );
+
+    app.step(1);
+    let position = app.position_of::<Enemy>().expect("wave should spawn");
+    assert!(position.x + ENEMY_HALF_SIDE <= PLAYFIELD_HALF_WIDTH);
+}
+
+#[test]
+fn pause_stops_and_resumes_gameplay() {
+    let mut app = start_game();
+    app.tap(PAUSE_KEY);
+    assert_eq!(app.pause_state(), Some(PauseState::Paused));
+
+    // ポーズ中は時間が進まず、敵も動かない
+    let elapsed = app.elapsed_secs();
+    app.spawn_enemy(ESCAPE_POSITION);
+    app.step_secs(1.0);
+    assert_eq!(app.elapsed_secs(), elapsed);
+    assert_eq!(app.enemies(), 1);
+    assert_eq!(app.player_hp(0), Some(PLAYER_HP));
+
+    app.tap(PAUSE_KEY);
+    assert_eq!(app.pause_state(), Some(PauseState::Running));
+    app.step(2);
+    assert!(app.elapsed_secs() > elapsed);
+    assert_eq!(app.player_hp(0), Some(PLAYER_HP - 1));
+}
+
+#[test]
+fn invulnerability_blocks_damage_until_it_ends() {
+    let mut app = start_game();
+    let invulnerable_ticks =
+        (app.invulnerability_secs() * FIXED_TIMESTEP_HZ as f32).round() as usize;
+    // 同じティックに重なった敵では1しか減らない
+    let position = app.player_position(0);
+    app.spawn_enemy(position);
+    app.spawn_enemy(position);
+    app.step(1);
+    assert_eq!(app.player_hp(0), Some(PLAYER_HP - 1));
+
+    // 無敵時間中は取り逃がしでも減らない
+    app.spawn_enemy(ESCAPE_POSITION);
+    app.step(2);
+    assert_eq!(app.enemies(), 0);
+    assert_eq!(app.player_hp(0), Some(PLAYER_HP - 1));
+
+    // 無敵時間は被弾したティックから数えて invulnerable_ticks ティック続き、最後のティックに当たってもまだ減らない
+    app.step(invulnerable_ticks - 4);
+    ram_player(&mut app);
+    assert_eq!(app.player_hp(0), Some(PLAYER_HP - 1));
+
+    // 無敵時間が切れた次のティックからは、またダメージを受ける
+    ram_player(&mut app);
+    assert_eq!(app.player_hp(0), Some(PLAYER_HP - 2));
+}
+
+/// 1Pの位置に敵を出し、1ティック進めて当てる
+fn ram_player(app: &mut TestApp) {
+    let position = app.player_position(0);
+    app.spawn_enemy(position);
+    app.step(1);
+}
+
+#[test]
+fn losing_all_hp_is_game_over() {
+    let mut app = start_game();
+    let invulnerability_secs = app.invulnerability_secs();
+    for _ in 0..PLAYER_HP - 1 {
+        app.spawn_enemy(ESCAPE_POSITION);
+        app.step(2);
+        // 無敵時間が切れるまで待つ
+        app.step_secs(invulnerability_secs);
+    }
+    app.spawn_enemy(ESCAPE_POSITION);
+
+    app.step_until(10, |app| app.state() == GameState::GameOver);
+    assert_eq!(app.player_hp(0), None);
+}
+
+/// 残り1体の敵を倒してボス戦に入り、ボスを倒すまで進める
+fn defeat_boss(app: &mut TestApp) {
+    app.set_enemy_count(1);
+    let position = app.player_position(0) + Vec2::new(0.0, 300.0);
+    app.spawn_enemy(position);
+    app.spawn_bullet(position, 1);
+    app.step_until(10, |app| app.enemy_count() == 0);
+
+    app.step_until(10, |app| app.position_of::<Boss>().is_some());
+    app.step_until(60, |app| {
+        // ボスが残っていれば、その位置に強い弾を置く
+        if let Some(boss) = app.position_of::<Boss>() {
+            app.spawn_bullet(boss, 1_000);
+        }
+        app.state() != GameState::Game
+    });
+}
+
+#[test]
+fn killing_enemies_during_boss_fight_keeps_single_boss() {
+    let mut app = start_game();
+    app.set_enemy_count(1);
+    let position = app.player_position(0) + Vec2::new(0.0, 150.0);
+    app.spawn_enemy(position);
+    app.spawn_bullet(position, 1);
+    app.step_until(10, |app| app.position_of::<Boss>().is_some());
+
+    // ボス戦の最中に敵を倒しても、ボスが出直さない
+    app.spawn_enemy(position);
+    app.spawn_bullet(position, 1);
+    app.step(3);
+    assert_eq!(app.enemies(), 0);
+    assert_eq!(app.count_of::<Boss>(), 1);
+}
+
+#[test]
+fn defeating_boss_clears_stage() {
+    let mut app = start_game();
+    defeat_boss(&mut app);
+    assert_eq!(app.state(), GameState::StageResult);
+}
+
+#[test]
+fn defeating_final_boss_is_game_clear() {
+    let mut app = TestApp::new();
+    app.start_stage(CAMPAIGN_STAGES.len() - 1);
+    defeat_boss(&mut app);
+    assert_eq!(app.state(), GameState::GameClear);
+}
+
+/// プレイの結果（比べるための値）
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    /// 最後のゲームの状態
+    state: GameState,
+    /// 獲得点数
+    points: u32,
+    /// 撃破数
+    kills: u32,
+    /// 発射した弾の数
+    shots: u32,
+    /// 命中した弾の数
+    hits: u32,
+    /// 残り討伐数
+    enemy_count: u32,
+    /// 1PのHP
+    player_hp: Option<u32>,
+    /// プレイヤーの位置
+    players: Vec<Vec2>,
+    /// 敵の位置
+    enemies: Vec<Vec2>,
+    /// 敵の弾の位置
+    enemy_bullets: Vec<Vec2>,
+    /// 1Pにシールドの効果があったティック数
+    shield_ticks: usize,
+    /// 1Pに貫通の効果があったティック数
+    piercing_ticks: usize,
+}
+
+/// シードを固定して、弾を撃ちながら左右に往復する操作で遊んだ結果
+///
+/// 最初にシールドと貫通のアイテムを拾い、効果が途中で切れるようにする
+fn play_scripted_run() -> Outcome {
+    let mut app = TestApp::new();
+    app.start_seeded_stage(0, SCRIPTED_RUN_SEED);
+    let position = app.player_position(0);
+    app.spawn_power_up(PowerUpKind::Shield, position);
+    app.spawn_power_up(PowerUpKind::Piercing, position);
+    let mut shield_ticks = 0;
+    let mut piercing_ticks = 0;
+
+    for tick in 0..SCRIPTED_RUN_TICKS {
+        // 弾は押した瞬間に撃つため、押しては離すのを繰り返す
+        if tick % FIRE_TICKS == 0 {
+            app.press(FIRE_KEY);
+        } else if tick % FIRE_TICKS == 1 {
+            app.release(FIRE_KEY);
+        }
+        if tick % TURN_TICKS == 0 {
+            let turn = tick / TURN_TICKS;
+            app.release(MOVE_KEYS[(turn + 1) % 2]);
+            app.press(MOVE_KEYS[turn % 2]);
+        }
+        app.step(1);
+        shield_ticks += usize::from(app.has_effect::<Shield>(0));
+        piercing_ticks += usize::from(app.has_effect::<Piercing>(0));
+    }
+
+    let score = app.score();
+    Outcome {
+        state: app.state(),
+        points: score.points,
+        kills: score.kills,
+        shots: score.shots,
+        hits: score.hits,
+        enemy_count: app.enemy_count(),
+        player_hp: app.player_hp(0),
+        players: app.positions_of::<Player>(),
+        enemies: app.positions_of::<Enemy>(),
+        enemy_bullets: app.positions_of::<EnemyBullet>(),
+        shield_ticks,
+        piercing_ticks,
+    }
+}
+
+#[test]
+fn same_seed_and_inputs_give_same_outcome() {
+    let first = play_scripted_run();
+    // 途中で終わらず、敵を倒しながら遊び続けた状態を比べる
+    assert_eq!(first.state, GameState::Game);
+    assert!(first.kills > 0, "the run should shoot down enemies");
+    // 効果が切れるティックを含めて比べる
+    for ticks in [first.shield_ticks, first.piercing_ticks] {
+        assert!(
+            ticks > 0 && ticks < SCRIPTED_RUN_TICKS,
+            "the effect should expire during the run"
+        );
+    }
+    assert_eq!(first, play_scripted_run());
+}
+
+#[test]
+fn tick_systems_run_in_a_fixed_order() {
+    // 順番の決まっていないシステムが同じデータを使っていれば、スケジュールを作るときに失敗させる
+    let mut app = headless_app();
+    app.edit_schedule(FixedUpdate, |schedule| {
+        schedule.set_build_settings(ScheduleBuildSettings {
+            ambiguity_detection: LogLevel::Error,
+            ..default()
+        });
+    });
+    app.finish();
+    app.cleanup();
+    app.update();
+    app.update();
+}
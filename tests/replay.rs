@@ -0,0 +1,136 @@
+/// リプレイの記録と再生の結合テスト
+mod common;
+
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use bevy::prelude::*;
+use common::TestApp;
+use space_battle::plugins::game::Score;
+use space_battle::state::GameState;
+
+/// 保存先を上書きする環境変数
+const DATA_DIR_ENV: &str = "SPACE_BATTLE_DATA_DIR";
+/// 乱数のシード
+const SEED: u64 = 42;
+/// ゲームオーバーになるまで遊ぶ最大のティック数（2分）
+const MAX_TICKS: usize = 7_200;
+/// 左右の移動を切り替える間隔（ティック）
+const TURN_TICKS: usize = 90;
+/// 弾を撃つ間隔（ティック）
+const FIRE_TICKS: usize = 12;
+/// 弾を撃つキー（1Pの既定の割り当て）
+const FIRE_KEY: KeyCode = KeyCode::Enter;
+/// 左右に移動するキー（1Pの既定の割り当て）
+const MOVE_KEYS: [KeyCode; 2] = [KeyCode::KeyA, KeyCode::KeyD];
+/// リプレイを見るキー（1Pの既定の割り当て）
+const WATCH_REPLAY_KEY: KeyCode = KeyCode::KeyV;
+
+/// リプレイの保存先（ユーザーのデータを書き換えないよう、テスト用の一時ディレクトリにする）
+static DATA_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("replay-test");
+    // SAFETY: 各テストがアプリを作る前に1回だけ設定し、その間ほかのスレッドは環境変数を読み書きしない
+    unsafe { std::env::set_var(DATA_DIR_ENV, &dir) };
+    dir
+});
+
+/// プレイの結果（比べるための値）
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    /// 最後のゲームの状態
+    state: GameState,
+    /// 獲得点数
+    points: u32,
+    /// 撃破数
+    kills: u32,
+    /// 発射した弾の数
+    shots: u32,
+    /// 命中した弾の数
+    hits: u32,
+    /// プレイ時間（秒）
+    time: f32,
+}
+
+impl Outcome {
+    /// 現在の状態とスコアから作る
+    fn of(app: &TestApp) -> Self {
+        let Score {
+            points,
+            kills,
+            shots,
+            hits,
+            time,
+            ..
+        } = app.score();
+        Self {
+            state: app.state(),
+            points,
+            kills,
+            shots,
+            hits,
+            time,
+        }
+    }
+}
+
+/// シードを固定して、弾を撃ちながら左右に往復する操作でゲームオーバーまで遊んだアプリ
+fn record_run() -> TestApp {
+    LazyLock::force(&DATA_DIR);
+    let mut app = TestApp::with_replays();
+    app.start_seeded_stage(0, SEED);
+
+    for tick in 0..MAX_TICKS {
+        if app.state() != GameState::Game {
+            break;
+        }
+        // 弾は押した瞬間に撃つため、押しては離すのを繰り返す
+        if tick % FIRE_TICKS == 0 {
+            app.press(FIRE_KEY);
+        } else if tick % FIRE_TICKS == 1 {
+            app.release(FIRE_KEY);
+        }
+        if tick % TURN_TICKS == 0 {
+            let turn = tick / TURN_TICKS;
+            app.release(MOVE_KEYS[(turn + 1) % 2]);
+            app.press(MOVE_KEYS[turn % 2]);
+        }
+        app.step(1);
+    }
+    for key in MOVE_KEYS.into_iter().chain([FIRE_KEY]) {
+        app.release(key);
+    }
+    app.step(1);
+    assert_eq!(app.state(), GameState::GameOver);
+    app
+}
+
+/// 結果画面からリプレイの再生を始め、ステージ開始前の画面まで進める
+fn watch_replay(app: &mut TestApp) {
+    app.tap(WATCH_REPLAY_KEY);
+    assert_eq!(app.state(), GameState::StageIntro);
+}
+
+#[test]
+fn recorded_run_plays_back_to_same_outcome() {
+    let mut app = record_run();
+    let recorded = Outcome::of(&app);
+    assert!(recorded.kills > 0, "the run should shoot down enemies");
+
+    watch_replay(&mut app);
+    app.enter_game();
+    app.step_until(MAX_TICKS, |app| app.state() != GameState::Game);
+    assert_eq!(Outcome::of(&app), recorded);
+}
+
+#[test]
+fn replay_of_changed_stage_is_refused() {
+    let mut app = record_run();
+
+    watch_replay(&mut app);
+    let mut stage = app.stage();
+    stage.enemy_total += 1;
+    app.set_stage(stage);
+    app.set_state(GameState::Game);
+    app.step(1);
+    assert_eq!(app.state(), GameState::Title);
+}
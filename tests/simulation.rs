@@ -0,0 +1,18 @@
+/// シミュレーションのコマンドライン引数のテスト
+use space_battle::plugins::simulation::SimulationOptions;
+
+/// コマンドライン引数の一覧を作る
+fn args(values: &[&str]) -> Vec<String> {
+    ["space-battle", "--headless"]
+        .iter()
+        .chain(values)
+        .map(|value| value.to_string())
+        .collect()
+}
+
+#[test]
+fn zero_runs_is_rejected() {
+    assert!(SimulationOptions::from_args(&args(&["--runs", "0"])).is_err());
+    assert!(SimulationOptions::from_args(&args(&["--runs", "1"])).is_ok());
+    assert!(SimulationOptions::from_args(&args(&[])).is_ok());
+}
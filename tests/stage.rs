@@ -0,0 +1,86 @@
+/// ステージ定義の検証のテスト
+use bevy::prelude::*;
+use space_battle::plugins::campaign::CAMPAIGN_STAGES;
+use space_battle::plugins::game::StageDefinition;
+
+/// 最も小さい解像度（800x800）の画面の広さ
+const SMALLEST_PLAYFIELD: Vec2 = Vec2::splat(400.0);
+
+/// RON の文字列からステージ定義を作る
+fn parse(source: &str) -> StageDefinition {
+    ron::from_str(source).expect("stage definition should parse")
+}
+
+#[test]
+fn shipped_stages_are_valid_and_fit_smallest_playfield() {
+    for path in CAMPAIGN_STAGES {
+        let source = std::fs::read_to_string(format!("assets/{path}")).unwrap();
+        let stage = parse(&source);
+        assert_eq!(stage.validate(), Vec::<String>::new(), "{path}");
+        assert_eq!(
+            stage.layout_errors(SMALLEST_PLAYFIELD),
+            Vec::<String>::new(),
+            "{path}"
+        );
+    }
+}
+
+#[test]
+fn scripted_stage_must_reach_enemy_total() {
+    let short = parse(
+        "(enemy_total: 6, random_spawns: false, waves: [
+            (time: 1.0, archetype: Grunt, formation: Line(count: 5, spacing: 60.0)),
+        ])",
+    );
+    assert_eq!(short.validate().len(), 1);
+
+    let enough = parse(
+        "(enemy_total: 6, random_spawns: false, waves: [
+            (time: 1.0, archetype: Grunt, formation: Line(count: 5, spacing: 60.0)),
+            (time: 2.0, archetype: Tank),
+        ])",
+    );
+    assert_eq!(enough.validate(), Vec::<String>::new());
+
+    // ランダムなスポーンがあれば、ウェーブだけで届かなくてもよい
+    let random = parse(
+        "(enemy_total: 6, random_spawns: true, waves: [
+            (time: 1.0, archetype: Grunt),
+        ])",
+    );
+    assert_eq!(random.validate(), Vec::<String>::new());
+}
+
+#[test]
+fn layout_is_checked_against_playfield_size() {
+    // 端の敵（幅50）は中心から 4 * 90 + 25 = 385 まで届くので、幅800の画面には収まり、幅600には収まらない
+    let stage = parse(
+        "(enemy_size: (initial: 50.0, min: 25.0, step_secs: 20.0, step_amount: 5.0), waves: [
+            (time: 1.0, archetype: Grunt, formation: Line(count: 9, spacing: 90.0)),
+        ])",
+    );
+    assert!(stage.layout_errors(Vec2::new(400.0, 400.0)).is_empty());
+    assert_eq!(stage.layout_errors(Vec2::new(300.0, 400.0)).len(), 1);
+
+    let waypoints = parse(
+        "(waves: [
+            (time: 1.0, archetype: Grunt, path: Some(Waypoints([(0.0, 0.0), (0.0, -500.0)]))),
+        ])",
+    );
+    assert_eq!(waypoints.layout_errors(SMALLEST_PLAYFIELD).len(), 1);
+}
+
+#[test]
+fn infinite_values_are_rejected() {
+    let stage = parse(
+        "(spawn_interval: (initial: inf, min: 0.5, step_secs: 10.0, step_amount: 0.1),
+          enemy_size: (initial: 50.0, min: 25.0, step_secs: 20.0, step_amount: inf))",
+    );
+    assert_eq!(
+        stage.validate(),
+        vec![
+            "spawn_interval.initial must be a positive number".to_string(),
+            "enemy_size.step_amount must be a non-negative number".to_string(),
+        ]
+    );
+}